@@ -0,0 +1,84 @@
+//! Invariants of the auction state, for property-based tests of the auction lifecycle, and a
+//! proptest strategy of bid sequences to drive them. The bidding token invariants hold for
+//! auctions escrowing whole bids, without bid bonds, lots, orders, candle windows, sealed bids
+//! or accepted currencies. Enabled by the `testutils` feature, the strategy only in the
+//! contract's own tests.
+
+use pbc_contract_common::address::Address;
+#[cfg(test)]
+use proptest::prelude::*;
+
+#[cfg(test)]
+use crate::Bid;
+use crate::{AuctionContractState, AuctionStatus};
+
+/// A transfer of bidding tokens between an address and the contract, as observed by a test.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transfer {
+    /// The contract escrowed `amount` from `from`.
+    Escrowed { from: Address, amount: u128 },
+    /// The contract paid `amount` out to `to`.
+    PaidOut { to: Address, amount: u128 },
+}
+
+/// The bidding tokens the contract owes: the claimable tokens, the deposited balances, the
+/// payment held for delivery and the escrow of a highest bid that has not been settled yet.
+/// `None` if the total overflows.
+pub fn owed_bidding_tokens(state: &AuctionContractState) -> Option<u128> {
+    let unsettled_escrow = match state.status {
+        AuctionStatus::Bidding {} | AuctionStatus::Paused {} => state.highest_bid_escrow(),
+        _ => 0,
+    };
+    state
+        .claim_map
+        .values()
+        .map(|claimable| claimable.tokens_for_bidding)
+        .chain(state.balances.values().copied())
+        .try_fold(
+            unsettled_escrow.checked_add(state.held_proceeds)?,
+            u128::checked_add,
+        )
+}
+
+/// Whether the highest bid is backed by its escrow, and the claimable tokens for sale do not
+/// exceed the tokens for sale.
+pub fn total_escrowed_consistent(state: &AuctionContractState) -> bool {
+    let claimable_sale_tokens = state
+        .claim_map
+        .values()
+        .try_fold(0u128, |total, claimable| {
+            total.checked_add(claimable.tokens_for_sale)
+        });
+    state.highest_bid_escrow() >= state.highest_bidder.amount
+        && owed_bidding_tokens(state).is_some()
+        && matches!(claimable_sale_tokens, Some(total) if total <= state.total_tokens_for_sale())
+}
+
+/// Whether the contract owes no more bidding tokens than it escrowed and did not pay out again
+/// in the transfers of `history`.
+pub fn claims_never_exceed_deposits(state: &AuctionContractState, history: &[Transfer]) -> bool {
+    let (escrowed, paid_out) = history.iter().fold(
+        (0u128, 0u128),
+        |(escrowed, paid_out), transfer| match transfer {
+            Transfer::Escrowed { amount, .. } => (escrowed.saturating_add(*amount), paid_out),
+            Transfer::PaidOut { amount, .. } => (escrowed, paid_out.saturating_add(*amount)),
+        },
+    );
+    match (owed_bidding_tokens(state), escrowed.checked_sub(paid_out)) {
+        (Some(owed), Some(held)) => owed <= held,
+        _ => false,
+    }
+}
+
+/// A proptest strategy of sequences of up to `max_length` bids from `bidders` of up to
+/// `max_amount`, which proptest shrinks to a minimal failing sequence.
+#[cfg(test)]
+pub fn bid_sequences(
+    bidders: Vec<Address>,
+    max_length: usize,
+    max_amount: u128,
+) -> impl Strategy<Value = Vec<Bid>> {
+    let bid = (proptest::sample::select(bidders), 0..=max_amount)
+        .prop_map(|(bidder, amount)| Bid { bidder, amount });
+    proptest::collection::vec(bid, 0..=max_length)
+}
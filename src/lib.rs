@@ -3,16 +3,26 @@
 #[macro_use]
 extern crate pbc_contract_codegen;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use create_type_spec_derive::CreateTypeSpec;
 use pbc_contract_common::address::{Address, AddressType, Shortname};
 use pbc_contract_common::context::{CallbackContext, ContractContext};
-use pbc_contract_common::events::EventGroup;
+use pbc_contract_common::events::{EventGroup, EventGroupBuilder};
+use pbc_contract_common::Hash;
+use pbc_traits::ReadRPC;
 use read_write_rpc_derive::{ReadRPC, WriteRPC};
 use read_write_state_derive::ReadWriteState;
+use sha2::{Digest, Sha256};
+use simulation::{bid_is_valid, split_proceeds, BidRules, ProceedsRates};
 
+#[cfg(any(test, feature = "testutils"))]
+pub mod invariants;
+pub mod simulation;
 mod tests;
+#[cfg(any(test, feature = "testutils"))]
+pub mod testutils;
+pub mod units;
 #[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec)]
 #[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
 pub struct Bid {
@@ -20,19 +30,894 @@ pub struct Bid {
     amount: u128,
 }
 
-#[derive(ReadWriteState, CreateTypeSpec)]
+#[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec)]
 #[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
 pub struct TokenClaim {
     tokens_for_bidding: u128,
     tokens_for_sale: u128,
 }
 
+type LotId = u32;
+
+/// An additional lot auctioned independently of the main sale, paid out of the same token
+/// for sale and bid on with the same bidding token.
+#[derive(ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct Lot {
+    token_amount_for_sale: u128,
+    highest_bidder: Bid,
+    reserve_price: u128,
+    min_increment: u128,
+    /// The end of the bidding on the lot, which can then be settled on its own with
+    /// `settle_lot`. Without an end time the lot ends with the auction.
+    end_time_millis: Option<i64>,
+}
+
+impl Lot {
+    /// Whether the bidding on the lot ended before the auction does.
+    fn has_ended(&self, block_production_time: i64) -> bool {
+        matches!(self.end_time_millis, Some(end) if block_production_time >= end)
+    }
+}
+
+/// A bid of `amount` on the lot `lot_id`, as part of a batch of lot bids.
+#[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct LotBid {
+    lot_id: LotId,
+    amount: u128,
+}
+
+/// A share of the proceeds of a sale, in basis points, withheld for `receiver` at settlement,
+/// such as a tax withholding or a donation to a charity.
+#[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct Withholding {
+    receiver: Address,
+    bps: u32,
+}
+
+/// A receiver of a weighted share of the seller proceeds.
+#[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct Beneficiary {
+    receiver: Address,
+    weight: u32,
+}
+
+/// Declares `AuctionError` from a single list of `code => Variant: "message"` entries, so the
+/// discriminant, the code and the message of an error are written once, next to each other.
+macro_rules! auction_errors {
+    ($($code:tt => $variant:ident: $message:literal,)*) => {
+        /// Errors the auction fails with. The panic message of a failed action starts with the
+        /// numeric error code in square brackets, so front ends can decode failures, e.g.
+        /// `[4] Tried to execute the auction before auction end block time`.
+        #[derive(ReadRPC, WriteRPC, CreateTypeSpec, Clone, Copy, PartialEq, Eq, Debug)]
+        pub enum AuctionError {
+            $(
+                #[discriminant($code)]
+                $variant {},
+            )*
+        }
+
+        impl AuctionError {
+            /// Every error, in the order of their codes.
+            #[cfg(test)]
+            pub(crate) const ALL: &'static [AuctionError] = &[$(AuctionError::$variant {},)*];
+
+            /// The numeric error code, equal to the discriminant of the error.
+            pub fn code(&self) -> u8 {
+                match self {
+                    $(AuctionError::$variant {} => $code,)*
+                }
+            }
+
+            fn message(&self) -> &'static str {
+                match self {
+                    $(AuctionError::$variant {} => $message,)*
+                }
+            }
+        }
+    };
+}
+
+auction_errors! {
+    1 => NotOwner: "Only the contract owner can perform this action",
+    2 => NotInCreation: "The action is only allowed while setting up the contract",
+    3 => NotBidding: "The action is only allowed while the auction is bidding",
+    4 => BeforeEndTime: "Tried to execute the auction before auction end block time",
+    5 => AfterEndTime: "The action is only allowed before auction end block time",
+    6 => TransferFailed: "Transfer event did not succeed",
+    7 => SaleTokenNotPublicContract: "Tried to create a contract selling a non publicContract token",
+    8 => BiddingTokenNotPublicContract: "Tried to create a contract buying a non publicContract token",
+    9 => UnknownAuctionFormat: "Tried to create a contract with an unknown auction format",
+    10 => MissingPriceSchedule: "Tried to create a Dutch auction without a price schedule",
+    11 => StartPriceBelowReserve: "Tried to create a Dutch auction starting below the reserve price",
+    12 => UnknownSettlementMode: "Tried to create a contract with an unknown settlement mode",
+    13 => UnsupportedSettlementMode: "Tried to create a Dutch auction with second price settlement",
+    14 => UnsupportedBuyout: "Tried to create a buyout price for a non English auction",
+    15 => BuyoutBelowReserve: "Tried to create a buyout price below the reserve price",
+    16 => InvalidNftAmount: "Tried to create a contract selling an NFT with an amount other than one",
+    17 => SharesExceedProceeds: "Tried to create a royalty, fee and keeper reward above 100%",
+    18 => MissingRoyaltyReceiver: "Tried to create a royalty without a royalty receiver",
+    19 => MissingFeeCollector: "Tried to create a fee without a fee collector",
+    20 => StartTimeInPast: "Tried to create a contract scheduled to start in the past",
+    21 => BidderBlocked: "Blocked addresses cannot bid",
+    22 => RaiseNotHigher: "Tried to raise the highest bid to an amount that is not higher",
+    23 => HighestBidderProxyBid: "The highest bidder can only raise their bid through bid",
+    24 => NotDutchAuction: "Accept price can only be called on a Dutch auction",
+    25 => LotsNotSupported: "Lots can only be added to an English auction of fungible tokens",
+    26 => LotAlreadyExists: "Tried to add a lot with an id that is already in use",
+    27 => CannotBlockOwner: "The contract owner cannot be blocked",
+    28 => ZeroBeneficiaryWeight: "Tried to set a beneficiary without a weight",
+    29 => ArithmeticOverflow: "An amount exceeded the supported range",
+    30 => NotFinished: "The action is only allowed after the auction has ended, failed or been cancelled",
+    31 => ClaimDeadlineBeforeEnd: "Tried to create a claim deadline before the auction end time",
+    32 => ClaimDeadlineNotReached: "Tried to sweep unclaimed tokens before the claim deadline",
+    33 => UnsupportedHiddenReserve: "Tried to create a hidden reserve price for a non English auction",
+    34 => NoHiddenReserve: "The auction has no hidden reserve price to reveal",
+    35 => InvalidReserveReveal: "The revealed reserve price does not match the commitment",
+    36 => ReserveNotRevealed: "Tried to execute the auction before the hidden reserve price was revealed",
+    37 => UnsupportedCandle: "Tried to create a candle auction that is not a plain first price English auction",
+    38 => InvalidCandleWindow: "Tried to create a candle window that is empty or longer than the auction",
+    39 => NoCandle: "The auction has no candle seed to reveal",
+    40 => InvalidCandleReveal: "The revealed candle seed does not match the commitment",
+    41 => CandleNotRevealed: "Tried to execute the auction before the candle seed was revealed",
+    42 => NotMultiUnitAuction: "Tried to place an order outside a multi-unit auction",
+    43 => NotPendingOwner: "Tried to accept ownership without being the proposed owner",
+    44 => NotPaused: "Tried to resume an auction that is not paused",
+    45 => ExtensionExceedsMaximum: "Tried to extend the auction beyond the maximum total extension",
+    46 => CandleWindowOpen: "Tried to extend a candle auction after its candle window opened",
+    47 => BidAlreadyPlaced: "Tried to update the terms of the auction after a bid was placed",
+    48 => UnsupportedRelist: "Tried to create a relisting candle or multi-unit auction",
+    49 => InvalidReserveDecrease: "Tried to decrease the reserve price by more than the whole reserve price",
+    50 => OwnerCannotBid: "The contract owner cannot bid on the auction",
+    51 => NotEnglishAuction: "Tried to bid outside an English auction",
+    52 => BidTooLow: "Tried to bid below the reserve price or the minimum increment",
+    53 => InvalidDuration: "Tried to create an auction shorter than a minute or longer than a year",
+    54 => BidBondUnsupported: "The action is not supported by bid bond auctions",
+    55 => InvalidBidBond: "Tried to create a bid bond auction that is not a plain first price English auction",
+    56 => NotSettling: "The action is only allowed while the winner completes the payment",
+    57 => NotWinner: "Only the winner of the auction can complete the payment",
+    58 => PaymentDeadlinePassed: "Tried to complete the payment after the payment deadline",
+    59 => PaymentPending: "Tried to pass over the winner before the payment deadline",
+    60 => InsufficientBalance: "Tried to withdraw more than the deposited balance",
+    61 => DepositBeforeStart: "Tried to deposit before the auction was started",
+    62 => RetractionDisabled: "The auction does not allow retracting bids",
+    63 => InvalidRetractionPenalty: "Tried to create a retraction penalty above 100%",
+    64 => NoBidToRetract: "Tried to retract without a bid in the auction",
+    65 => NotVerified: "Only addresses verified by the KYC registry can bid",
+    66 => NoKycRegistry: "The auction has no KYC registry to verify with",
+    67 => ClaimsOutstanding: "Tried to finalize the auction before every claim and balance was paid out",
+    68 => AuctionFinalized: "The auction has been finalized",
+    69 => InvalidDecayCurve: "Tried to create a price decay with an empty interval or a decay above 100%",
+    70 => AllPayUnsupported: "The action is not supported by all-pay auctions",
+    71 => PennyBidRequired: "Bids on a penny auction can only be placed through penny_bid",
+    72 => NotPennyAuction: "Tried to place a penny bid outside a penny auction",
+    73 => InvalidPennyAuction: "Tried to create a penny auction that is not a plain first price English auction",
+    74 => SealedBidRequired: "Bids on a sealed bid auction can only be committed through commit_bid",
+    75 => InvalidSealedAuction: "Tried to create a sealed bid auction that is not a plain first price English auction",
+    76 => AlreadyCommitted: "Tried to commit to a second sealed bid",
+    77 => NotInRevealWindow: "The action is only allowed in the reveal window after auction end block time",
+    78 => NoCommitment: "Tried to reveal a bid without committing to one",
+    79 => InvalidBidReveal: "The revealed bid does not match the commitment",
+    80 => BidsNotRevealed: "Tried to execute the auction before the reveal window of the sealed bids closed",
+    81 => InvalidCurrencies: "Tried to accept bidding currencies that are invalid for the auction",
+    82 => CurrencyNotAccepted: "The currency is not accepted for bidding",
+    83 => RateUnavailable: "The exchange rate of the currency has not been fetched from its oracle",
+    84 => MultiCurrencyUnsupported: "The action is not supported by multi-currency auctions",
+    85 => InvalidDeliveryEscrow: "Tried to hold the payment for delivery in an auction that does not support it",
+    86 => NoHeldPayment: "There is no payment held for delivery",
+    87 => NotBuyer: "Only the winner of the auction can confirm the receipt of the goods",
+    88 => DeliveryTimeoutPending: "Tried to release the held payment before the delivery timeout",
+    89 => InvalidArbiter: "Tried to appoint an arbiter without holding the payment for delivery",
+    90 => NoArbiter: "The auction has no arbiter",
+    91 => DisputeWindowClosed: "Disputes can only be opened before the delivery timeout",
+    92 => DisputeOpen: "The held payment is in dispute",
+    93 => NotArbiter: "Only the arbiter can resolve a dispute",
+    94 => NoDispute: "There is no open dispute",
+    95 => InvalidAward: "The award exceeds the held payment",
+    96 => NotDisputeParty: "Only the winner or the seller can open a dispute",
+    97 => CannotCoverBid: "The bidding token allowance or balance of the bidder does not cover the bid",
+    98 => MissingTokenId: "Tried to sell a semi-fungible token without a token id",
+    99 => InvalidBundle: "Tried to sell a bundle in an auction without a single winner",
+    100 => InvalidRounds: "Tried to configure rounds that are invalid for the auction",
+    101 => InvalidRecurrence: "Tried to configure a recurrence that is invalid for the auction",
+    102 => InvalidReferrer: "Bidders cannot refer themselves",
+    103 => InvalidBidCooldown: "Tried to configure a non-positive bid cooldown",
+    104 => BidCooldown: "The bidder has to wait for the bid cooldown to bid again",
+    105 => InvalidTiePolicy: "Tried to split ties in an auction whose sale cannot be split",
+    106 => InvalidInstantWin: "Tried to configure an instant win multiple that is invalid for the auction",
+    107 => NotPayoutParty: "Only the winner or the seller can register a payout recipient",
+    108 => NotDelegate: "Only the delegate of a bidder can bid on their behalf",
+    109 => InvalidSigners: "Tried to configure duplicate signers or a threshold the signers cannot reach",
+    110 => NotSigner: "Only a signer of the owner can approve an owner action",
+    111 => ApprovalsMissing: "Tried to perform an owner action without the approvals of enough signers",
+    112 => InvalidTimelock: "Tried to configure a negative timelock",
+    113 => ActionNotProposed: "Tried to perform an owner action under a timelock without proposing it",
+    114 => TimelockPending: "Tried to perform an owner action before its timelock elapsed",
+    115 => InvalidRecoveryPeriod: "Tried to configure a non-positive recovery period",
+    116 => RecoveryUnavailable: "Tried to recover the auction before its recovery period elapsed",
+    117 => NotParticipant: "Only a participant of the auction can recover it",
+    118 => BiddingBooksUnbalanced: "The bidding tokens owed do not match the tracked bidding token escrow",
+    119 => SaleBooksUnbalanced: "The claimable tokens for sale exceed the tracked escrow of tokens for sale",
+    120 => InvalidReserveOracle: "Tried to configure a reserve oracle for an auction without a public reserve",
+    121 => ReserveLocked: "Tried to refresh the reserve price without an oracle or after a bid was placed",
+    122 => ZeroSaleAmount: "Tried to create an auction of zero tokens",
+    123 => SameTokens: "Tried to create an auction selling the bidding token without allowing it",
+    124 => ZeroIncrement: "Tried to create an English auction without a minimum increment",
+    125 => StartTimeTooFar: "Tried to create a contract scheduled to start more than a year from now",
+    126 => EndBeforeStart: "Tried to create an auction ending before it starts",
+    127 => NotMember: "Only holders of the membership token can bid",
+    128 => NoMembershipGate: "The auction has no membership token to verify with",
+    129 => EmptyBatch: "Tried to bid on no lots",
+    130 => InvalidLotEndTime: "Tried to add a lot ending outside of the auction",
+    131 => LotNotEnded: "Tried to settle a lot that is unknown or has not ended yet",
+    132 => CancelAfterBids: "Tried to cancel an auction that cannot be cancelled once a bid was accepted",
+    133 => InvalidSetupWindow: "Tried to create a contract with a setup window that is not positive",
+    134 => SetupExpired: "The setup window of the auction has passed",
+    135 => SetupNotExpired: "Tried to expire an auction that was started or is within its setup window",
+    136 => NotPendingStart: "The action is only allowed while the start of the auction is pending",
+    137 => StartNotFailed: "Tried to reset a start whose escrow of the tokens for sale has not failed",
+}
+
+/// Abort the action with the given error.
+fn fail(error: AuctionError) -> ! {
+    panic!("[{}] {}", error.code(), error.message())
+}
+
+/// Add two token amounts, failing instead of wrapping around on overflow.
+fn add_amounts(amount: u128, additional: u128) -> u128 {
+    amount
+        .checked_add(additional)
+        .unwrap_or_else(|| fail(AuctionError::ArithmeticOverflow {}))
+}
+
+/// `amount * numerator / denominator` rounded down, without overflowing in the intermediate
+/// product. Saturates at `u128::MAX` if the result itself does not fit.
+fn mul_div(amount: u128, numerator: u128, denominator: u128) -> u128 {
+    (amount / denominator)
+        .saturating_mul(numerator)
+        .saturating_add(amount % denominator * numerator / denominator)
+}
+
+/// The fixed point scale of exponential price decay factors.
+const DECAY_SCALE: u128 = 1_000_000_000_000_000_000;
+
+/// The factor, scaled by `DECAY_SCALE`, left after decaying by `decay_bps` `intervals` times.
+fn decay_factor(decay_bps: u32, intervals: u128) -> u128 {
+    let mut base = mul_div(
+        DECAY_SCALE,
+        u128::from(BASIS_POINTS - decay_bps),
+        u128::from(BASIS_POINTS),
+    );
+    let mut factor = DECAY_SCALE;
+    let mut remaining = intervals;
+    // Both factors are at most DECAY_SCALE, so their product fits
+    while remaining > 0 && factor > 0 {
+        if remaining & 1 == 1 {
+            factor = factor * base / DECAY_SCALE;
+        }
+        base = base * base / DECAY_SCALE;
+        remaining >>= 1;
+    }
+    factor
+}
+
+/// Lifecycle of the auction.
+#[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AuctionStatus {
+    /// The contract is set up, but the tokens for sale have not been escrowed yet.
+    #[discriminant(0)]
+    Creation {},
+    /// The auction accepts bids.
+    #[discriminant(1)]
+    Bidding {},
+    /// The auction has been settled.
+    #[discriminant(2)]
+    Ended {},
+    /// The auction was cancelled by the owner.
+    #[discriminant(3)]
+    Cancelled {},
+    /// The tokens are escrowed, and bidding opens at the start time.
+    #[discriminant(4)]
+    BiddingScheduled {},
+    /// Too few bidders participated, and the auction ended without a sale.
+    #[discriminant(5)]
+    Failed {},
+    /// Bidding was paused by the owner, and incoming bids are refunded.
+    #[discriminant(6)]
+    Paused {},
+    /// The winner of a bid bond auction has to pay the rest of the winning bid.
+    #[discriminant(7)]
+    Settling {},
+    /// Every claim was paid out and the residual state was cleared.
+    #[discriminant(8)]
+    Finalized {},
+    /// The sealed bids are being revealed after the end time.
+    #[discriminant(9)]
+    Revealing {},
+    /// The auction was not started within its setup window, and never will be.
+    #[discriminant(10)]
+    Expired {},
+    /// `start` requested the escrow of the tokens for sale, and awaits the transfer.
+    #[discriminant(11)]
+    PendingStart {},
+    /// The auction ended without a bid meeting the reserve price, and the tokens for sale
+    /// returned to the owner.
+    #[discriminant(12)]
+    ReserveNotMet {},
+}
+
+pub type AuctionFormat = u8;
+pub const ENGLISH: AuctionFormat = 0;
+pub const DUTCH: AuctionFormat = 1;
+pub const UNIFORM_PRICE: AuctionFormat = 2;
+pub const PAY_AS_BID: AuctionFormat = 3;
+/// Units are sold at the reserve price to the orders in the order they arrive, partially
+/// filling the order that exceeds the remaining units.
+pub const FIXED_PRICE: AuctionFormat = 4;
+
+pub type SettlementMode = u8;
+pub const FIRST_PRICE: SettlementMode = 0;
+pub const SECOND_PRICE: SettlementMode = 1;
+/// Every bid is paid to the owner, not only the winning bid. Outbid bidders are not refunded.
+pub const ALL_PAY: SettlementMode = 2;
+
+const MILLIS_PER_HOUR: i64 = 60 * 60 * 1000;
+/// The bounds of the duration of an auction, one minute and one year.
+const MIN_DURATION_MILLIS: i64 = 60 * 1000;
+const MAX_DURATION_MILLIS: i64 = 365 * 24 * MILLIS_PER_HOUR;
+/// How far ahead an auction can be scheduled to start.
+const MAX_START_DELAY_MILLIS: i64 = 365 * 24 * MILLIS_PER_HOUR;
+const BASIS_POINTS: u32 = 10_000;
+/// How long the owner has after the end time to reveal a hidden reserve price or candle seed.
+const REVEAL_WINDOW_MILLIS: i64 = 24 * MILLIS_PER_HOUR;
+/// The maximum number of refunds pushed by a single `refund_all` call.
+const REFUND_BATCH_SIZE: usize = 50;
+/// Exchange rates are the value of `RATE_SCALE` units of a currency in the bidding token.
+const RATE_SCALE: u128 = 1_000_000_000_000_000_000;
+/// The version of the `AuctionSnapshot` layout, raised whenever the layout changes.
+const SNAPSHOT_VERSION: u32 = 1;
+/// The number of bidders ranked on the leaderboard.
+const LEADERBOARD_SIZE: usize = 10;
+
+/// Descending price schedule of a Dutch auction. The price starts at `start_price` and
+/// decreases along `curve` until it reaches the reserve price, which is the floor price.
+#[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct PriceSchedule {
+    start_price: u128,
+    decrement_per_hour: u128,
+    curve: DecayCurve,
+}
+
+/// How the price of a Dutch auction decays over time.
+#[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub enum DecayCurve {
+    /// The price decreases continuously by `decrement_per_hour`.
+    #[discriminant(0)]
+    Linear {},
+    /// The price decreases by `decrement_per_hour` in steps at the end of every interval.
+    #[discriminant(1)]
+    Stepped { interval_millis: i64 },
+    /// The price decreases by `decay_bps` of the previous price at the end of every interval.
+    /// The `decrement_per_hour` is not used.
+    #[discriminant(2)]
+    Exponential {
+        interval_millis: i64,
+        decay_bps: u32,
+    },
+}
+
+impl PriceSchedule {
+    /// The price asked `elapsed_millis` after the start of the auction, never below
+    /// `floor_price`.
+    fn price_at(&self, elapsed_millis: i64, floor_price: u128) -> u128 {
+        let elapsed_millis = elapsed_millis.max(0) as u128;
+        let linear_price = |elapsed_millis: u128| {
+            let decrease = mul_div(
+                self.decrement_per_hour,
+                elapsed_millis,
+                MILLIS_PER_HOUR as u128,
+            );
+            self.start_price.saturating_sub(decrease)
+        };
+        let price = match self.curve {
+            DecayCurve::Linear {} => linear_price(elapsed_millis),
+            DecayCurve::Stepped { interval_millis } => {
+                let interval_millis = interval_millis as u128;
+                linear_price(elapsed_millis - elapsed_millis % interval_millis)
+            }
+            DecayCurve::Exponential {
+                interval_millis,
+                decay_bps,
+            } => {
+                let intervals = elapsed_millis / interval_millis as u128;
+                mul_div(
+                    self.start_price,
+                    decay_factor(decay_bps, intervals),
+                    DECAY_SCALE,
+                )
+            }
+        };
+        price.max(floor_price)
+    }
+}
+
+/// Configuration of a candle auction. The effective end of the auction is a cutoff within the
+/// closing window before the end time, derived from a seed the owner commits to up front by its
+/// SHA-256 hash and reveals after the end time.
+#[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct CandleConfig {
+    window_millis: i64,
+    seed_commitment: Hash,
+}
+
+/// The invocation shortnames of a token contract that does not use the standard MPC-20 or
+/// MPC-721 shortnames for its `transfer` and `transfer_from` actions.
+#[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct TokenInterface {
+    transfer_shortname: u32,
+    transfer_from_shortname: u32,
+}
+
+impl TokenInterface {
+    fn transfer(&self) -> Shortname {
+        Shortname::from_u32(self.transfer_shortname)
+    }
+
+    fn transfer_from(&self) -> Shortname {
+        Shortname::from_u32(self.transfer_from_shortname)
+    }
+}
+
+/// A membership token, such as an MPC-721 NFT, bidders must hold. `balance_shortname` is the
+/// shortname of the token action taking an address and returning the number of tokens it holds.
+#[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct MembershipGate {
+    address: Address,
+    balance_shortname: u32,
+}
+
+/// An identity registry contract verifying bidders. `is_verified_shortname` is the shortname
+/// of the registry action taking an address and returning whether it is verified.
+#[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct KycRegistry {
+    address: Address,
+    is_verified_shortname: u32,
+}
+
+/// A token accepted for bidding besides the bidding token, valued in the bidding token at
+/// `rate`.
+#[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct AcceptedCurrency {
+    token: Address,
+    rate: ExchangeRate,
+}
+
+/// An oracle the reserve price is converted from: the reserve is `reference_reserve` units of
+/// a reference unit, such as a fiat currency, at the rate returned by `rate_shortname`, in
+/// units of the bidding token per `RATE_SCALE` reference units. The minimum increment is
+/// `reference_increment` reference units at the same rate, if set.
+#[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct ReserveOracle {
+    address: Address,
+    rate_shortname: u32,
+    reference_reserve: u128,
+    reference_increment: Option<u128>,
+}
+
+/// The exchange rate of an accepted currency, in units of the bidding token per `RATE_SCALE`
+/// units of the currency.
+#[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub enum ExchangeRate {
+    /// A rate fixed by the owner.
+    #[discriminant(0)]
+    Fixed { rate: u128 },
+    /// A rate fetched by `refresh_rate` from the `rate_shortname` action of an oracle contract,
+    /// taking the address of the currency and returning its rate.
+    #[discriminant(1)]
+    Oracle {
+        address: Address,
+        rate_shortname: u32,
+    },
+}
+
+/// The escrow of a highest bid placed in an accepted currency instead of the bidding token.
+#[derive(ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct CurrencyEscrow {
+    token: Address,
+    amount: u128,
+}
+
+/// An item sold in a bundle with the token for sale, an amount of an MPC-20 token or the token
+/// id of an MPC-721 token.
+#[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct BundleItem {
+    token: Address,
+    amount_or_id: u128,
+}
+
+/// Relist the same sale after every settlement, up to `max_repeats` times. Each repeat starts
+/// `interval_millis` after the start of the previous one and sells another tranche of
+/// `token_amount_for_sale` from the inventory escrowed in `start`.
+#[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct RecurrenceConfig {
+    interval_millis: i64,
+    max_repeats: u32,
+}
+
+/// A round of a sequential auction, selling another tranche of the token for sale once the
+/// previous round ended.
+#[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct RoundConfig {
+    token_amount_for_sale: u128,
+    reserve_price: u128,
+    duration_millis: i64,
+}
+
+/// The outcome of a round of a sequential auction that ended with a sale.
+#[derive(ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct RoundResult {
+    round: u32,
+    winner: Address,
+    price: u128,
+    units: u128,
+}
+
+/// Penny auction mode of an English auction. Every bid pays the non-refundable `bid_fee` to
+/// the owner, raises the price by `price_increment`, and keeps the auction open for at least
+/// `extension_millis` more. The last bidder wins at the final price.
+#[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct PennyConfig {
+    bid_fee: u128,
+    price_increment: u128,
+    extension_millis: i64,
+}
+
+/// Bid bond mode of an English auction. Bidders only escrow `bond` when bidding, and the
+/// winner pays the rest of the winning bid within `payment_window_millis` after `execute`.
+#[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct BidBondConfig {
+    bond: u128,
+    payment_window_millis: i64,
+}
+
+/// Restart the bidding when the auction ends without a bid meeting the reserve price, up to
+/// `max_relists` times. Each new round lowers the reserve price by `reserve_decrease_bps`.
+#[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct RelistConfig {
+    max_relists: u32,
+    reserve_decrease_bps: u32,
+}
+
+/// A bid placed in the closing window of a candle auction, escrowed until the cutoff is known.
+#[derive(ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct CandleBid {
+    bidder: Address,
+    amount: u128,
+    timestamp_millis: i64,
+}
+
+/// An order in a multi-unit auction, for up to `quantity` units at up to `unit_price` each.
+#[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct Order {
+    bidder: Address,
+    quantity: u128,
+    unit_price: u128,
+}
+
+impl Order {
+    /// The bidding tokens escrowed for the order.
+    fn escrow(&self) -> u128 {
+        self.quantity
+            .checked_mul(self.unit_price)
+            .unwrap_or_else(|| fail(AuctionError::ArithmeticOverflow {}))
+    }
+}
+
+/// How a bid equal to the highest bid is resolved, which can only happen without a minimum
+/// increment. Bids are ordered by their sequence number in the bid count.
+#[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TiePolicy {
+    /// The highest bid received first wins, and the tying bid is refunded.
+    #[discriminant(0)]
+    FirstReceived {},
+    /// The tying bid received last becomes the highest bid.
+    #[discriminant(1)]
+    LastReceived {},
+    /// The tying bidders split the tokens for sale and the price equally.
+    #[discriminant(2)]
+    Split {},
+}
+
+/// An accepted bid in the bid history. `lot_id` is set for bids on a lot.
+#[derive(ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct BidRecord {
+    sequence: u64,
+    bidder: Address,
+    amount: u128,
+    timestamp_millis: i64,
+    lot_id: Option<LotId>,
+}
+
+/// Why a bid was rejected and refunded.
+#[derive(ReadWriteState, CreateTypeSpec, Clone, Copy)]
+#[cfg_attr(test, derive(PartialEq, Eq, Debug))]
+pub enum RejectionReason {
+    /// The auction did not take bids of this kind, or was not open for bidding.
+    #[discriminant(0)]
+    Closed {},
+    #[discriminant(1)]
+    Ended {},
+    #[discriminant(2)]
+    BelowReserve {},
+    /// The bid did not outbid the highest bid by the minimum increment.
+    #[discriminant(3)]
+    TooLow {},
+    #[discriminant(4)]
+    Blocked {},
+    #[discriminant(5)]
+    Cooldown {},
+    /// The bidder was outbid before their top up or revealed bid arrived.
+    #[discriminant(6)]
+    Outbid {},
+    #[discriminant(7)]
+    UnknownLot {},
+    /// The order was for no units, or for more units than are for sale.
+    #[discriminant(8)]
+    InvalidOrder {},
+    /// Fewer bidding tokens arrived than the bid, order or deposit escrows.
+    #[discriminant(9)]
+    Underpaid {},
+    /// The bidder already committed to a sealed bid.
+    #[discriminant(10)]
+    AlreadyCommitted {},
+    /// The bidder already escrowed a bond.
+    #[discriminant(11)]
+    AlreadyBonded {},
+}
+
+/// The last rejected bid of a bidder, whose escrow was refunded.
+#[derive(ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct RejectedBid {
+    amount: u128,
+    reason: RejectionReason,
+    timestamp_millis: i64,
+}
+
+/// The highest accepted bid of a bidder on the leaderboard.
+#[derive(ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct LeaderboardEntry {
+    bidder: Address,
+    amount: u128,
+    timestamp_millis: i64,
+}
+
+/// Statistics of the bids placed by a bidder.
+#[derive(ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct BidderStats {
+    bid_count: u32,
+    total_escrowed: u128,
+    highest_bid: u128,
+    last_bid_millis: i64,
+}
+
+/// A claim payout whose outgoing transfer failed, waiting to be retried.
+#[derive(ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct FailedPayout {
+    /// The amounts of the last failed payout, which were restored to the claim map.
+    failed: TokenClaim,
+    attempts: u32,
+    last_attempt_millis: i64,
+}
+
+/// A sensitive owner action, which requires the approvals of the signers of the owner once
+/// signers are configured. The approval of an action covers its exact arguments.
+#[derive(ReadRPC, WriteRPC, ReadWriteState, CreateTypeSpec, PartialEq, Eq)]
+#[cfg_attr(test, derive(Clone, Debug))]
+pub enum OwnerAction {
+    #[discriminant(0)]
+    Cancel {},
+    #[discriminant(1)]
+    SweepUnclaimed {},
+    #[discriminant(2)]
+    UpdateTerms {
+        reserve_price: u128,
+        min_increment: u128,
+    },
+}
+
+/// The signers who approved an owner action that has not been performed yet.
+#[derive(ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct ActionApproval {
+    action: OwnerAction,
+    approvers: Vec<Address>,
+}
+
+/// An owner action proposed under a timelock, which the owner can perform from
+/// `executable_at_millis` on.
+#[derive(ReadWriteState, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct PendingAction {
+    action: OwnerAction,
+    executable_at_millis: i64,
+}
+
+/// Notification sent to the observer contract on significant transitions of the auction.
+#[derive(ReadRPC, WriteRPC, CreateTypeSpec, Clone, Copy)]
+#[cfg_attr(test, derive(PartialEq, Eq, Debug))]
+pub enum AuctionNotification {
+    #[discriminant(0)]
+    Started {},
+    #[discriminant(1)]
+    NewHighestBid { bidder: Address, amount: u128 },
+    #[discriminant(2)]
+    Executed { winner: Address, price: u128 },
+    #[discriminant(3)]
+    Cancelled {},
+    #[discriminant(4)]
+    Failed {},
+    #[discriminant(5)]
+    Cleared { price: u128, units_sold: u128 },
+    #[discriminant(6)]
+    Paused {},
+    #[discriminant(7)]
+    Resumed { end_time_millis: i64 },
+    #[discriminant(8)]
+    Relisted {
+        end_time_millis: i64,
+        reserve_price: u128,
+    },
+    #[discriminant(9)]
+    BidRetracted { bidder: Address, penalty: u128 },
+    #[discriminant(10)]
+    RoundStarted { round: u32, end_time_millis: i64 },
+    #[discriminant(11)]
+    ReserveNotMet {},
+}
+
+/// Outcome of a bid, returned as return data of `bid_callback` so the bidder learns whether
+/// the bid became the highest bid, was accepted but outbid, or was refunded, without
+/// inspecting the claim map.
+#[derive(ReadRPC, WriteRPC, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub enum BidOutcome {
+    #[discriminant(0)]
+    HighestBid { amount: u128 },
+    #[discriminant(1)]
+    Outbid { highest_bid: u128 },
+    #[discriminant(2)]
+    Refunded { amount: u128 },
+}
+
+/// Compact snapshot of the auction for off-chain indexers. Its layout is versioned by
+/// `version` and kept independent of the internal state layout.
+#[derive(ReadRPC, WriteRPC, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct AuctionSnapshot {
+    version: u32,
+    status: AuctionStatus,
+    highest_bidder: Address,
+    highest_bid: u128,
+    end_time_millis: i64,
+    bid_count: u64,
+    bidder_count: u32,
+    claimable_bidding_tokens: u128,
+    claimable_sale_tokens: u128,
+}
+
+/// Overview of the auction for wallets, without the claim map.
+#[derive(ReadRPC, WriteRPC, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct AuctionSummary {
+    highest_bid: u128,
+    reserve_price: u128,
+    min_increment: u128,
+    time_remaining_millis: i64,
+    status: AuctionStatus,
+    /// The decimals of the bidding token the amounts are in, if known.
+    bidding_token_decimals: Option<u8>,
+}
 
-type ContractStatus = u8;
-const CREATION: ContractStatus = 0;
-const BIDDING: ContractStatus = 1;
-const ENDED: ContractStatus = 2;
-const CANCELLED: ContractStatus = 3;
+/// The parameters of an auction, as accepted by `initialize` and `configure`.
+#[derive(ReadRPC, WriteRPC, CreateTypeSpec)]
+#[cfg_attr(test, derive(PartialEq, Eq, Clone, Debug))]
+pub struct AuctionConfig {
+    token_amount_for_sale: u128,
+    token_for_sale: Address,
+    token_for_bidding: Address,
+    reserve_price: u128,
+    min_increment: u128,
+    auction_duration_millis: i64,
+    auction_format: AuctionFormat,
+    price_schedule: Option<PriceSchedule>,
+    settlement_mode: SettlementMode,
+    buyout_price: Option<u128>,
+    sale_token_id: Option<u128>,
+    start_time_millis: Option<i64>,
+    royalty_receiver: Option<Address>,
+    royalty_bps: u32,
+    fee_collector: Option<Address>,
+    fee_bps: u32,
+    claim_deadline_millis: Option<i64>,
+    sweep_sink: Option<Address>,
+    max_bid_history: u32,
+    observer: Option<Address>,
+    min_unique_bidders: u32,
+    reserve_commitment: Option<Hash>,
+    candle: Option<CandleConfig>,
+    max_extension_hours: u32,
+    relist: Option<RelistConfig>,
+    title: Option<String>,
+    description: Option<String>,
+    metadata_uri: Option<String>,
+    sale_token_interface: Option<TokenInterface>,
+    bidding_token_interface: Option<TokenInterface>,
+    bidding_balance_shortname: Option<u32>,
+    allow_owner_bids: bool,
+    keeper_reward_bps: u32,
+    bid_bond: Option<BidBondConfig>,
+    retraction_penalty_bps: Option<u32>,
+    max_escrow_per_bidder: Option<u128>,
+    kyc_registry: Option<KycRegistry>,
+    penny: Option<PennyConfig>,
+    sealed_bid_deposit: Option<u128>,
+    accepted_currencies: Vec<AcceptedCurrency>,
+    delivery_timeout_millis: Option<i64>,
+    arbiter: Option<Address>,
+    bidding_allowance_shortname: Option<u32>,
+    strict_refunds: bool,
+    semi_fungible_sale: bool,
+    bundle: Vec<BundleItem>,
+    rounds: Vec<RoundConfig>,
+    recurrence: Option<RecurrenceConfig>,
+    referral_bps: u32,
+    bid_cooldown_millis: Option<i64>,
+    tie_policy: TiePolicy,
+    instant_win_multiple: Option<u32>,
+    recovery_period_millis: Option<i64>,
+    reserve_oracle: Option<ReserveOracle>,
+    /// Allow selling the bidding token itself, such as in a token swap by auction.
+    allow_same_token: bool,
+    /// The end time of the auction, instead of ending `auction_duration_millis` after its start.
+    end_time_millis: Option<i64>,
+    membership_gate: Option<MembershipGate>,
+    withholdings: Vec<Withholding>,
+    /// The shortname of the action of both tokens returning their number of decimals, which
+    /// is queried at `start`.
+    decimals_shortname: Option<u32>,
+    /// Whether the owner can still cancel the auction once a bid was accepted.
+    cancellable_after_bids: bool,
+    /// How long after `initialize` the owner has to start the auction, before anyone can
+    /// expire it with `expire`.
+    setup_window_millis: Option<i64>,
+}
 
 /// Token contract actions
 #[inline]
@@ -44,6 +929,35 @@ fn token_contract_transfer() -> Shortname {
 fn token_contract_transfer_from() -> Shortname {
     Shortname::from_u32(0x03)
 }
+
+/// MPC-721 contract actions
+#[inline]
+fn nft_contract_transfer() -> Shortname {
+    Shortname::from_u32(0x01)
+}
+
+#[inline]
+fn nft_contract_transfer_from() -> Shortname {
+    Shortname::from_u32(0x03)
+}
+
+/// MPC-1155 style semi-fungible token contract actions
+#[inline]
+fn multi_token_contract_transfer() -> Shortname {
+    Shortname::from_u32(0x01)
+}
+
+#[inline]
+fn multi_token_contract_transfer_from() -> Shortname {
+    Shortname::from_u32(0x03)
+}
+
+/// Observer contract actions
+#[inline]
+fn observer_notify() -> Shortname {
+    Shortname::from_u32(0x01)
+}
+
 #[state]
 #[cfg_attr(test, derive(Clone, PartialEq, Eq, Debug))]
 pub struct AuctionContractState {
@@ -54,10 +968,152 @@ pub struct AuctionContractState {
     token_for_sale: Address,
     token_for_bidding: Address,
     highest_bidder: Bid,
+    highest_bidder_max: Option<u128>,
+    second_highest_bidder: Bid,
     reserve_price: u128,
     min_increment: u128,
     claim_map: BTreeMap<Address, TokenClaim>,
-    status: ContractStatus,
+    status: AuctionStatus,
+    auction_format: AuctionFormat,
+    price_schedule: Option<PriceSchedule>,
+    settlement_mode: SettlementMode,
+    buyout_price: Option<u128>,
+    sale_token_id: Option<u128>,
+    lots: BTreeMap<LotId, Lot>,
+    blocklist: BTreeSet<Address>,
+    royalty_receiver: Option<Address>,
+    royalty_bps: u32,
+    fee_collector: Option<Address>,
+    fee_bps: u32,
+    beneficiaries: Vec<Beneficiary>,
+    claim_deadline_millis: Option<i64>,
+    sweep_sink: Option<Address>,
+    bid_history: Vec<BidRecord>,
+    max_bid_history: u32,
+    bid_count: u64,
+    observer: Option<Address>,
+    participants: BTreeSet<Address>,
+    /// The highest accepted bids of the top bidders, highest first, ties in order of bidding.
+    leaderboard: Vec<LeaderboardEntry>,
+    bidder_stats: BTreeMap<Address, BidderStats>,
+    min_unique_bidders: u32,
+    reserve_commitment: Option<Hash>,
+    candle: Option<CandleConfig>,
+    candle_bids: Vec<CandleBid>,
+    candle_cutoff_millis: Option<i64>,
+    orders: Vec<Order>,
+    pending_owner: Option<Address>,
+    paused_at_millis: Option<i64>,
+    max_extension_millis: i64,
+    total_extension_millis: i64,
+    relist: Option<RelistConfig>,
+    relist_count: u32,
+    round_duration_millis: i64,
+    title: Option<String>,
+    description: Option<String>,
+    metadata_uri: Option<String>,
+    sale_token_interface: Option<TokenInterface>,
+    bidding_token_interface: Option<TokenInterface>,
+    bidding_balance_shortname: Option<u32>,
+    allow_owner_bids: bool,
+    keeper_reward_bps: u32,
+    settled_by: Option<Address>,
+    bid_bond: Option<BidBondConfig>,
+    bonded_bids: BTreeMap<Address, u128>,
+    payment_deadline_millis: Option<i64>,
+    balances: BTreeMap<Address, u128>,
+    units_sold: u128,
+    retraction_penalty_bps: Option<u32>,
+    max_escrow_per_bidder: Option<u128>,
+    kyc_registry: Option<KycRegistry>,
+    verified_bidders: BTreeSet<Address>,
+    all_pay_bids: BTreeMap<Address, u128>,
+    penny: Option<PennyConfig>,
+    sealed_bid_deposit: Option<u128>,
+    sealed_commitments: BTreeMap<Address, Hash>,
+    accepted_currencies: Vec<AcceptedCurrency>,
+    oracle_rates: BTreeMap<Address, u128>,
+    highest_bid_currency: Option<CurrencyEscrow>,
+    currency_claims: BTreeMap<Address, BTreeMap<Address, u128>>,
+    delivery_timeout_millis: Option<i64>,
+    held_proceeds: u128,
+    arbiter: Option<Address>,
+    disputed: bool,
+    bidding_allowance_shortname: Option<u32>,
+    strict_refunds: bool,
+    semi_fungible_sale: bool,
+    bundle: Vec<BundleItem>,
+    bundle_claimant: Option<Address>,
+    rounds: Vec<RoundConfig>,
+    round_index: u32,
+    round_results: Vec<RoundResult>,
+    recurrence: Option<RecurrenceConfig>,
+    repeat_count: u32,
+    referral_bps: u32,
+    referrers: BTreeMap<Address, Address>,
+    bid_cooldown_millis: Option<i64>,
+    tie_policy: TiePolicy,
+    /// The bids tying with the highest bid under the split tie policy, in the order received.
+    tied_bids: Vec<BidRecord>,
+    /// A highest bid of this multiple of the reserve price ends the auction right away.
+    instant_win_multiple: Option<u32>,
+    /// The claimants whose last payout failed, until a payout to them succeeds.
+    failed_payouts: BTreeMap<Address, FailedPayout>,
+    /// The addresses the claims of the winner or the seller are paid out to instead.
+    payout_recipients: BTreeMap<Address, Address>,
+    /// The delegate authorized to bid on behalf of each delegating bidder.
+    bidding_delegates: BTreeMap<Address, Address>,
+    /// The address credited with the owner's share of the proceeds instead of the owner.
+    proceeds_receiver: Option<Address>,
+    /// The signers of the owner, of whom `approval_threshold` approve every sensitive owner
+    /// action. No signers leaves the owner in sole control.
+    owner_signers: Vec<Address>,
+    approval_threshold: u32,
+    action_approvals: Vec<ActionApproval>,
+    /// The delay between proposing a sensitive owner action and performing it, so bidders can
+    /// react. Zero lets the owner act right away.
+    timelock_millis: i64,
+    pending_actions: Vec<PendingAction>,
+    /// How long after the end time any participant can recover an auction left unsettled.
+    recovery_period_millis: Option<i64>,
+    /// The bidding tokens and tokens for sale held by the contract, tracked on every transfer
+    /// in and out. Tokens in accepted currencies and bundle items are not included.
+    total_escrowed_bidding: u128,
+    total_escrowed_sale: u128,
+    /// The last rejected bid of each bidder, so they can see why it was refunded.
+    rejected_bids: BTreeMap<Address, RejectedBid>,
+    /// The oracle the reserve price is converted from, until the first bid, and the minimum
+    /// increment throughout the auction.
+    reserve_oracle: Option<ReserveOracle>,
+    /// The last rate fetched from the reserve oracle, which bids are checked at.
+    reference_rate: Option<u128>,
+    /// The membership token bidders must hold, and the holders seen by `verify_membership`.
+    membership_gate: Option<MembershipGate>,
+    members: BTreeSet<Address>,
+    /// The shares of the proceeds withheld for their receivers at settlement.
+    withholdings: Vec<Withholding>,
+    /// The decimals of the token for sale and the bidding token, once queried at `start`, to
+    /// convert amounts with the `units` helpers.
+    decimals_shortname: Option<u32>,
+    sale_token_decimals: Option<u8>,
+    bidding_token_decimals: Option<u8>,
+    cancellable_after_bids: bool,
+    /// The time the setup window ends, after which an auction that was not started expires.
+    setup_deadline_millis: Option<i64>,
+    /// Whether the escrow of the tokens for sale requested by the pending start failed.
+    start_failed: bool,
+}
+
+/// The commitment to a hidden reserve price or a sealed bid, the SHA-256 hash of the big
+/// endian amount followed by the salt.
+fn reserve_commitment(reserve_price: u128, salt: Hash) -> Hash {
+    let mut preimage = reserve_price.to_be_bytes().to_vec();
+    preimage.extend_from_slice(&salt);
+    sha256(&preimage)
+}
+
+fn sha256(data: &[u8]) -> Hash {
+    Sha256::digest(data).into()
 }
 
 impl AuctionContractState {
@@ -66,251 +1122,5209 @@ impl AuctionContractState {
             tokens_for_bidding: 0,
             tokens_for_sale: 0,
         });
-        entry.tokens_for_bidding += additional_claim.tokens_for_bidding;
-        entry.tokens_for_sale += additional_claim.tokens_for_sale;
+        entry.tokens_for_bidding = add_amounts(
+            entry.tokens_for_bidding,
+            additional_claim.tokens_for_bidding,
+        );
+        entry.tokens_for_sale =
+            add_amounts(entry.tokens_for_sale, additional_claim.tokens_for_sale);
+    }
+
+    /// Append an accepted bid to the bid history, dropping the oldest records beyond
+    /// `max_bid_history`. The bidder counts as a participant of the auction.
+    fn record_bid(
+        &mut self,
+        bidder: Address,
+        amount: u128,
+        lot_id: Option<LotId>,
+        block_production_time: i64,
+    ) {
+        self.participants.insert(bidder);
+        self.bid_count += 1;
+        self.bid_history.push(BidRecord {
+            sequence: self.bid_count,
+            bidder,
+            amount,
+            timestamp_millis: block_production_time,
+            lot_id,
+        });
+        let max_records = self.max_bid_history as usize;
+        if self.bid_history.len() > max_records {
+            let excess = self.bid_history.len() - max_records;
+            self.bid_history.drain(..excess);
+        }
+        if lot_id.is_none() {
+            self.rank_bid(bidder, amount, block_production_time);
+        }
+    }
+
+    /// Rank a bid on the leaderboard, replacing a lower bid of the same bidder. Only the top
+    /// `LEADERBOARD_SIZE` bidders are kept, so ranking a bid has bounded cost.
+    fn rank_bid(&mut self, bidder: Address, amount: u128, block_production_time: i64) {
+        if let Some(index) = self
+            .leaderboard
+            .iter()
+            .position(|entry| entry.bidder == bidder)
+        {
+            if self.leaderboard[index].amount >= amount {
+                return;
+            }
+            self.leaderboard.remove(index);
+        }
+        let rank = self
+            .leaderboard
+            .partition_point(|entry| entry.amount >= amount);
+        if rank < LEADERBOARD_SIZE {
+            self.leaderboard.insert(
+                rank,
+                LeaderboardEntry {
+                    bidder,
+                    amount,
+                    timestamp_millis: block_production_time,
+                },
+            );
+            self.leaderboard.truncate(LEADERBOARD_SIZE);
+        }
+    }
+
+    /// Count a bid of `bidder` escrowing `amount` in the statistics of the bidder, whether the
+    /// bid is accepted or refunded.
+    fn record_bidder_stats(&mut self, bidder: Address, amount: u128, block_production_time: i64) {
+        let stats = self.bidder_stats.entry(bidder).or_insert(BidderStats {
+            bid_count: 0,
+            total_escrowed: 0,
+            highest_bid: 0,
+            last_bid_millis: block_production_time,
+        });
+        stats.bid_count = stats.bid_count.saturating_add(1);
+        stats.total_escrowed = add_amounts(stats.total_escrowed, amount);
+        stats.highest_bid = stats.highest_bid.max(amount);
+        stats.last_bid_millis = block_production_time;
+    }
+
+    /// The event groups transferring the claimable bidding tokens of up to `REFUND_BATCH_SIZE`
+    /// claims to their owners.
+    fn push_refunds(&mut self) -> Vec<EventGroup> {
+        let transfer = self.bidding_token_transfer();
+        let mut events = vec![];
+        for (address, claimable) in self
+            .claim_map
+            .iter_mut()
+            .filter(|(_, claimable)| claimable.tokens_for_bidding > 0)
+            .take(REFUND_BATCH_SIZE)
+        {
+            let mut event_group = EventGroup::builder();
+            event_group
+                .call(self.token_for_bidding, transfer)
+                .argument(*address)
+                .argument(claimable.tokens_for_bidding)
+                .done();
+            event_group
+                .with_callback(SHORTNAME_CLAIM_CALLBACK)
+                .argument(*address)
+                .argument(TokenClaim {
+                    tokens_for_bidding: claimable.tokens_for_bidding,
+                    tokens_for_sale: 0,
+                })
+                .done();
+            events.push(event_group.build());
+            self.total_escrowed_bidding = self
+                .total_escrowed_bidding
+                .saturating_sub(claimable.tokens_for_bidding);
+            claimable.tokens_for_bidding = 0;
+        }
+        events
+    }
+
+    /// Record a failed payout of `failed` to `claimant` in the retry queue, counting the attempt.
+    fn record_failed_payout(
+        &mut self,
+        claimant: Address,
+        failed: TokenClaim,
+        block_production_time: i64,
+    ) {
+        let attempts = self
+            .failed_payouts
+            .get(&claimant)
+            .map_or(0, |payout| payout.attempts);
+        self.failed_payouts.insert(
+            claimant,
+            FailedPayout {
+                failed,
+                attempts: attempts.saturating_add(1),
+                last_attempt_millis: block_production_time,
+            },
+        );
+    }
+
+    /// Authorize the owner to perform `action` at `block_production_time`, consuming its
+    /// approvals and its proposal.
+    fn authorize_owner_action(&mut self, action: &OwnerAction, block_production_time: i64) {
+        self.consume_approvals(action);
+        self.consume_proposal(action, block_production_time);
+    }
+
+    /// Consume the proposal of `action`, failing unless it was proposed at least the timelock
+    /// before `block_production_time`. Without a timelock, no proposal is needed.
+    fn consume_proposal(&mut self, action: &OwnerAction, block_production_time: i64) {
+        if self.timelock_millis == 0 {
+            return;
+        }
+        match self
+            .pending_actions
+            .iter()
+            .position(|pending| pending.action == *action)
+        {
+            None => fail(AuctionError::ActionNotProposed {}),
+            Some(index)
+                if block_production_time < self.pending_actions[index].executable_at_millis =>
+            {
+                fail(AuctionError::TimelockPending {})
+            }
+            Some(index) => {
+                self.pending_actions.remove(index);
+            }
+        }
+    }
+
+    /// Consume the approvals of `action`, failing unless enough signers approved it. Without
+    /// signers, the owner acts alone.
+    fn consume_approvals(&mut self, action: &OwnerAction) {
+        if self.owner_signers.is_empty() {
+            return;
+        }
+        match self
+            .action_approvals
+            .iter()
+            .position(|approval| approval.action == *action)
+        {
+            Some(index)
+                if self.action_approvals[index].approvers.len() as u32
+                    >= self.approval_threshold =>
+            {
+                self.action_approvals.remove(index);
+            }
+            _ => fail(AuctionError::ApprovalsMissing {}),
+        }
+    }
+
+    /// The event group notifying the observer contract, if one is configured.
+    fn notify_observer(&self, notifications: Vec<AuctionNotification>) -> Vec<EventGroup> {
+        match self.observer {
+            None => vec![],
+            Some(observer) => {
+                let mut event_group = EventGroup::builder();
+                for notification in notifications {
+                    event_group
+                        .call(observer, observer_notify())
+                        .argument(notification)
+                        .done();
+                }
+                vec![event_group.build()]
+            }
+        }
+    }
+
+    /// The notifications for an accepted bid on the main sale, which may have ended the
+    /// auction at the buyout price or as an instant win.
+    fn bid_notifications(&self) -> Vec<AuctionNotification> {
+        let mut notifications = vec![AuctionNotification::NewHighestBid {
+            bidder: self.highest_bidder.bidder,
+            amount: self.highest_bidder.amount,
+        }];
+        if let (AuctionStatus::Ended {}, Some(price)) = (self.status, self.instant_sale_price()) {
+            notifications.push(AuctionNotification::Executed {
+                winner: self.highest_bidder.bidder,
+                price,
+            });
+        }
+        notifications
+    }
+
+    /// The tokens escrowed in `start`, covering the main sale, every lot and every later round.
+    fn total_tokens_for_sale(&self) -> u128 {
+        self.lots
+            .values()
+            .fold(self.token_amount_for_sale, |total, lot| {
+                add_amounts(total, lot.token_amount_for_sale)
+            })
+            .saturating_add(self.upcoming_round_tokens())
+            .saturating_add(self.upcoming_repeat_tokens())
+    }
+
+    /// The tokens for sale in the repeats of a recurring auction that have not started yet.
+    fn upcoming_repeat_tokens(&self) -> u128 {
+        match &self.recurrence {
+            Some(recurrence) => self
+                .token_amount_for_sale
+                .checked_mul(u128::from(recurrence.max_repeats - self.repeat_count))
+                .unwrap_or_else(|| fail(AuctionError::ArithmeticOverflow {})),
+            None => 0,
+        }
+    }
+
+    /// Whether the auction is over, with or without a sale, and only its claims are left to pay
+    /// out. An expired auction never escrowed anything, but it is over all the same.
+    fn is_finished(&self) -> bool {
+        matches!(
+            self.status,
+            AuctionStatus::Ended {}
+                | AuctionStatus::Cancelled {}
+                | AuctionStatus::Failed {}
+                | AuctionStatus::ReserveNotMet {}
+                | AuctionStatus::Expired {}
+        )
+    }
+
+    /// The bidder the tokens for sale were sold to, once the auction ended with a sale.
+    fn winner(&self) -> Option<Address> {
+        (matches!(self.status, AuctionStatus::Ended {}) && self.highest_bidder.amount > 0)
+            .then_some(self.highest_bidder.bidder)
+    }
+
+    /// Whether a settled recurring auction is relisted for another repeat.
+    fn can_repeat(&self) -> bool {
+        matches!(
+            self.status,
+            AuctionStatus::Ended {} | AuctionStatus::ReserveNotMet {}
+        ) && matches!(&self.recurrence, Some(recurrence) if self.repeat_count < recurrence.max_repeats)
+    }
+
+    /// Schedule the next repeat of a recurring auction, on the same terms, one interval after
+    /// the start of the settled one.
+    fn repeat(&mut self, block_production_time: i64) -> Vec<EventGroup> {
+        let interval_millis = self
+            .recurrence
+            .as_ref()
+            .map_or(0, |recurrence| recurrence.interval_millis);
+        self.repeat_count += 1;
+        self.start_time_millis = self
+            .start_time_millis
+            .saturating_add(interval_millis)
+            .max(block_production_time);
+        self.end_time_millis = self
+            .start_time_millis
+            .saturating_add(self.round_duration_millis);
+        self.highest_bidder = Bid {
+            bidder: self.contract_owner,
+            amount: 0,
+        };
+        self.highest_bidder_max = None;
+        self.second_highest_bidder = Bid {
+            bidder: self.contract_owner,
+            amount: 0,
+        };
+        self.total_extension_millis = 0;
+        self.settled_by = None;
+        self.status = AuctionStatus::BiddingScheduled {};
+        self.advance_schedule(block_production_time);
+        self.notify_observer(vec![AuctionNotification::Relisted {
+            end_time_millis: self.end_time_millis,
+            reserve_price: self.reserve_price,
+        }])
+    }
+
+    /// The tokens for sale in the rounds that have not started yet.
+    fn upcoming_round_tokens(&self) -> u128 {
+        self.rounds
+            .iter()
+            .skip(self.round_index as usize)
+            .fold(0, |total, round| {
+                add_amounts(total, round.token_amount_for_sale)
+            })
+    }
+
+    /// Whether a sequential auction has another round to start once the current round ends.
+    fn has_next_round(&self) -> bool {
+        (self.round_index as usize) < self.rounds.len()
+    }
+
+    /// Record the sale of the current round of a sequential auction to the highest bidder.
+    fn record_round_result(&mut self) {
+        self.round_results.push(RoundResult {
+            round: self.round_index,
+            winner: self.highest_bidder.bidder,
+            price: self.clearing_price(),
+            units: self.token_amount_for_sale,
+        });
+    }
+
+    /// Settle the current round of a sequential auction and start the next round right away.
+    /// The tranche of a round without bids is carried over to the next round.
+    fn advance_round(&mut self, block_production_time: i64) -> Vec<EventGroup> {
+        let mut notifications = vec![];
+        let carried_over = if self.highest_bidder.amount == 0 {
+            self.token_amount_for_sale
+        } else {
+            notifications.push(AuctionNotification::Executed {
+                winner: self.highest_bidder.bidder,
+                price: self.clearing_price(),
+            });
+            self.record_round_result();
+            self.settle();
+            0
+        };
+        let round = &self.rounds[self.round_index as usize];
+        self.token_amount_for_sale = add_amounts(carried_over, round.token_amount_for_sale);
+        self.reserve_price = round.reserve_price;
+        self.start_time_millis = block_production_time;
+        self.end_time_millis = block_production_time.saturating_add(round.duration_millis);
+        self.round_index += 1;
+        self.highest_bidder = Bid {
+            bidder: self.contract_owner,
+            amount: 0,
+        };
+        self.highest_bidder_max = None;
+        self.second_highest_bidder = Bid {
+            bidder: self.contract_owner,
+            amount: 0,
+        };
+        self.total_extension_millis = 0;
+        self.status = AuctionStatus::Bidding {};
+        notifications.push(AuctionNotification::RoundStarted {
+            round: self.round_index,
+            end_time_millis: self.end_time_millis,
+        });
+        self.notify_observer(notifications)
+    }
+
+    /// Credit each lot's highest bid to the owner and the lot's tokens to its highest bidder.
+    fn settle_lots(&mut self) {
+        let lots = std::mem::take(&mut self.lots);
+        for lot in lots.values() {
+            self.settle_lot(lot);
+        }
+        self.lots = lots;
+    }
+
+    /// Credit the highest bid of `lot` to the owner and its tokens to its highest bidder.
+    fn settle_lot(&mut self, lot: &Lot) {
+        self.credit_proceeds(lot.highest_bidder.amount);
+        self.add_to_claim_map(
+            lot.highest_bidder.bidder,
+            TokenClaim {
+                tokens_for_bidding: 0,
+                tokens_for_sale: lot.token_amount_for_sale,
+            },
+        );
+    }
+
+    /// End the auction without a sale.
+    fn fail_auction(&mut self) -> Vec<EventGroup> {
+        self.status = AuctionStatus::Failed {};
+        self.return_escrow();
+        self.notify_observer(vec![AuctionNotification::Failed {}])
+    }
+
+    /// End the auction without selling the tokens for sale, which return to the owner, as no bid
+    /// met the reserve price. No proceeds are paid out, and the lots are settled on their own.
+    fn end_reserve_not_met(&mut self) -> Vec<EventGroup> {
+        self.status = AuctionStatus::ReserveNotMet {};
+        self.refund_highest_bid_escrow(self.highest_bidder.bidder, self.highest_bid_escrow());
+        self.refund_tied_bids();
+        self.add_to_claim_map(
+            self.contract_owner,
+            TokenClaim {
+                tokens_for_bidding: 0,
+                tokens_for_sale: self.token_amount_for_sale,
+            },
+        );
+        if !self.bundle.is_empty() {
+            self.bundle_claimant = Some(self.contract_owner);
+        }
+        self.settle_lots();
+        self.notify_observer(vec![AuctionNotification::ReserveNotMet {}])
+    }
+
+    /// Settle the auction after the end time, unless too few bidders participated.
+    fn conclude(&mut self) -> Vec<EventGroup> {
+        let events = self.settle_outcome();
+        // Only the seller and the winner may redirect their payouts
+        let owner = self.contract_owner;
+        let winner = self.winner();
+        self.payout_recipients
+            .retain(|party, _| *party == owner || Some(*party) == winner);
+        events
+    }
+
+    /// Settle the auction by its outcome, with or without a sale.
+    fn settle_outcome(&mut self) -> Vec<EventGroup> {
+        if self.auction_format == FIXED_PRICE {
+            // The units sold at a fixed price are final, the owner reclaims the rest
+            self.close_sale()
+        } else if self.participants.len() < self.min_unique_bidders as usize {
+            self.fail_auction()
+        } else if self.is_multi_unit() {
+            let (price, units_sold) = self.settle_orders();
+            self.notify_observer(vec![AuctionNotification::Cleared { price, units_sold }])
+        } else if self.highest_bidder.amount < self.reserve_price.max(1) {
+            self.end_reserve_not_met()
+        } else {
+            let events = self.notify_observer(vec![AuctionNotification::Executed {
+                winner: self.highest_bidder.bidder,
+                price: self.clearing_price(),
+            }]);
+            self.settle();
+            self.settle_lots();
+            events
+        }
+    }
+
+    /// Whether the auction ended without a bid meeting the reserve price, and may start another
+    /// bidding round that ends before the claim deadline.
+    fn can_relist(&self, block_production_time: i64) -> bool {
+        let round_end_millis = block_production_time.saturating_add(self.round_duration_millis);
+        matches!(&self.relist, Some(relist) if self.relist_count < relist.max_relists)
+            && self.highest_bidder.amount < self.reserve_price
+            && !matches!(self.claim_deadline_millis, Some(deadline) if deadline < round_end_millis)
+    }
+
+    /// Start a fresh bidding round with the escrowed tokens for sale and a lowered reserve
+    /// price. Lots keep their bids and are settled with the new round.
+    fn relist(&mut self, block_production_time: i64) -> Vec<EventGroup> {
+        if let Some(relist) = &self.relist {
+            self.reserve_price -= mul_div(
+                self.reserve_price,
+                u128::from(relist.reserve_decrease_bps),
+                u128::from(BASIS_POINTS),
+            );
+        }
+        self.relist_count += 1;
+        self.start_time_millis = block_production_time;
+        self.end_time_millis = block_production_time.saturating_add(self.round_duration_millis);
+        self.total_extension_millis = 0;
+        self.notify_observer(vec![AuctionNotification::Relisted {
+            end_time_millis: self.end_time_millis,
+            reserve_price: self.reserve_price,
+        }])
+    }
+
+    /// Accept an escrowed bid as the new highest bid, or refund it if it is no longer valid.
+    fn accept_bid(&mut self, bid: Bid, block_production_time: i64) -> Vec<EventGroup> {
+        let bid = Bid {
+            amount: self.cap_escrow(bid.bidder, bid.amount),
+            ..bid
+        };
+        let rejection = if self.auction_format != ENGLISH {
+            Some(RejectionReason::Closed {})
+        } else {
+            let valid = bid_is_valid(&self.bid_rules(self.highest_bidder.amount), bid.amount);
+            self.rejection_reason(&bid, self.reserve_price, valid, block_production_time)
+        };
+        if let Some(reason) = rejection {
+            self.refund_invalid_bid(bid.bidder, bid.amount, reason, block_production_time)
+        } else {
+            self.record_bid(bid.bidder, bid.amount, None, block_production_time);
+            if self.in_candle_window(block_production_time) {
+                self.accept_candle_bid(bid, block_production_time);
+            } else if matches!(self.tie_policy, TiePolicy::Split {})
+                && self.ties_highest_bid(bid.amount)
+            {
+                self.tied_bids.push(BidRecord {
+                    sequence: self.bid_count,
+                    bidder: bid.bidder,
+                    amount: bid.amount,
+                    timestamp_millis: block_production_time,
+                    lot_id: None,
+                });
+            } else {
+                self.resolve_bid(bid.bidder, bid.amount, false);
+            }
+            // Buy it now, the auction ends without waiting for the end time
+            self.settle_if_bought_out();
+            self.notify_observer(self.bid_notifications())
+        }
+    }
+
+    /// The outcome of a bid of `amount` by `bidder`, given the bid count before the bid. Only
+    /// accepted bids are counted.
+    fn bid_outcome(&self, bidder: Address, amount: u128, bid_count: u64) -> BidOutcome {
+        if self.bid_count == bid_count {
+            BidOutcome::Refunded { amount }
+        } else if self.highest_bidder.bidder == bidder {
+            BidOutcome::HighestBid {
+                amount: self.highest_bidder.amount,
+            }
+        } else {
+            BidOutcome::Outbid {
+                highest_bid: self.highest_bidder.amount,
+            }
+        }
+    }
+
+    /// Why `bid` is rejected at `block_production_time`, if it is. `raises_enough` tells
+    /// whether the bid outbids the highest bid it competes with.
+    fn rejection_reason(
+        &self,
+        bid: &Bid,
+        reserve_price: u128,
+        raises_enough: bool,
+        block_production_time: i64,
+    ) -> Option<RejectionReason> {
+        if block_production_time >= self.end_time_millis {
+            Some(RejectionReason::Ended {})
+        } else if !matches!(self.status, AuctionStatus::Bidding {}) {
+            Some(RejectionReason::Closed {})
+        } else if self.blocklist.contains(&bid.bidder) {
+            Some(RejectionReason::Blocked {})
+        } else if bid.amount < reserve_price {
+            Some(RejectionReason::BelowReserve {})
+        } else if !raises_enough {
+            Some(RejectionReason::TooLow {})
+        } else {
+            None
+        }
+    }
+
+    /// The event group querying the rate of the reserve oracle, if one is configured.
+    fn reserve_rate_event(&self) -> Option<EventGroup> {
+        self.reserve_oracle.as_ref().map(|oracle| {
+            let mut event_group = EventGroup::builder();
+            event_group
+                .call(oracle.address, Shortname::from_u32(oracle.rate_shortname))
+                .argument(self.token_for_bidding)
+                .done();
+            event_group
+                .with_callback(SHORTNAME_REFRESH_RESERVE_CALLBACK)
+                .done();
+            event_group.build()
+        })
+    }
+
+    /// Whether the setup window passed without the auction being started.
+    fn setup_expired(&self, block_production_time: i64) -> bool {
+        let deadline_passed = matches!(
+            self.setup_deadline_millis,
+            Some(deadline) if block_production_time >= deadline
+        );
+        deadline_passed && matches!(self.status, AuctionStatus::Creation {})
+    }
+
+    /// The event group querying the decimals of the token for sale and the bidding token, if
+    /// the tokens have a decimals action.
+    fn decimals_event(&self) -> Option<EventGroup> {
+        self.decimals_shortname.map(|shortname| {
+            let mut event_group = EventGroup::builder();
+            for token in [self.token_for_sale, self.token_for_bidding] {
+                event_group
+                    .call(token, Shortname::from_u32(shortname))
+                    .done();
+            }
+            event_group
+                .with_callback(SHORTNAME_DECIMALS_CALLBACK)
+                .done();
+            event_group.build()
+        })
+    }
+
+    /// Convert the reserve price and the minimum increment from the reference unit at the cached
+    /// rate of the reserve oracle. The reserve price is kept once a bid was placed, and capped
+    /// at the buyout price. A converted increment is at least one unit of the bidding token.
+    fn apply_reference_rate(&mut self) {
+        let (Some(oracle), Some(rate)) = (self.reserve_oracle.as_ref(), self.reference_rate) else {
+            return;
+        };
+        if self.bid_count == 0 {
+            let reserve_price = mul_div(oracle.reference_reserve, rate, RATE_SCALE);
+            self.reserve_price = match self.buyout_price {
+                Some(price) => reserve_price.min(price),
+                None => reserve_price,
+            };
+        }
+        if let Some(reference_increment) = oracle.reference_increment {
+            self.min_increment = mul_div(reference_increment, rate, RATE_SCALE).max(1);
+        }
+    }
+
+    /// Record the rejection of a bid of `amount` by `bidder` as their last rejected bid.
+    fn record_rejected_bid(
+        &mut self,
+        bidder: Address,
+        amount: u128,
+        reason: RejectionReason,
+        block_production_time: i64,
+    ) {
+        self.rejected_bids.insert(
+            bidder,
+            RejectedBid {
+                amount,
+                reason,
+                timestamp_millis: block_production_time,
+            },
+        );
+    }
+
+    /// Refund the escrow of a bid rejected for `reason`, recording the rejection for the
+    /// bidder. In strict refund mode the escrow is transferred back right away, otherwise it
+    /// can be claimed back. A failed refund transfer is restored as a claim.
+    fn refund_invalid_bid(
+        &mut self,
+        bidder: Address,
+        amount: u128,
+        reason: RejectionReason,
+        block_production_time: i64,
+    ) -> Vec<EventGroup> {
+        self.record_rejected_bid(bidder, amount, reason, block_production_time);
+        let refund = TokenClaim {
+            tokens_for_bidding: amount,
+            tokens_for_sale: 0,
+        };
+        if !self.strict_refunds || amount == 0 {
+            self.add_to_claim_map(bidder, refund);
+            return vec![];
+        }
+        self.total_escrowed_bidding = self.total_escrowed_bidding.saturating_sub(amount);
+        let mut event_group = EventGroup::builder();
+        event_group
+            .call(self.token_for_bidding, self.bidding_token_transfer())
+            .argument(bidder)
+            .argument(amount)
+            .done();
+        event_group
+            .with_callback(SHORTNAME_CLAIM_CALLBACK)
+            .argument(bidder)
+            .argument(refund)
+            .done();
+        vec![event_group.build()]
+    }
+
+    /// Make the escrowed `bid` the highest bid on the lot `lot_id` if it is valid, and refund
+    /// it otherwise. The outbid highest bid of the lot can be claimed back.
+    fn accept_lot_bid(
+        &mut self,
+        lot_id: LotId,
+        bid: Bid,
+        block_production_time: i64,
+    ) -> Vec<EventGroup> {
+        let rejection = match self.lots.get(&lot_id) {
+            None => Some(RejectionReason::UnknownLot {}),
+            Some(lot) if lot.has_ended(block_production_time) => Some(RejectionReason::Ended {}),
+            Some(lot) => self.rejection_reason(
+                &bid,
+                lot.reserve_price,
+                bid.amount >= lot.highest_bidder.amount.saturating_add(lot.min_increment),
+                block_production_time,
+            ),
+        };
+        match (rejection, self.lots.get_mut(&lot_id)) {
+            (None, Some(lot)) => {
+                let (bidder, amount) = (bid.bidder, bid.amount);
+                let prev_highest_bidder = std::mem::replace(&mut lot.highest_bidder, bid);
+                self.add_to_claim_map(
+                    prev_highest_bidder.bidder,
+                    TokenClaim {
+                        tokens_for_bidding: prev_highest_bidder.amount,
+                        tokens_for_sale: 0,
+                    },
+                );
+                self.record_bid(bidder, amount, Some(lot_id), block_production_time);
+                vec![]
+            }
+            (rejection, _) => {
+                // Unknown lot or invalid bid, the bid is refunded
+                let reason = rejection.unwrap_or(RejectionReason::UnknownLot {});
+                self.refund_invalid_bid(bid.bidder, bid.amount, reason, block_production_time)
+            }
+        }
+    }
+
+    /// Raise the highest bid to `bid` with the escrowed `top_up`, or refund the top up if the
+    /// bidder was outbid in the meantime.
+    fn accept_top_up(
+        &mut self,
+        bid: Bid,
+        top_up: u128,
+        block_production_time: i64,
+    ) -> Vec<EventGroup> {
+        let allowed = self.cap_escrow(bid.bidder, top_up);
+        let bid = Bid {
+            amount: bid.amount - (top_up - allowed),
+            ..bid
+        };
+        let top_up = allowed;
+        let rejection = if self.auction_format != ENGLISH {
+            Some(RejectionReason::Closed {})
+        } else if self.highest_bidder.bidder != bid.bidder
+            || self.highest_bid_escrow().checked_add(top_up) != Some(bid.amount)
+        {
+            Some(RejectionReason::Outbid {})
+        } else if self.in_candle_window(block_production_time) {
+            Some(RejectionReason::Closed {})
+        } else {
+            let valid = bid_is_valid(&self.bid_rules(bid.amount - top_up), bid.amount);
+            self.rejection_reason(&bid, self.reserve_price, valid, block_production_time)
+        };
+        if let Some(reason) = rejection {
+            // The bidder was outbid in the meantime, the top up is refunded
+            self.refund_invalid_bid(bid.bidder, top_up, reason, block_production_time)
+        } else if self.highest_bidder_max.is_some() {
+            // A proxy bidder raises their maximum, not the current highest bid
+            self.highest_bidder_max = Some(bid.amount);
+            self.record_bid(bid.bidder, bid.amount, None, block_production_time);
+            vec![]
+        } else {
+            self.highest_bidder.amount = bid.amount;
+            self.refund_tied_bids();
+            self.record_bid(bid.bidder, bid.amount, None, block_production_time);
+            self.settle_if_bought_out();
+            self.notify_observer(self.bid_notifications())
+        }
+    }
+
+    /// Accept the first bid of a bidder whose bond is escrowed, or refund the bond if the bid is
+    /// no longer valid.
+    fn accept_bond(&mut self, bid: Bid, block_production_time: i64) -> Vec<EventGroup> {
+        let raises_enough = bid.amount
+            >= self
+                .highest_bidder
+                .amount
+                .saturating_add(self.min_increment);
+        let rejection = self
+            .rejection_reason(
+                &bid,
+                self.reserve_price,
+                raises_enough,
+                block_production_time,
+            )
+            .or_else(|| {
+                self.bonded_bids
+                    .contains_key(&bid.bidder)
+                    .then_some(RejectionReason::AlreadyBonded {})
+            });
+        if let Some(reason) = rejection {
+            let bond = self.bid_bond.as_ref().map_or(0, |bid_bond| bid_bond.bond);
+            self.refund_invalid_bid(bid.bidder, bond, reason, block_production_time)
+        } else {
+            self.accept_bonded_bid(bid, block_production_time)
+        }
+    }
+
+    /// The bidding tokens currently escrowed for the bids of `bidder`.
+    fn escrowed_by(&self, bidder: Address) -> u128 {
+        let mut escrow = self
+            .orders
+            .iter()
+            .filter(|order| order.bidder == bidder)
+            .fold(0, |sum, order| add_amounts(sum, order.escrow()));
+        escrow = add_amounts(escrow, self.all_pay_bids.get(&bidder).copied().unwrap_or(0));
+        for lot in self.lots.values() {
+            if lot.highest_bidder.bidder == bidder {
+                escrow = add_amounts(escrow, lot.highest_bidder.amount);
+            }
+        }
+        if let Some(bid_bond) = &self.bid_bond {
+            if self.bonded_bids.contains_key(&bidder) {
+                escrow = add_amounts(escrow, bid_bond.bond);
+            }
+        } else if !self.candle_bids.is_empty() {
+            // The candle timeline includes the highest bid
+            for candle_bid in self.candle_bids.iter().filter(|bid| bid.bidder == bidder) {
+                escrow = add_amounts(escrow, candle_bid.amount);
+            }
+        } else if self.highest_bidder.bidder == bidder {
+            escrow = add_amounts(escrow, self.highest_bid_escrow());
+        }
+        escrow
+    }
+
+    /// The part of an incoming escrow of `amount` that keeps the bidder within the maximum
+    /// escrow per bidder. The excess can be claimed back.
+    fn cap_escrow(&mut self, bidder: Address, amount: u128) -> u128 {
+        let allowed = match self.max_escrow_per_bidder {
+            Some(cap) => cap.saturating_sub(self.escrowed_by(bidder)).min(amount),
+            None => amount,
+        };
+        if allowed < amount {
+            self.add_to_claim_map(
+                bidder,
+                TokenClaim {
+                    tokens_for_bidding: amount - allowed,
+                    tokens_for_sale: 0,
+                },
+            );
+        }
+        allowed
+    }
+
+    /// The value in the bidding token of `amount` units of the accepted currency `token`.
+    fn currency_value(&self, token: Address, amount: u128) -> u128 {
+        let currency = self
+            .accepted_currencies
+            .iter()
+            .find(|currency| currency.token == token)
+            .unwrap_or_else(|| fail(AuctionError::CurrencyNotAccepted {}));
+        let rate = match currency.rate {
+            ExchangeRate::Fixed { rate } => rate,
+            ExchangeRate::Oracle { .. } => self
+                .oracle_rates
+                .get(&token)
+                .copied()
+                .unwrap_or_else(|| fail(AuctionError::RateUnavailable {})),
+        };
+        mul_div(amount, rate, RATE_SCALE)
+    }
+
+    /// Credit `amount` of the accepted currency `token` to the currency claims of `claimant`.
+    fn add_currency_claim(&mut self, claimant: Address, token: Address, amount: u128) {
+        if amount > 0 {
+            let claimable = self
+                .currency_claims
+                .entry(claimant)
+                .or_default()
+                .entry(token)
+                .or_insert(0);
+            *claimable = add_amounts(*claimable, amount);
+        }
+    }
+
+    /// Refund the escrow of the highest bid to `bidder` in the currency it was placed in, which
+    /// is `escrow` bidding tokens unless it was placed in an accepted currency.
+    fn refund_highest_bid_escrow(&mut self, bidder: Address, escrow: u128) {
+        match self.highest_bid_currency.take() {
+            Some(currency_escrow) => {
+                self.add_currency_claim(bidder, currency_escrow.token, currency_escrow.amount)
+            }
+            None => self.add_to_claim_map(
+                bidder,
+                TokenClaim {
+                    tokens_for_bidding: escrow,
+                    tokens_for_sale: 0,
+                },
+            ),
+        }
+    }
+
+    /// Accept a bid of `amount` units of the accepted currency `token` as the new highest bid,
+    /// valued at the current exchange rate, or refund it in the currency if it is no longer
+    /// valid.
+    fn accept_currency_bid(
+        &mut self,
+        bidder: Address,
+        escrow: CurrencyEscrow,
+        block_production_time: i64,
+    ) -> Vec<EventGroup> {
+        let value = self.currency_value(escrow.token, escrow.amount);
+        let bid = Bid {
+            bidder,
+            amount: value,
+        };
+        let valid = bid_is_valid(&self.bid_rules(self.highest_bidder.amount), value);
+        if let Some(reason) =
+            self.rejection_reason(&bid, self.reserve_price, valid, block_production_time)
+        {
+            self.record_rejected_bid(bidder, escrow.amount, reason, block_production_time);
+            self.add_currency_claim(bidder, escrow.token, escrow.amount);
+            vec![]
+        } else {
+            self.record_bid(bidder, value, None, block_production_time);
+            self.resolve_bid(bidder, value, false);
+            self.highest_bid_currency = Some(escrow);
+            self.settle_if_bought_out();
+            self.notify_observer(self.bid_notifications())
+        }
+    }
+
+    /// The price the next bid of a penny auction bids, one increment above the highest bid
+    /// but at least the reserve price.
+    fn next_penny_price(&self, penny: &PennyConfig) -> u128 {
+        add_amounts(self.highest_bidder.amount, penny.price_increment).max(self.reserve_price)
+    }
+
+    /// Accept a penny bid of `bid.amount` for which `paid` tokens were escrowed, or refund the
+    /// payment if another bid came first. The bid fee goes to the owner, the price is escrowed
+    /// like any highest bid.
+    fn accept_penny_bid(
+        &mut self,
+        bid: Bid,
+        paid: u128,
+        block_production_time: i64,
+    ) -> Vec<EventGroup> {
+        let penny = self
+            .penny
+            .as_ref()
+            .unwrap_or_else(|| fail(AuctionError::NotPennyAuction {}));
+        let next_price = self.next_penny_price(penny);
+        let bid_fee = penny.bid_fee;
+        let extension_millis = penny.extension_millis;
+        let rejection = self
+            .rejection_reason(&bid, 0, true, block_production_time)
+            .or_else(|| {
+                if bid.amount != next_price {
+                    // Another penny bid raised the price first
+                    Some(RejectionReason::Outbid {})
+                } else if bid.amount.checked_add(bid_fee) != Some(paid) {
+                    Some(RejectionReason::Underpaid {})
+                } else {
+                    None
+                }
+            });
+        if let Some(reason) = rejection {
+            self.refund_invalid_bid(bid.bidder, paid, reason, block_production_time)
+        } else {
+            self.credit_proceeds(bid_fee);
+            self.record_bid(bid.bidder, bid.amount, None, block_production_time);
+            self.resolve_bid(bid.bidder, bid.amount, false);
+            self.end_time_millis = self
+                .end_time_millis
+                .max(block_production_time.saturating_add(extension_millis));
+            self.notify_observer(self.bid_notifications())
+        }
+    }
+
+    fn refund_sealed_bid_deposit(&mut self, bidder: Address) {
+        self.add_to_claim_map(
+            bidder,
+            TokenClaim {
+                tokens_for_bidding: self.sealed_bid_deposit.unwrap_or(0),
+                tokens_for_sale: 0,
+            },
+        );
+    }
+
+    /// Forfeit the deposits of the sealed bids that were never revealed to the owner.
+    fn forfeit_unrevealed_bids(&mut self) {
+        let unrevealed = std::mem::take(&mut self.sealed_commitments).len() as u128;
+        let forfeited = self
+            .sealed_bid_deposit
+            .unwrap_or(0)
+            .saturating_mul(unrevealed);
+        if forfeited > 0 {
+            self.add_to_claim_map(
+                self.contract_owner,
+                TokenClaim {
+                    tokens_for_bidding: forfeited,
+                    tokens_for_sale: 0,
+                },
+            );
+        }
+    }
+
+    /// Whether `block_production_time` is in the window after the end time in which sealed
+    /// bids are revealed.
+    fn in_reveal_window(&self, block_production_time: i64) -> bool {
+        matches!(
+            self.status,
+            AuctionStatus::Bidding {} | AuctionStatus::Revealing {}
+        ) && block_production_time >= self.end_time_millis
+            && block_production_time < self.end_time_millis.saturating_add(REVEAL_WINDOW_MILLIS)
+    }
+
+    /// Take `amount` from the deposited balance of `address`, if the balance covers it.
+    fn draw_balance(&mut self, address: Address, amount: u128) -> bool {
+        match self.balances.get_mut(&address) {
+            Some(balance) if *balance >= amount => {
+                *balance -= amount;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The highest bid of the bidders holding a bond, or the owner if there is none.
+    fn highest_bonded_bid(&self) -> Bid {
+        self.bonded_bids
+            .iter()
+            .max_by_key(|(_, amount)| **amount)
+            .map(|(bidder, amount)| Bid {
+                bidder: *bidder,
+                amount: *amount,
+            })
+            .unwrap_or(Bid {
+                bidder: self.contract_owner,
+                amount: 0,
+            })
+    }
+
+    /// Accept a bid of a bidder holding a bond as the new highest bid.
+    fn accept_bonded_bid(&mut self, bid: Bid, block_production_time: i64) -> Vec<EventGroup> {
+        self.record_bid(bid.bidder, bid.amount, None, block_production_time);
+        self.bonded_bids.insert(bid.bidder, bid.amount);
+        self.highest_bidder = bid;
+        self.notify_observer(self.bid_notifications())
+    }
+
+    fn refund_bond(&mut self, bidder: Address) {
+        let bond = self.bid_bond.as_ref().map_or(0, |bid_bond| bid_bond.bond);
+        self.add_to_claim_map(
+            bidder,
+            TokenClaim {
+                tokens_for_bidding: bond,
+                tokens_for_sale: 0,
+            },
+        );
+    }
+
+    /// Give the highest bidder of a bid bond auction the payment window to pay the rest of the
+    /// winning bid. Without bids, the auction is concluded right away.
+    fn await_payment(&mut self, block_production_time: i64) -> Vec<EventGroup> {
+        match &self.bid_bond {
+            Some(bid_bond)
+                if !self.bonded_bids.is_empty()
+                    && self.participants.len() >= self.min_unique_bidders as usize =>
+            {
+                self.status = AuctionStatus::Settling {};
+                self.payment_deadline_millis =
+                    Some(block_production_time.saturating_add(bid_bond.payment_window_millis));
+                vec![]
+            }
+            _ => self.conclude(),
+        }
+    }
+
+    /// Forfeit the bond of a winner who did not pay in time to the owner, and offer the sale to
+    /// the next highest bidder. The auction fails once no bidder is left.
+    fn pass_over_winner(&mut self, block_production_time: i64) -> Vec<EventGroup> {
+        let winner = self.highest_bidder.bidder;
+        if self.bonded_bids.remove(&winner).is_some() {
+            let bond = self.bid_bond.as_ref().map_or(0, |bid_bond| bid_bond.bond);
+            self.add_to_claim_map(
+                self.contract_owner,
+                TokenClaim {
+                    tokens_for_bidding: bond,
+                    tokens_for_sale: 0,
+                },
+            );
+        }
+        self.highest_bidder = self.highest_bonded_bid();
+        if self.bonded_bids.is_empty() {
+            self.payment_deadline_millis = None;
+            self.fail_auction()
+        } else {
+            self.await_payment(block_production_time)
+        }
+    }
+
+    /// The start of the closing window of a candle auction.
+    fn candle_window_start(&self) -> Option<i64> {
+        self.candle
+            .as_ref()
+            .map(|candle| self.end_time_millis - candle.window_millis)
+    }
+
+    /// Whether a candle auction is in its closing window at the given block production time.
+    fn in_candle_window(&self, block_production_time: i64) -> bool {
+        matches!(self.candle_window_start(), Some(start) if block_production_time >= start)
+    }
+
+    /// Accept a bid placed in the closing window of a candle auction. Outbid bids are not
+    /// refunded, as they may still win depending on the cutoff.
+    fn accept_candle_bid(&mut self, bid: Bid, block_production_time: i64) {
+        if self.candle_bids.is_empty() {
+            // The leader when the window opens is recorded at the start of the window
+            self.candle_bids.push(CandleBid {
+                bidder: self.highest_bidder.bidder,
+                amount: self.highest_bidder.amount,
+                timestamp_millis: self.candle_window_start().unwrap_or(block_production_time),
+            });
+        }
+        self.candle_bids.push(CandleBid {
+            bidder: bid.bidder,
+            amount: bid.amount,
+            timestamp_millis: block_production_time,
+        });
+        self.highest_bidder = bid;
+    }
+
+    /// Make the last bid placed at or before the cutoff the highest bid, and refund the others.
+    /// Bids increase along the timeline, so this is the highest bid before the cutoff.
+    fn resolve_candle(&mut self, cutoff_millis: i64) {
+        let candle_bids = std::mem::take(&mut self.candle_bids);
+        let winner = candle_bids
+            .iter()
+            .rposition(|candle_bid| candle_bid.timestamp_millis <= cutoff_millis);
+        for (index, candle_bid) in candle_bids.into_iter().enumerate() {
+            if Some(index) == winner {
+                self.highest_bidder = Bid {
+                    bidder: candle_bid.bidder,
+                    amount: candle_bid.amount,
+                };
+            } else {
+                self.refund_candle_bid(candle_bid);
+            }
+        }
+    }
+
+    fn refund_candle_bid(&mut self, candle_bid: CandleBid) {
+        if candle_bid.amount > 0 {
+            self.add_to_claim_map(
+                candle_bid.bidder,
+                TokenClaim {
+                    tokens_for_bidding: candle_bid.amount,
+                    tokens_for_sale: 0,
+                },
+            );
+        }
+    }
+
+    /// Whether the auction sells units to orders, at a uniform price, pay as bid or at a fixed
+    /// price.
+    fn is_multi_unit(&self) -> bool {
+        matches!(
+            self.auction_format,
+            UNIFORM_PRICE | PAY_AS_BID | FIXED_PRICE
+        )
+    }
+
+    /// The units of a fixed price sale that have not been sold yet.
+    fn units_remaining(&self) -> u128 {
+        self.token_amount_for_sale - self.units_sold
+    }
+
+    /// Fill `order` at the fixed price with the remaining units, refunding the escrow of the
+    /// units that were not filled. The sale ends once every unit is sold.
+    fn fill_order(&mut self, order: Order, block_production_time: i64) -> Vec<EventGroup> {
+        let units = order.quantity.min(self.units_remaining());
+        let paid = units * self.reserve_price;
+        self.units_sold += units;
+        self.record_bid(order.bidder, paid, None, block_production_time);
+        self.add_to_claim_map(
+            order.bidder,
+            TokenClaim {
+                tokens_for_bidding: order.escrow() - paid,
+                tokens_for_sale: units,
+            },
+        );
+        self.credit_proceeds(paid);
+        if self.units_remaining() == 0 {
+            self.close_sale()
+        } else {
+            vec![]
+        }
+    }
+
+    /// End a fixed price sale, returning the unsold units to the owner.
+    fn close_sale(&mut self) -> Vec<EventGroup> {
+        self.status = AuctionStatus::Ended {};
+        if self.units_remaining() > 0 {
+            self.add_to_claim_map(
+                self.contract_owner,
+                TokenClaim {
+                    tokens_for_bidding: 0,
+                    tokens_for_sale: self.units_remaining(),
+                },
+            );
+        }
+        self.notify_observer(vec![AuctionNotification::Cleared {
+            price: self.reserve_price,
+            units_sold: self.units_sold,
+        }])
+    }
+
+    /// Settle a multi-unit auction. Units are allocated to the orders with the highest unit
+    /// prices, earlier orders first on equal prices, and the marginal order may be partially
+    /// filled. In a uniform price auction every winner pays the unit price of the lowest order
+    /// that received units, the clearing price, and in a pay as bid auction every winner pays
+    /// their own unit price. Returns the clearing price and the units sold.
+    fn settle_orders(&mut self) -> (u128, u128) {
+        self.status = AuctionStatus::Ended {};
+        let orders = std::mem::take(&mut self.orders);
+        let mut ranking: Vec<usize> = (0..orders.len()).collect();
+        ranking.sort_by(|&a, &b| orders[b].unit_price.cmp(&orders[a].unit_price));
+        let mut allocations = vec![0; orders.len()];
+        let mut units_left = self.token_amount_for_sale;
+        let mut clearing_price = 0;
+        for index in ranking {
+            if units_left == 0 {
+                break;
+            }
+            let units = orders[index].quantity.min(units_left);
+            allocations[index] = units;
+            units_left -= units;
+            clearing_price = orders[index].unit_price;
+        }
+        let mut proceeds = 0;
+        for (order, units) in orders.iter().zip(allocations) {
+            // The clearing price is at most the unit price of every order that received units
+            let unit_price = if self.auction_format == PAY_AS_BID {
+                order.unit_price
+            } else {
+                clearing_price
+            };
+            let paid = units * unit_price;
+            proceeds = add_amounts(proceeds, paid);
+            self.add_to_claim_map(
+                order.bidder,
+                TokenClaim {
+                    tokens_for_bidding: order.escrow() - paid,
+                    tokens_for_sale: units,
+                },
+            );
+        }
+        self.add_to_claim_map(
+            self.contract_owner,
+            TokenClaim {
+                tokens_for_bidding: 0,
+                tokens_for_sale: units_left,
+            },
+        );
+        self.credit_proceeds(proceeds);
+        (clearing_price, self.token_amount_for_sale - units_left)
+    }
+
+    fn refund_order(&mut self, order: Order) {
+        self.add_to_claim_map(
+            order.bidder,
+            TokenClaim {
+                tokens_for_bidding: order.escrow(),
+                tokens_for_sale: 0,
+            },
+        );
+    }
+
+    /// Return the escrowed highest bids to their bidders, and the tokens for sale to the owner.
+    fn return_escrow(&mut self) {
+        for bidder in std::mem::take(&mut self.sealed_commitments).into_keys() {
+            self.refund_sealed_bid_deposit(bidder);
+        }
+        if self.bid_bond.is_some() {
+            // Only the bonds are escrowed, not the bids themselves
+            for bidder in std::mem::take(&mut self.bonded_bids).into_keys() {
+                self.refund_bond(bidder);
+            }
+        } else if self.candle_bids.is_empty() {
+            self.refund_highest_bid_escrow(self.highest_bidder.bidder, self.highest_bid_escrow());
+            self.refund_tied_bids();
+        } else {
+            // The candle timeline includes the highest bid
+            for candle_bid in std::mem::take(&mut self.candle_bids) {
+                self.refund_candle_bid(candle_bid);
+            }
+        }
+        for order in std::mem::take(&mut self.orders) {
+            self.refund_order(order);
+        }
+        // Without a sale the outbid bids of an all-pay auction are refunded as well
+        for (bidder, paid) in std::mem::take(&mut self.all_pay_bids) {
+            self.add_to_claim_map(
+                bidder,
+                TokenClaim {
+                    tokens_for_bidding: paid,
+                    tokens_for_sale: 0,
+                },
+            );
+        }
+        self.add_to_claim_map(
+            self.contract_owner,
+            TokenClaim {
+                tokens_for_bidding: 0,
+                tokens_for_sale: add_amounts(
+                    self.units_remaining(),
+                    add_amounts(self.upcoming_round_tokens(), self.upcoming_repeat_tokens()),
+                ),
+            },
+        );
+        if !self.bundle.is_empty() {
+            self.bundle_claimant = Some(self.contract_owner);
+        }
+        self.refund_lots();
+    }
+
+    /// Refund each lot's highest bid and return the lot's tokens to the owner.
+    fn refund_lots(&mut self) {
+        let mut claims = vec![];
+        for lot in self.lots.values() {
+            claims.push((
+                lot.highest_bidder.bidder,
+                TokenClaim {
+                    tokens_for_bidding: lot.highest_bidder.amount,
+                    tokens_for_sale: 0,
+                },
+            ));
+            claims.push((
+                self.contract_owner,
+                TokenClaim {
+                    tokens_for_bidding: 0,
+                    tokens_for_sale: lot.token_amount_for_sale,
+                },
+            ));
+        }
+        for (address, claim) in claims {
+            self.add_to_claim_map(address, claim);
+        }
+    }
+
+    /// The transfer and transfer_from shortnames of the token for sale.
+    fn sale_token_shortnames(&self) -> (Shortname, Shortname) {
+        match (&self.sale_token_interface, self.sale_token_id) {
+            (Some(interface), _) => (interface.transfer(), interface.transfer_from()),
+            (None, Some(_)) if self.semi_fungible_sale => (
+                multi_token_contract_transfer(),
+                multi_token_contract_transfer_from(),
+            ),
+            (None, Some(_)) => (nft_contract_transfer(), nft_contract_transfer_from()),
+            (None, None) => (token_contract_transfer(), token_contract_transfer_from()),
+        }
+    }
+
+    /// The transfer shortname of the token used for bidding.
+    fn bidding_token_transfer(&self) -> Shortname {
+        match &self.bidding_token_interface {
+            Some(interface) => interface.transfer(),
+            None => token_contract_transfer(),
+        }
+    }
+
+    /// The transfer_from shortname of the token used for bidding.
+    fn bidding_token_transfer_from(&self) -> Shortname {
+        match &self.bidding_token_interface {
+            Some(interface) => interface.transfer_from(),
+            None => token_contract_transfer_from(),
+        }
+    }
+
+    /// Fail unless `bidder` is allowed to bid, before any bidding tokens are transferred.
+    fn check_bidder(&self, bidder: Address) {
+        if self.blocklist.contains(&bidder) {
+            fail(AuctionError::BidderBlocked {});
+        } else if bidder == self.contract_owner && !self.allow_owner_bids {
+            fail(AuctionError::OwnerCannotBid {});
+        } else if self.kyc_registry.is_some() && !self.verified_bidders.contains(&bidder) {
+            fail(AuctionError::NotVerified {});
+        } else if self.membership_gate.is_some() && !self.members.contains(&bidder) {
+            fail(AuctionError::NotMember {});
+        }
+    }
+
+    /// Whether `bidder` bid less than the bid cooldown ago, and may not bid yet.
+    fn in_bid_cooldown(&self, bidder: Address, block_production_time: i64) -> bool {
+        match (self.bid_cooldown_millis, self.bidder_stats.get(&bidder)) {
+            (Some(cooldown_millis), Some(stats)) => {
+                block_production_time < stats.last_bid_millis.saturating_add(cooldown_millis)
+            }
+            _ => false,
+        }
+    }
+
+    /// The terms a bid is checked against, outbidding `highest_bid`.
+    fn bid_rules(&self, highest_bid: u128) -> BidRules {
+        BidRules {
+            highest_bid,
+            min_increment: self.min_increment,
+            reserve_price: self.reserve_price,
+            buyout_price: self.buyout_price,
+            tie_policy: self.tie_policy,
+        }
+    }
+
+    /// Whether a bid of `amount` ties with the highest bid.
+    fn ties_highest_bid(&self, amount: u128) -> bool {
+        amount > 0 && amount == self.highest_bidder.amount
+    }
+
+    /// Refund the bids tying with the highest bid, once it is outbid or raised.
+    fn refund_tied_bids(&mut self) {
+        for tied_bid in std::mem::take(&mut self.tied_bids) {
+            self.add_to_claim_map(
+                tied_bid.bidder,
+                TokenClaim {
+                    tokens_for_bidding: tied_bid.amount,
+                    tokens_for_sale: 0,
+                },
+            );
+        }
+    }
+
+    /// Fail if a bid of `bid_amount`, or a raise of the highest bid to `bid_amount` by
+    /// `top_up`, would be refunded by the bid callback anyway, so the bidder does not pay for a
+    /// pointless escrow and claim. The callback still checks the bid, as the auction may change
+    /// in the meantime.
+    fn check_bid(&self, block_production_time: i64, bid_amount: u128, top_up: Option<u128>) {
+        let highest_bid = match top_up {
+            Some(top_up) => bid_amount - top_up,
+            None => self.highest_bidder.amount,
+        };
+        let scheduled_start = matches!(self.status, AuctionStatus::BiddingScheduled {})
+            && block_production_time >= self.start_time_millis;
+        if !matches!(self.status, AuctionStatus::Bidding {}) && !scheduled_start {
+            fail(AuctionError::NotBidding {});
+        } else if block_production_time >= self.end_time_millis {
+            fail(AuctionError::AfterEndTime {});
+        } else if self.auction_format != ENGLISH {
+            fail(AuctionError::NotEnglishAuction {});
+        } else if !bid_is_valid(&self.bid_rules(highest_bid), bid_amount) {
+            fail(AuctionError::BidTooLow {});
+        }
+    }
+
+    /// Add the escrow transfer of `amount` bidding tokens from the sender to the event group.
+    fn escrow_bidding_tokens(
+        &self,
+        event_group: &mut EventGroupBuilder,
+        context: &ContractContext,
+        amount: u128,
+    ) {
+        self.escrow_bidding_tokens_from(
+            event_group,
+            context.sender,
+            context.contract_address,
+            amount,
+        );
+    }
+
+    /// Add the escrow transfer of `amount` bidding tokens from `from` to the contract at
+    /// `contract_address` to the event group. For a bidding token taking a fee on transfer, the
+    /// transfer is wrapped in queries of the contract's balance, so the callback can credit the
+    /// amount actually received.
+    fn escrow_bidding_tokens_from(
+        &self,
+        event_group: &mut EventGroupBuilder,
+        from: Address,
+        contract_address: Address,
+        amount: u128,
+    ) {
+        if let Some(shortname) = self.bidding_balance_shortname {
+            event_group
+                .call(self.token_for_bidding, Shortname::from_u32(shortname))
+                .argument(contract_address)
+                .done();
+        }
+        event_group
+            .call(self.token_for_bidding, self.bidding_token_transfer_from())
+            .argument(from)
+            .argument(contract_address)
+            .argument(amount)
+            .done();
+        if let Some(shortname) = self.bidding_balance_shortname {
+            event_group
+                .call(self.token_for_bidding, Shortname::from_u32(shortname))
+                .argument(contract_address)
+                .done();
+        }
+    }
+
+    /// The event group escrowing `bid`, or only the `top_up` of the bidder's own highest bid,
+    /// calling back with the bid.
+    fn escrow_bid_event(
+        &self,
+        contract_address: Address,
+        bid: Bid,
+        top_up: Option<u128>,
+    ) -> EventGroup {
+        // transfer(auctionContract, escrow)
+        let mut event_group = EventGroup::builder();
+        let escrow = top_up.unwrap_or(bid.amount);
+        self.escrow_bidding_tokens_from(&mut event_group, bid.bidder, contract_address, escrow);
+        match top_up {
+            Some(top_up) => event_group
+                .with_callback(SHORTNAME_TOP_UP_CALLBACK)
+                .argument(bid)
+                .argument(top_up)
+                .done(),
+            None => event_group
+                .with_callback(SHORTNAME_BID_CALLBACK)
+                .argument(bid)
+                .done(),
+        }
+        event_group.build()
+    }
+
+    /// The bidding tokens received by an escrow transfer of `amount`, from the balances
+    /// returned by the queries around the transfer, which are added to the tracked escrow.
+    /// Without balance queries the full amount is assumed to be received.
+    fn receive_bidding_tokens(&mut self, callback_ctx: &CallbackContext, amount: u128) -> u128 {
+        let received = self.received_bidding_tokens(callback_ctx, amount);
+        if callback_ctx.success {
+            self.total_escrowed_bidding = self.total_escrowed_bidding.saturating_add(received);
+        }
+        received
+    }
+
+    fn received_bidding_tokens(&self, callback_ctx: &CallbackContext, amount: u128) -> u128 {
+        match (
+            self.bidding_balance_shortname,
+            callback_ctx.results.first(),
+            callback_ctx.results.get(2),
+        ) {
+            (None, _, _) => amount,
+            (Some(_), Some(before), Some(after)) => {
+                let before = u128::rpc_read_from(&mut before.return_data.as_slice());
+                let after = u128::rpc_read_from(&mut after.return_data.as_slice());
+                after.saturating_sub(before).min(amount)
+            }
+            (Some(_), _, _) => 0,
+        }
+    }
+
+    /// Add the transfer of `units` of the token for sale to `to` to the event group, with
+    /// transfer_from if the tokens are sent `from` another address than the contract. An NFT
+    /// is transferred by its token id instead of an amount, and a semi-fungible token by its
+    /// token id followed by the amount.
+    fn transfer_sale_tokens(
+        &self,
+        event_group: &mut EventGroupBuilder,
+        from: Option<Address>,
+        to: Address,
+        units: u128,
+    ) {
+        let (transfer, transfer_from) = self.sale_token_shortnames();
+        let interaction = match from {
+            Some(from) => event_group
+                .call(self.token_for_sale, transfer_from)
+                .argument(from),
+            None => event_group.call(self.token_for_sale, transfer),
+        }
+        .argument(to);
+        match self.sale_token_id {
+            Some(token_id) if self.semi_fungible_sale => {
+                interaction.argument(token_id).argument(units).done()
+            }
+            Some(token_id) => interaction.argument(token_id).done(),
+            None => interaction.argument(units).done(),
+        }
+    }
+
+    /// The event group transferring every bundle item claimed by `claimant` to `to`. A failed
+    /// transfer restores the claim.
+    fn transfer_bundle(&mut self, claimant: Address, to: Address) -> EventGroup {
+        self.bundle_claimant = None;
+        let mut event_group = EventGroup::builder();
+        for item in &self.bundle {
+            event_group
+                .call(item.token, token_contract_transfer())
+                .argument(to)
+                .argument(item.amount_or_id)
+                .done();
+        }
+        event_group
+            .with_callback(SHORTNAME_CLAIM_BUNDLE_CALLBACK)
+            .argument(claimant)
+            .done();
+        event_group.build()
+    }
+
+    /// Refund every highest bid placed by `bidder`, resetting those bids to the owner.
+    fn refund_highest_bids_of(&mut self, bidder: Address) {
+        if self.sealed_commitments.remove(&bidder).is_some() {
+            self.refund_sealed_bid_deposit(bidder);
+        }
+        let (refunded, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.tied_bids)
+            .into_iter()
+            .partition(|tied_bid| tied_bid.bidder == bidder);
+        self.tied_bids = kept;
+        for tied_bid in refunded {
+            self.add_to_claim_map(
+                bidder,
+                TokenClaim {
+                    tokens_for_bidding: tied_bid.amount,
+                    tokens_for_sale: 0,
+                },
+            );
+        }
+        let (refunded, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.orders)
+            .into_iter()
+            .partition(|order| order.bidder == bidder);
+        self.orders = kept;
+        for order in refunded {
+            self.refund_order(order);
+        }
+        if self.bid_bond.is_some() {
+            if self.bonded_bids.remove(&bidder).is_some() {
+                self.refund_bond(bidder);
+            }
+            if self.highest_bidder.bidder == bidder {
+                self.highest_bidder = self.highest_bonded_bid();
+            }
+        } else if !self.candle_bids.is_empty() {
+            // Every bid in the candle timeline is escrowed, including the highest bid
+            let (refunded, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.candle_bids)
+                .into_iter()
+                .partition(|candle_bid| candle_bid.bidder == bidder);
+            self.candle_bids = kept;
+            self.highest_bidder = match self.candle_bids.last() {
+                Some(candle_bid) => Bid {
+                    bidder: candle_bid.bidder,
+                    amount: candle_bid.amount,
+                },
+                None => Bid {
+                    bidder: self.contract_owner,
+                    amount: 0,
+                },
+            };
+            for candle_bid in refunded {
+                self.refund_candle_bid(candle_bid);
+            }
+        } else if self.highest_bidder.bidder == bidder {
+            let escrow = self.highest_bid_escrow();
+            // The first bid tying with the highest bid takes its place
+            self.highest_bidder = if self.tied_bids.is_empty() {
+                Bid {
+                    bidder: self.contract_owner,
+                    amount: 0,
+                }
+            } else {
+                let tied_bid = self.tied_bids.remove(0);
+                Bid {
+                    bidder: tied_bid.bidder,
+                    amount: tied_bid.amount,
+                }
+            };
+            self.highest_bidder_max = None;
+            self.refund_highest_bid_escrow(bidder, escrow);
+        }
+        let mut refund = 0;
+        for lot in self.lots.values_mut() {
+            if lot.highest_bidder.bidder == bidder {
+                refund = add_amounts(refund, lot.highest_bidder.amount);
+                lot.highest_bidder = Bid {
+                    bidder: self.contract_owner,
+                    amount: 0,
+                };
+            }
+        }
+        if refund > 0 {
+            self.add_to_claim_map(
+                bidder,
+                TokenClaim {
+                    tokens_for_bidding: refund,
+                    tokens_for_sale: 0,
+                },
+            );
+        }
+    }
+
+    /// Open bidding once the scheduled start time has been reached.
+    fn advance_schedule(&mut self, block_production_time: i64) {
+        if matches!(self.status, AuctionStatus::BiddingScheduled {})
+            && block_production_time >= self.start_time_millis
+        {
+            self.status = AuctionStatus::Bidding {};
+        }
+    }
+
+    /// The price a Dutch auction currently asks at the given block production time.
+    fn current_price(&self, block_production_time: i64) -> u128 {
+        match &self.price_schedule {
+            None => self.reserve_price,
+            Some(schedule) => schedule.price_at(
+                block_production_time - self.start_time_millis,
+                self.reserve_price,
+            ),
+        }
+    }
+
+    /// The amount the highest bidder pays. In second price settlement this is the second
+    /// highest bid, but never less than the reserve price.
+    fn clearing_price(&self) -> u128 {
+        simulation::clearing_price(
+            self.settlement_mode,
+            self.highest_bidder.amount,
+            self.second_highest_bidder.amount,
+            self.reserve_price,
+        )
+    }
+
+    /// Credit the clearing price to the owner and the tokens for sale to the highest bidder,
+    /// together with the part of the highest bid exceeding the clearing price.
+    fn settle(&mut self) {
+        let price = self.clearing_price();
+        self.settle_at(price);
+    }
+
+    /// End the auction with the highest bidder paying `price`.
+    fn settle_at(&mut self, price: u128) {
+        self.status = AuctionStatus::Ended {};
+        if !self.tied_bids.is_empty() {
+            self.settle_split(price);
+            return;
+        }
+        let outbid_bids = std::mem::take(&mut self.all_pay_bids)
+            .into_values()
+            .fold(0, add_amounts);
+        let refund = match self.highest_bid_currency.take() {
+            Some(escrow) => {
+                // The price is paid in the currency of the bid, at the rate the bid was valued at
+                let paid = mul_div(escrow.amount, price, self.highest_bidder.amount);
+                for (receiver, share) in self.proceeds_shares(paid, self.winner_referrer()) {
+                    self.add_currency_claim(receiver, escrow.token, share);
+                }
+                self.add_currency_claim(
+                    self.highest_bidder.bidder,
+                    escrow.token,
+                    escrow.amount - paid,
+                );
+                0
+            }
+            None if self.delivery_timeout_millis.is_some() => {
+                // The seller is only paid once the winner confirms the receipt of the goods
+                self.held_proceeds = add_amounts(price, outbid_bids);
+                self.highest_bid_escrow() - price
+            }
+            None => {
+                self.credit_winning_bid(add_amounts(price, outbid_bids));
+                self.highest_bid_escrow() - price
+            }
+        };
+        self.add_to_claim_map(
+            self.highest_bidder.bidder,
+            TokenClaim {
+                tokens_for_bidding: refund,
+                tokens_for_sale: self.token_amount_for_sale,
+            },
+        );
+        if !self.bundle.is_empty() {
+            self.bundle_claimant = Some(self.highest_bidder.bidder);
+        }
+    }
+
+    /// Split the sale between the highest bidder and the bidders tying with them, who each pay an
+    /// equal share of `price` for an equal share of the tokens for sale. The highest bidder, who
+    /// bid first, receives the units and pays the price left over by rounding.
+    fn settle_split(&mut self, price: u128) {
+        let tied_bids = std::mem::take(&mut self.tied_bids);
+        let tied_count = tied_bids.len() as u128;
+        let units = self.token_amount_for_sale / (tied_count + 1);
+        let payment = mul_div(price, units, self.token_amount_for_sale);
+        for tied_bid in tied_bids {
+            self.add_to_claim_map(
+                tied_bid.bidder,
+                TokenClaim {
+                    tokens_for_bidding: tied_bid.amount - payment,
+                    tokens_for_sale: units,
+                },
+            );
+        }
+        let winner_payment = price - payment * tied_count;
+        self.credit_proceeds(price);
+        self.add_to_claim_map(
+            self.highest_bidder.bidder,
+            TokenClaim {
+                tokens_for_bidding: self.highest_bid_escrow() - winner_payment,
+                tokens_for_sale: self.token_amount_for_sale - units * tied_count,
+            },
+        );
+    }
+
+    /// The time the payment held for delivery is released without a confirmation of the
+    /// winner, the delivery timeout after the end time.
+    fn delivery_deadline_millis(&self) -> Option<i64> {
+        self.delivery_timeout_millis
+            .map(|timeout| self.end_time_millis.saturating_add(timeout))
+    }
+
+    /// Credit the payment held for delivery as the proceeds of the sale.
+    fn release_held_proceeds(&mut self) {
+        let proceeds = std::mem::take(&mut self.held_proceeds);
+        self.credit_winning_bid(proceeds);
+    }
+
+    /// Credit the proceeds of a sale to the owner, after paying out the platform fee, the
+    /// royalty and the reward of the keeper settling the auction.
+    fn credit_proceeds(&mut self, proceeds: u128) {
+        let shares = self.proceeds_shares(proceeds, None);
+        self.credit_shares(shares);
+    }
+
+    /// Credit the payment of the highest bidder as the proceeds of the sale, paying out the
+    /// referral reward if the highest bidder was referred.
+    fn credit_winning_bid(&mut self, proceeds: u128) {
+        let shares = self.proceeds_shares(proceeds, self.winner_referrer());
+        self.credit_shares(shares);
+    }
+
+    fn credit_shares(&mut self, shares: Vec<(Address, u128)>) {
+        for (receiver, share) in shares {
+            self.add_to_claim_map(
+                receiver,
+                TokenClaim {
+                    tokens_for_bidding: share,
+                    tokens_for_sale: 0,
+                },
+            );
+        }
+    }
+
+    /// The referrer of the highest bidder, if any.
+    fn winner_referrer(&self) -> Option<Address> {
+        self.referrers.get(&self.highest_bidder.bidder).copied()
+    }
+
+    /// Split the proceeds of a sale between the keeper settling the auction, the fee collector,
+    /// the royalty receiver, the `referrer` of the winner and the beneficiaries. All shares are
+    /// computed from the full proceeds, and without beneficiaries, and for any rounding
+    /// remainder, the owner is credited.
+    fn proceeds_shares(&self, proceeds: u128, referrer: Option<Address>) -> Vec<(Address, u128)> {
+        let keeper = self.settled_by.filter(|_| self.keeper_reward_bps > 0);
+        let split = split_proceeds(
+            proceeds,
+            &ProceedsRates {
+                keeper_reward_bps: keeper.map_or(0, |_| self.keeper_reward_bps),
+                referral_bps: referrer.map_or(0, |_| self.referral_bps),
+                fee_bps: self.fee_bps,
+                royalty_bps: self.royalty_bps,
+                withholding_bps: self
+                    .withholdings
+                    .iter()
+                    .map(|withholding| withholding.bps)
+                    .collect(),
+                beneficiary_weights: self
+                    .beneficiaries
+                    .iter()
+                    .map(|beneficiary| beneficiary.weight)
+                    .collect(),
+            },
+        );
+        let mut shares = vec![];
+        if let Some(referrer) = referrer {
+            shares.push((referrer, split.referral_reward));
+        }
+        if let Some(keeper) = keeper {
+            shares.push((keeper, split.keeper_reward));
+        }
+        if let Some(collector) = self.fee_collector {
+            shares.push((collector, split.fee));
+        }
+        if let Some(receiver) = self.royalty_receiver {
+            shares.push((receiver, split.royalty));
+        }
+        for (withholding, share) in self.withholdings.iter().zip(split.withholdings) {
+            shares.push((withholding.receiver, share));
+        }
+        for (beneficiary, share) in self.beneficiaries.iter().zip(split.beneficiary_shares) {
+            shares.push((beneficiary.receiver, share));
+        }
+        let proceeds_receiver = self.proceeds_receiver.unwrap_or(self.contract_owner);
+        shares.push((proceeds_receiver, split.owner_share));
+        shares
+    }
+
+    /// End the auction once the highest bid reaches the buyout price or the instant win
+    /// threshold.
+    fn settle_if_bought_out(&mut self) {
+        if let Some(price) = self.instant_sale_price() {
+            self.settle_at(price);
+        }
+    }
+
+    /// The price the highest bid ends the auction at right away: the buyout price once the
+    /// highest bid reaches it, or the clearing price once the highest bid reaches the instant
+    /// win multiple of the reserve price.
+    fn instant_sale_price(&self) -> Option<u128> {
+        let highest_bid = self.highest_bidder.amount;
+        match (self.buyout_price, self.instant_win_multiple) {
+            (Some(price), _) if highest_bid >= price => Some(price),
+            (_, Some(multiple))
+                if highest_bid >= self.reserve_price.saturating_mul(u128::from(multiple)) =>
+            {
+                Some(self.clearing_price())
+            }
+            _ => None,
+        }
+    }
+
+    /// The bidding tokens the contract owes: the claimable tokens, the deposited balances, the
+    /// payment held for delivery and the escrow of the bids that have not been settled yet.
+    fn owed_bidding_tokens(&self) -> u128 {
+        self.claim_map
+            .values()
+            .map(|claimable| claimable.tokens_for_bidding)
+            .chain(self.balances.values().copied())
+            .fold(
+                add_amounts(self.held_proceeds, self.unsettled_bidding_escrow()),
+                add_amounts,
+            )
+    }
+
+    /// The bidding tokens escrowed for bids, bonds, orders and deposits before the auction is
+    /// settled. Settling moves all of them to the claim map.
+    fn unsettled_bidding_escrow(&self) -> u128 {
+        if !matches!(
+            self.status,
+            AuctionStatus::BiddingScheduled {}
+                | AuctionStatus::Bidding {}
+                | AuctionStatus::Paused {}
+                | AuctionStatus::Revealing {}
+                | AuctionStatus::Settling {}
+        ) {
+            return 0;
+        }
+        // A bonded highest bid is not escrowed, and one in an accepted currency not in tokens
+        let highest_bid_escrow = if self.bid_bond.is_some() || self.highest_bid_currency.is_some() {
+            0
+        } else if !self.candle_bids.is_empty() {
+            // The candle timeline includes the highest bid
+            self.candle_bids
+                .iter()
+                .fold(0, |sum, candle_bid| add_amounts(sum, candle_bid.amount))
+        } else {
+            self.highest_bid_escrow()
+        };
+        let bonds = self
+            .bid_bond
+            .as_ref()
+            .map_or(0, |bid_bond| bid_bond.bond)
+            .saturating_mul(self.bonded_bids.len() as u128);
+        let sealed_deposits = self
+            .sealed_bid_deposit
+            .unwrap_or(0)
+            .saturating_mul(self.sealed_commitments.len() as u128);
+        [highest_bid_escrow, bonds, sealed_deposits]
+            .into_iter()
+            .chain(self.orders.iter().map(Order::escrow))
+            .chain(self.all_pay_bids.values().copied())
+            .chain(self.lots.values().map(|lot| lot.highest_bidder.amount))
+            .chain(self.tied_bids.iter().map(|tied_bid| tied_bid.amount))
+            .fold(0, add_amounts)
+    }
+
+    /// The bidding tokens escrowed for the highest bid. A proxy bidder has escrowed their
+    /// maximum, which can exceed the current highest bid.
+    fn highest_bid_escrow(&self) -> u128 {
+        self.highest_bidder_max
+            .unwrap_or(self.highest_bidder.amount)
+    }
+
+    /// Resolve a valid bid of up to `max_amount` against the highest bidder. A proxy bidder
+    /// only bids what is needed to lead by the minimum increment, and a leading proxy bidder
+    /// automatically outbids challengers up to their maximum.
+    fn resolve_bid(&mut self, bidder: Address, max_amount: u128, is_proxy: bool) {
+        let leader_max = self.highest_bid_escrow();
+        if self.highest_bidder_max.is_some() && leader_max >= max_amount {
+            self.highest_bidder.amount =
+                leader_max.min(max_amount.saturating_add(self.min_increment));
+            self.second_highest_bidder = Bid {
+                bidder,
+                amount: max_amount,
+            };
+            self.add_to_claim_map(
+                bidder,
+                TokenClaim {
+                    tokens_for_bidding: max_amount,
+                    tokens_for_sale: 0,
+                },
+            );
+        } else {
+            let amount = if is_proxy {
+                max_amount.min(
+                    leader_max
+                        .saturating_add(self.min_increment)
+                        .max(self.reserve_price),
+                )
+            } else {
+                max_amount
+            };
+            let prev_highest_bidder =
+                std::mem::replace(&mut self.highest_bidder, Bid { bidder, amount });
+            self.highest_bidder_max = if is_proxy { Some(max_amount) } else { None };
+            if self.settlement_mode == ALL_PAY {
+                // The outbid bid is paid to the owner once the auction ends with a sale
+                if leader_max > 0 {
+                    let paid = self
+                        .all_pay_bids
+                        .entry(prev_highest_bidder.bidder)
+                        .or_insert(0);
+                    *paid = add_amounts(*paid, leader_max);
+                }
+            } else {
+                self.refund_highest_bid_escrow(prev_highest_bidder.bidder, leader_max);
+            }
+            self.refund_tied_bids();
+            self.second_highest_bidder = Bid {
+                bidder: prev_highest_bidder.bidder,
+                amount: leader_max,
+            };
+        }
+    }
+}
+
+/// Check the reserve price and minimum increment against the other terms of an auction, at
+/// initialization and whenever the owner corrects them.
+fn check_terms(
+    auction_format: AuctionFormat,
+    tie_policy: TiePolicy,
+    price_schedule: Option<&PriceSchedule>,
+    buyout_price: Option<u128>,
+    reserve_price: u128,
+    min_increment: u128,
+) {
+    // Without a minimum increment a bid can only tie, which the first received policy refunds
+    if auction_format == ENGLISH
+        && min_increment == 0
+        && matches!(tie_policy, TiePolicy::FirstReceived {})
+    {
+        fail(AuctionError::ZeroIncrement {});
+    }
+    if matches!(price_schedule, Some(schedule) if schedule.start_price < reserve_price) {
+        fail(AuctionError::StartPriceBelowReserve {});
+    }
+    if matches!(buyout_price, Some(price) if price < reserve_price) {
+        fail(AuctionError::BuyoutBelowReserve {});
+    }
+}
+
+/// Validate the configuration and create the state of a new auction owned by `owner`.
+fn new_auction(
+    owner: Address,
+    block_production_time: i64,
+    config: AuctionConfig,
+) -> AuctionContractState {
+    let AuctionConfig {
+        token_amount_for_sale,
+        token_for_sale,
+        token_for_bidding,
+        reserve_price,
+        min_increment,
+        auction_duration_millis,
+        auction_format,
+        price_schedule,
+        settlement_mode,
+        buyout_price,
+        sale_token_id,
+        start_time_millis,
+        royalty_receiver,
+        royalty_bps,
+        fee_collector,
+        fee_bps,
+        claim_deadline_millis,
+        sweep_sink,
+        max_bid_history,
+        observer,
+        min_unique_bidders,
+        reserve_commitment,
+        candle,
+        max_extension_hours,
+        relist,
+        title,
+        description,
+        metadata_uri,
+        sale_token_interface,
+        bidding_token_interface,
+        bidding_balance_shortname,
+        allow_owner_bids,
+        keeper_reward_bps,
+        bid_bond,
+        retraction_penalty_bps,
+        max_escrow_per_bidder,
+        kyc_registry,
+        penny,
+        sealed_bid_deposit,
+        accepted_currencies,
+        delivery_timeout_millis,
+        arbiter,
+        bidding_allowance_shortname,
+        strict_refunds,
+        semi_fungible_sale,
+        bundle,
+        rounds,
+        recurrence,
+        referral_bps,
+        bid_cooldown_millis,
+        tie_policy,
+        instant_win_multiple,
+        recovery_period_millis,
+        reserve_oracle,
+        allow_same_token,
+        end_time_millis,
+        membership_gate,
+        withholdings,
+        decimals_shortname,
+        cancellable_after_bids,
+        setup_window_millis,
+    } = config;
+    if token_for_sale.address_type != AddressType::PublicContract {
+        fail(AuctionError::SaleTokenNotPublicContract {});
+    }
+    if token_for_bidding.address_type != AddressType::PublicContract {
+        fail(AuctionError::BiddingTokenNotPublicContract {});
+    }
+    if token_amount_for_sale == 0 {
+        fail(AuctionError::ZeroSaleAmount {});
+    }
+    if token_for_sale == token_for_bidding && !allow_same_token {
+        fail(AuctionError::SameTokens {});
+    }
+    match (auction_format, &price_schedule) {
+        (ENGLISH, _) => {}
+        (DUTCH, Some(schedule)) => match schedule.curve {
+            DecayCurve::Linear {} => {}
+            DecayCurve::Stepped { interval_millis } => {
+                if interval_millis <= 0 {
+                    fail(AuctionError::InvalidDecayCurve {});
+                }
+            }
+            DecayCurve::Exponential {
+                interval_millis,
+                decay_bps,
+            } => {
+                if interval_millis <= 0 || decay_bps > BASIS_POINTS {
+                    fail(AuctionError::InvalidDecayCurve {});
+                }
+            }
+        },
+        (DUTCH, None) => fail(AuctionError::MissingPriceSchedule {}),
+        (UNIFORM_PRICE | PAY_AS_BID | FIXED_PRICE, _) => {}
+        _ => fail(AuctionError::UnknownAuctionFormat {}),
+    }
+    match (auction_format, settlement_mode) {
+        (_, FIRST_PRICE) => {}
+        (ENGLISH, SECOND_PRICE | ALL_PAY) => {}
+        (DUTCH | UNIFORM_PRICE | PAY_AS_BID | FIXED_PRICE, SECOND_PRICE | ALL_PAY) => {
+            fail(AuctionError::UnsupportedSettlementMode {})
+        }
+        _ => fail(AuctionError::UnknownSettlementMode {}),
+    }
+    if buyout_price.is_some() && auction_format != ENGLISH {
+        fail(AuctionError::UnsupportedBuyout {});
+    }
+    check_terms(
+        auction_format,
+        tie_policy,
+        price_schedule.as_ref(),
+        buyout_price,
+        reserve_price,
+        min_increment,
+    );
+    if reserve_commitment.is_some() && auction_format != ENGLISH {
+        fail(AuctionError::UnsupportedHiddenReserve {});
+    }
+    if semi_fungible_sale && sale_token_id.is_none() {
+        fail(AuctionError::MissingTokenId {});
+    }
+    if sale_token_id.is_some() && token_amount_for_sale != 1 && !semi_fungible_sale {
+        fail(AuctionError::InvalidNftAmount {});
+    }
+    if withholdings
+        .iter()
+        .fold(royalty_bps, |total, withholding| {
+            total.saturating_add(withholding.bps)
+        })
+        .saturating_add(fee_bps)
+        .saturating_add(keeper_reward_bps)
+        .saturating_add(referral_bps)
+        > BASIS_POINTS
+    {
+        fail(AuctionError::SharesExceedProceeds {});
+    }
+    if royalty_bps > 0 && royalty_receiver.is_none() {
+        fail(AuctionError::MissingRoyaltyReceiver {});
+    }
+    if fee_bps > 0 && fee_collector.is_none() {
+        fail(AuctionError::MissingFeeCollector {});
+    }
+    let start_time_millis = start_time_millis.unwrap_or(block_production_time);
+    if start_time_millis < block_production_time {
+        fail(AuctionError::StartTimeInPast {});
+    } else if start_time_millis - block_production_time > MAX_START_DELAY_MILLIS {
+        fail(AuctionError::StartTimeTooFar {});
+    }
+    let auction_duration_millis = match end_time_millis {
+        Some(end_time_millis) if end_time_millis <= start_time_millis => {
+            fail(AuctionError::EndBeforeStart {})
+        }
+        Some(end_time_millis) => end_time_millis - start_time_millis,
+        None => auction_duration_millis,
+    };
+    // A positive duration also keeps the end time strictly after the start time
+    if !(MIN_DURATION_MILLIS..=MAX_DURATION_MILLIS).contains(&auction_duration_millis) {
+        fail(AuctionError::InvalidDuration {});
+    }
+    let end_time_millis = start_time_millis
+        .checked_add(auction_duration_millis)
+        .unwrap_or_else(|| fail(AuctionError::ArithmeticOverflow {}));
+    if let Some(candle) = &candle {
+        if auction_format != ENGLISH
+            || settlement_mode != FIRST_PRICE
+            || buyout_price.is_some()
+            || reserve_commitment.is_some()
+        {
+            fail(AuctionError::UnsupportedCandle {});
+        }
+        if candle.window_millis <= 0 || candle.window_millis > auction_duration_millis {
+            fail(AuctionError::InvalidCandleWindow {});
+        }
+    }
+    if let Some(relist) = &relist {
+        if candle.is_some() || matches!(auction_format, UNIFORM_PRICE | PAY_AS_BID | FIXED_PRICE) {
+            fail(AuctionError::UnsupportedRelist {});
+        }
+        if relist.reserve_decrease_bps > BASIS_POINTS {
+            fail(AuctionError::InvalidReserveDecrease {});
+        }
+    }
+    if let Some(bid_bond) = &bid_bond {
+        if auction_format != ENGLISH
+            || settlement_mode != FIRST_PRICE
+            || buyout_price.is_some()
+            || reserve_commitment.is_some()
+            || candle.is_some()
+            || bidding_balance_shortname.is_some()
+            || bid_bond.payment_window_millis <= 0
+        {
+            fail(AuctionError::InvalidBidBond {});
+        }
+    }
+    if let Some(penny) = &penny {
+        if auction_format != ENGLISH
+            || settlement_mode != FIRST_PRICE
+            || buyout_price.is_some()
+            || reserve_commitment.is_some()
+            || candle.is_some()
+            || bid_bond.is_some()
+            || penny.price_increment == 0
+            || penny.extension_millis < 0
+        {
+            fail(AuctionError::InvalidPennyAuction {});
+        }
+    }
+    if sealed_bid_deposit.is_some()
+        && (auction_format != ENGLISH
+            || settlement_mode != FIRST_PRICE
+            || buyout_price.is_some()
+            || reserve_commitment.is_some()
+            || candle.is_some()
+            || relist.is_some()
+            || bid_bond.is_some()
+            || penny.is_some())
+    {
+        fail(AuctionError::InvalidSealedAuction {});
+    }
+    if !accepted_currencies.is_empty()
+        && (auction_format != ENGLISH
+            || settlement_mode != FIRST_PRICE
+            || candle.is_some()
+            || bid_bond.is_some()
+            || penny.is_some()
+            || sealed_bid_deposit.is_some()
+            || retraction_penalty_bps.is_some()
+            || max_escrow_per_bidder.is_some())
+    {
+        fail(AuctionError::InvalidCurrencies {});
+    }
+    let mut currency_tokens = BTreeSet::new();
+    for currency in &accepted_currencies {
+        if currency.token.address_type != AddressType::PublicContract
+            || currency.token == token_for_bidding
+            || !currency_tokens.insert(currency.token)
+            || matches!(currency.rate, ExchangeRate::Fixed { rate: 0 })
+        {
+            fail(AuctionError::InvalidCurrencies {});
+        }
+    }
+    if let Some(timeout_millis) = delivery_timeout_millis {
+        if !matches!(auction_format, ENGLISH | DUTCH)
+            || !accepted_currencies.is_empty()
+            || timeout_millis <= 0
+        {
+            fail(AuctionError::InvalidDeliveryEscrow {});
+        }
+    }
+    if arbiter.is_some() && delivery_timeout_millis.is_none() {
+        fail(AuctionError::InvalidArbiter {});
+    }
+    if matches!(bid_cooldown_millis, Some(cooldown_millis) if cooldown_millis <= 0) {
+        fail(AuctionError::InvalidBidCooldown {});
+    }
+    if matches!(recovery_period_millis, Some(period_millis) if period_millis <= 0) {
+        fail(AuctionError::InvalidRecoveryPeriod {});
+    }
+    let setup_deadline_millis = setup_window_millis.map(|window_millis| {
+        if window_millis <= 0 {
+            fail(AuctionError::InvalidSetupWindow {});
+        }
+        block_production_time.saturating_add(window_millis)
+    });
+    if reserve_oracle.is_some()
+        && (auction_format != ENGLISH
+            || reserve_commitment.is_some()
+            || !accepted_currencies.is_empty())
+    {
+        fail(AuctionError::InvalidReserveOracle {});
+    }
+    if !bundle.is_empty()
+        && (!matches!(auction_format, ENGLISH | DUTCH)
+            || bundle
+                .iter()
+                .any(|item| item.token.address_type != AddressType::PublicContract))
+    {
+        fail(AuctionError::InvalidBundle {});
+    }
+    if !rounds.is_empty()
+        && (auction_format != ENGLISH
+            || buyout_price.is_some()
+            || reserve_commitment.is_some()
+            || candle.is_some()
+            || relist.is_some()
+            || bid_bond.is_some()
+            || penny.is_some()
+            || sealed_bid_deposit.is_some()
+            || delivery_timeout_millis.is_some()
+            || !bundle.is_empty()
+            || (sale_token_id.is_some() && !semi_fungible_sale)
+            || rounds.iter().any(|round| {
+                !(MIN_DURATION_MILLIS..=MAX_DURATION_MILLIS).contains(&round.duration_millis)
+            }))
+    {
+        fail(AuctionError::InvalidRounds {});
+    }
+    if let Some(recurrence) = &recurrence {
+        if !matches!(auction_format, ENGLISH | DUTCH)
+            || (sale_token_id.is_some() && !semi_fungible_sale)
+            || candle.is_some()
+            || relist.is_some()
+            || bid_bond.is_some()
+            || sealed_bid_deposit.is_some()
+            || delivery_timeout_millis.is_some()
+            || !bundle.is_empty()
+            || !rounds.is_empty()
+            || recurrence.interval_millis < auction_duration_millis
+        {
+            fail(AuctionError::InvalidRecurrence {});
+        }
+    }
+    if matches!(tie_policy, TiePolicy::Split {})
+        && (auction_format != ENGLISH
+            || settlement_mode != FIRST_PRICE
+            || (sale_token_id.is_some() && !semi_fungible_sale)
+            || candle.is_some()
+            || relist.is_some()
+            || bid_bond.is_some()
+            || retraction_penalty_bps.is_some()
+            || penny.is_some()
+            || sealed_bid_deposit.is_some()
+            || !accepted_currencies.is_empty()
+            || delivery_timeout_millis.is_some()
+            || !bundle.is_empty()
+            || !rounds.is_empty()
+            || recurrence.is_some())
+    {
+        fail(AuctionError::InvalidTiePolicy {});
+    }
+    if matches!(instant_win_multiple, Some(multiple) if multiple < 2)
+        || (instant_win_multiple.is_some()
+            && (auction_format != ENGLISH
+                || reserve_price == 0
+                || candle.is_some()
+                || bid_bond.is_some()
+                || penny.is_some()
+                || sealed_bid_deposit.is_some()))
+    {
+        fail(AuctionError::InvalidInstantWin {});
+    }
+    if matches!(retraction_penalty_bps, Some(bps) if bps > BASIS_POINTS) {
+        fail(AuctionError::InvalidRetractionPenalty {});
+    }
+    if matches!(claim_deadline_millis, Some(deadline) if deadline < end_time_millis) {
+        fail(AuctionError::ClaimDeadlineBeforeEnd {});
+    }
+    AuctionContractState {
+        contract_owner: owner,
+        start_time_millis,
+        end_time_millis,
+        token_amount_for_sale,
+        token_for_sale,
+        token_for_bidding,
+        highest_bidder: Bid {
+            bidder: owner,
+            amount: 0,
+        },
+        highest_bidder_max: None,
+        second_highest_bidder: Bid {
+            bidder: owner,
+            amount: 0,
+        },
+        reserve_price,
+        min_increment,
+        claim_map: BTreeMap::new(),
+        status: AuctionStatus::Creation {},
+        auction_format,
+        price_schedule,
+        settlement_mode,
+        buyout_price,
+        sale_token_id,
+        lots: BTreeMap::new(),
+        blocklist: BTreeSet::new(),
+        royalty_receiver,
+        royalty_bps,
+        fee_collector,
+        fee_bps,
+        beneficiaries: vec![],
+        claim_deadline_millis,
+        sweep_sink,
+        bid_history: vec![],
+        max_bid_history,
+        bid_count: 0,
+        observer,
+        participants: BTreeSet::new(),
+        leaderboard: vec![],
+        bidder_stats: BTreeMap::new(),
+        min_unique_bidders,
+        reserve_commitment,
+        candle,
+        candle_bids: vec![],
+        candle_cutoff_millis: None,
+        orders: vec![],
+        pending_owner: None,
+        paused_at_millis: None,
+        max_extension_millis: i64::from(max_extension_hours) * MILLIS_PER_HOUR,
+        total_extension_millis: 0,
+        relist,
+        relist_count: 0,
+        round_duration_millis: auction_duration_millis,
+        title,
+        description,
+        metadata_uri,
+        sale_token_interface,
+        bidding_token_interface,
+        bidding_balance_shortname,
+        allow_owner_bids,
+        keeper_reward_bps,
+        settled_by: None,
+        bid_bond,
+        bonded_bids: BTreeMap::new(),
+        payment_deadline_millis: None,
+        balances: BTreeMap::new(),
+        units_sold: 0,
+        retraction_penalty_bps,
+        max_escrow_per_bidder,
+        kyc_registry,
+        verified_bidders: BTreeSet::new(),
+        all_pay_bids: BTreeMap::new(),
+        penny,
+        sealed_bid_deposit,
+        sealed_commitments: BTreeMap::new(),
+        accepted_currencies,
+        oracle_rates: BTreeMap::new(),
+        highest_bid_currency: None,
+        currency_claims: BTreeMap::new(),
+        delivery_timeout_millis,
+        held_proceeds: 0,
+        arbiter,
+        disputed: false,
+        bidding_allowance_shortname,
+        strict_refunds,
+        semi_fungible_sale,
+        bundle,
+        bundle_claimant: None,
+        rounds,
+        round_index: 0,
+        round_results: vec![],
+        recurrence,
+        repeat_count: 0,
+        referral_bps,
+        referrers: BTreeMap::new(),
+        bid_cooldown_millis,
+        tie_policy,
+        tied_bids: vec![],
+        instant_win_multiple,
+        failed_payouts: BTreeMap::new(),
+        payout_recipients: BTreeMap::new(),
+        bidding_delegates: BTreeMap::new(),
+        proceeds_receiver: None,
+        owner_signers: vec![],
+        approval_threshold: 0,
+        action_approvals: vec![],
+        timelock_millis: 0,
+        pending_actions: vec![],
+        recovery_period_millis,
+        total_escrowed_bidding: 0,
+        total_escrowed_sale: 0,
+        rejected_bids: BTreeMap::new(),
+        reserve_oracle,
+        reference_rate: None,
+        membership_gate,
+        members: BTreeSet::new(),
+        withholdings,
+        decimals_shortname,
+        sale_token_decimals: None,
+        bidding_token_decimals: None,
+        cancellable_after_bids,
+        setup_deadline_millis,
+        start_failed: false,
+    }
+}
+
+#[init]
+pub fn initialize(
+    ctx: ContractContext,
+    config: AuctionConfig,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    (
+        new_auction(ctx.sender, ctx.block_production_time, config),
+        vec![],
+    )
+}
+
+#[action(shortname = 0x01)]
+pub fn start(
+    context: ContractContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    if context.sender != state.contract_owner {
+        fail(AuctionError::NotOwner {});
+    }
+    if !matches!(state.status, AuctionStatus::Creation {}) {
+        fail(AuctionError::NotInCreation {});
+    }
+    if state.setup_expired(context.block_production_time) {
+        fail(AuctionError::SetupExpired {});
+    }
+    let mut new_state = state;
+    new_state.status = AuctionStatus::PendingStart {};
+
+    let mut event_group = EventGroup::builder();
+
+    event_group.with_callback(SHORTNAME_START_CALLBACK).done();
+
+    new_state.transfer_sale_tokens(
+        &mut event_group,
+        Some(context.sender),
+        context.contract_address,
+        new_state.total_tokens_for_sale(),
+    );
+    for item in &new_state.bundle {
+        event_group
+            .call(item.token, token_contract_transfer_from())
+            .argument(context.sender)
+            .argument(context.contract_address)
+            .argument(item.amount_or_id)
+            .done();
+    }
+
+    let mut events = vec![event_group.build()];
+    events.extend(new_state.reserve_rate_event());
+    events.extend(new_state.decimals_event());
+    (new_state, events)
+}
+
+#[callback(shortname = 0x02)]
+pub fn start_callback(
+    ctx: ContractContext,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if !matches!(new_state.status, AuctionStatus::PendingStart {}) {
+        fail(AuctionError::NotPendingStart {});
+    } else if !callback_ctx.success {
+        // Nothing was escrowed, the owner can reset the start and try again
+        new_state.start_failed = true;
+        return (new_state, vec![]);
+    }
+    new_state.total_escrowed_sale = new_state.total_tokens_for_sale();
+    new_state.status = AuctionStatus::BiddingScheduled {};
+    new_state.advance_schedule(ctx.block_production_time);
+    let events = new_state.notify_observer(vec![AuctionNotification::Started {}]);
+    (new_state, events)
+}
+
+/// Return an auction whose start is pending to `Creation` once the escrow of the tokens for
+/// sale failed, so the owner can call `start` again. While the escrow is in flight the start
+/// cannot be reset, so the tokens for sale are never escrowed twice.
+#[action(shortname = 0x57)]
+pub fn reset_start(
+    context: ContractContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if context.sender != new_state.contract_owner {
+        fail(AuctionError::NotOwner {});
+    } else if !matches!(new_state.status, AuctionStatus::PendingStart {}) {
+        fail(AuctionError::NotPendingStart {});
+    } else if !new_state.start_failed {
+        fail(AuctionError::StartNotFailed {});
+    }
+    new_state.status = AuctionStatus::Creation {};
+    new_state.start_failed = false;
+    (new_state, vec![])
+}
+
+#[action(shortname = 0x03)]
+pub fn bid(
+    context: ContractContext,
+    state: AuctionContractState,
+    bid_amount: u128,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    state.check_bidder(context.sender);
+    if state.penny.is_some() {
+        fail(AuctionError::PennyBidRequired {});
+    } else if state.sealed_bid_deposit.is_some() {
+        fail(AuctionError::SealedBidRequired {});
+    } else if state.in_bid_cooldown(context.sender, context.block_production_time) {
+        fail(AuctionError::BidCooldown {});
+    }
+    match state.bid_bond {
+        Some(_) => bid_with_bond(context, state, bid_amount),
+        None => bid_with_escrow(context, state, bid_amount),
+    }
+}
+
+/// Bid in a bid bond auction. A bidder only transfers the bond with their first bid, later
+/// bids of the bidder are accepted right away.
+fn bid_with_bond(
+    context: ContractContext,
+    state: AuctionContractState,
+    bid_amount: u128,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    state.check_bid(context.block_production_time, bid_amount, None);
+    let bid: Bid = Bid {
+        bidder: context.sender,
+        amount: bid_amount,
+    };
+    let bond = state.bid_bond.as_ref().map_or(0, |bid_bond| bid_bond.bond);
+    let mut new_state = state;
+    new_state.advance_schedule(context.block_production_time);
+    if new_state.bonded_bids.contains_key(&context.sender) {
+        let events = new_state.accept_bonded_bid(bid, context.block_production_time);
+        (new_state, events)
+    } else if new_state.draw_balance(context.sender, bond) {
+        // The bond is taken from the deposited balance
+        let events = new_state.accept_bond(bid, context.block_production_time);
+        (new_state, events)
+    } else {
+        // transfer(auctionContract, bond)
+        let mut event_group = EventGroup::builder();
+        new_state.escrow_bidding_tokens(&mut event_group, &context, bond);
+        event_group
+            .with_callback(SHORTNAME_BOND_CALLBACK)
+            .argument(bid)
+            .done();
+        (new_state, vec![event_group.build()])
+    }
+}
+
+/// Bid with the whole bid amount escrowed by the contract. The escrow is taken from the
+/// deposited balance of the bidder if it covers it, and transferred otherwise.
+fn bid_with_escrow(
+    context: ContractContext,
+    state: AuctionContractState,
+    bid_amount: u128,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let bid: Bid = Bid {
+        bidder: context.sender,
+        amount: bid_amount,
+    };
+    // A highest bid held in an accepted currency is replaced by the new bid instead
+    let raises_own_bid =
+        context.sender == state.highest_bidder.bidder && state.highest_bid_currency.is_none();
+    let escrow = if raises_own_bid {
+        // The highest bidder raises their own bid, only escrow the difference
+        if bid_amount <= state.highest_bid_escrow() {
+            fail(AuctionError::RaiseNotHigher {});
+        }
+        let top_up = bid_amount - state.highest_bid_escrow();
+        state.check_bid(context.block_production_time, bid_amount, Some(top_up));
+        top_up
+    } else {
+        state.check_bid(context.block_production_time, bid_amount, None);
+        bid_amount
+    };
+
+    let mut new_state = state;
+    if new_state.draw_balance(context.sender, escrow) {
+        new_state.advance_schedule(context.block_production_time);
+        let events = if raises_own_bid {
+            new_state.accept_top_up(bid, escrow, context.block_production_time)
+        } else {
+            new_state.accept_bid(bid, context.block_production_time)
+        };
+        return (new_state, events);
+    }
+
+    // Potential new bid, create the transfer event
+    let top_up = if raises_own_bid { Some(escrow) } else { None };
+    let event_group = match new_state.bidding_allowance_shortname {
+        Some(shortname) => {
+            // Check that the bidder can cover the escrow before transferring it
+            let mut event_group = EventGroup::builder();
+            event_group
+                .call(new_state.token_for_bidding, Shortname::from_u32(shortname))
+                .argument(context.sender)
+                .argument(context.contract_address)
+                .done();
+            if let Some(shortname) = new_state.bidding_balance_shortname {
+                event_group
+                    .call(new_state.token_for_bidding, Shortname::from_u32(shortname))
+                    .argument(context.sender)
+                    .done();
+            }
+            event_group
+                .with_callback(SHORTNAME_BID_ALLOWANCE_CALLBACK)
+                .argument(bid)
+                .argument(top_up)
+                .done();
+            event_group.build()
+        }
+        None => new_state.escrow_bid_event(context.contract_address, bid, top_up),
+    };
+    (new_state, vec![event_group])
+}
+
+/// Reject a bid whose escrow exceeds the allowance, or the balance, of the bidder returned by
+/// the pre-flight queries, and transfer the escrow otherwise. A failed query does not reject
+/// the bid, the transfer itself decides then.
+#[callback(shortname = 0x3f)]
+pub fn bid_allowance_callback(
+    ctx: ContractContext,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
+    bid: Bid,
+    top_up: Option<u128>,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let escrow = top_up.unwrap_or(bid.amount);
+    let cannot_cover = callback_ctx.results.iter().any(|result| {
+        result.succeeded && u128::rpc_read_from(&mut result.return_data.as_slice()) < escrow
+    });
+    if cannot_cover {
+        fail(AuctionError::CannotCoverBid {});
+    }
+    let event_group = state.escrow_bid_event(ctx.contract_address, bid, top_up);
+    (state, vec![event_group])
+}
+
+#[callback(shortname = 0x04)]
+pub fn bid_callback(
+    ctx: ContractContext,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
+    bid: Bid,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    new_state.advance_schedule(ctx.block_production_time);
+    let bid = Bid {
+        amount: new_state.receive_bidding_tokens(&callback_ctx, bid.amount),
+        ..bid
+    };
+    if !callback_ctx.success {
+        fail(AuctionError::TransferFailed {});
+    }
+    // The bid action only sees bids whose callback ran, bids sent within one cooldown are refunded
+    let in_bid_cooldown = new_state.in_bid_cooldown(bid.bidder, ctx.block_production_time);
+    new_state.record_bidder_stats(bid.bidder, bid.amount, ctx.block_production_time);
+    let (bidder, amount, bid_count) = (bid.bidder, bid.amount, new_state.bid_count);
+    let mut events = if in_bid_cooldown {
+        new_state.refund_invalid_bid(
+            bidder,
+            amount,
+            RejectionReason::Cooldown {},
+            ctx.block_production_time,
+        )
+    } else {
+        new_state.accept_bid(bid, ctx.block_production_time)
+    };
+    let mut event_group = EventGroup::builder();
+    event_group.return_data(new_state.bid_outcome(bidder, amount, bid_count));
+    events.push(event_group.build());
+    (new_state, events)
+}
+#[action(shortname = 0x05)]
+pub fn claim(
+    context: ContractContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    push_claim(state, context.sender)
+}
+
+/// Transfer the claimable tokens of `claimant` to `claimant`, on behalf of anyone. Relayers
+/// or the owner can deliver refunds to bidders who never return to claim them.
+#[action(shortname = 0x2d)]
+pub fn claim_for(
+    context: ContractContext,
+    state: AuctionContractState,
+    claimant: Address,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    push_claim(state, claimant)
+}
+
+/// Transfer the claimable tokens of `claimant` to them, or to their registered payout
+/// recipient, whoever sent the claim.
+fn push_claim(
+    state: AuctionContractState,
+    claimant: Address,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    let recipient = *new_state
+        .payout_recipients
+        .get(&claimant)
+        .unwrap_or(&claimant);
+    let opt_claimable = new_state.claim_map.get(&claimant);
+    let mut events = match opt_claimable {
+        None => vec![],
+        Some(claimable) => {
+            let mut event_group = EventGroup::builder();
+            if claimable.tokens_for_bidding > 0 {
+                event_group
+                    .call(
+                        new_state.token_for_bidding,
+                        new_state.bidding_token_transfer(),
+                    )
+                    .argument(recipient)
+                    .argument(claimable.tokens_for_bidding)
+                    .done();
+            }
+            if claimable.tokens_for_sale > 0 {
+                new_state.transfer_sale_tokens(
+                    &mut event_group,
+                    None,
+                    recipient,
+                    claimable.tokens_for_sale,
+                );
+            }
+            if claimable.tokens_for_bidding > 0 || claimable.tokens_for_sale > 0 {
+                event_group
+                    .with_callback(SHORTNAME_CLAIM_CALLBACK)
+                    .argument(claimant)
+                    .argument(TokenClaim {
+                        tokens_for_bidding: claimable.tokens_for_bidding,
+                        tokens_for_sale: claimable.tokens_for_sale,
+                    })
+                    .done();
+            }
+            new_state.total_escrowed_bidding = new_state
+                .total_escrowed_bidding
+                .saturating_sub(claimable.tokens_for_bidding);
+            new_state.total_escrowed_sale = new_state
+                .total_escrowed_sale
+                .saturating_sub(claimable.tokens_for_sale);
+            new_state.claim_map.insert(
+                claimant,
+                TokenClaim {
+                    tokens_for_bidding: 0,
+                    tokens_for_sale: 0,
+                },
+            );
+            vec![event_group.build()]
+        }
+    };
+    if new_state.bundle_claimant == Some(claimant) {
+        events.push(new_state.transfer_bundle(claimant, recipient));
+    }
+    (new_state, events)
+}
+
+/// Authorize `delegate` to bid on behalf of the sender, replacing any earlier delegate. Naming
+/// the sender revokes the delegation.
+#[action(shortname = 0x45)]
+pub fn delegate_bidding(
+    context: ContractContext,
+    state: AuctionContractState,
+    delegate: Address,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if delegate == context.sender {
+        new_state.bidding_delegates.remove(&context.sender);
+    } else {
+        new_state.bidding_delegates.insert(context.sender, delegate);
+    }
+    (new_state, vec![])
+}
+
+/// Bid `bid_amount` like `bid` as the delegate of `delegator`. The bid is placed by the
+/// delegator: the escrow is taken from them, and refunds and sale tokens are claimed by them.
+#[action(shortname = 0x46)]
+pub fn bid_for(
+    context: ContractContext,
+    state: AuctionContractState,
+    delegator: Address,
+    bid_amount: u128,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    if state.bidding_delegates.get(&delegator) != Some(&context.sender) {
+        fail(AuctionError::NotDelegate {});
+    }
+    let delegator_context = ContractContext {
+        sender: delegator,
+        ..context
+    };
+    bid(delegator_context, state, bid_amount)
+}
+
+/// Register `recipient` as the address the claims of the sender are paid out to, as the
+/// winner or the seller of a settled auction. Claims of the sender made afterwards honor the
+/// recipient.
+#[action(shortname = 0x44)]
+pub fn set_payout_recipient(
+    context: ContractContext,
+    state: AuctionContractState,
+    recipient: Address,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if !new_state.is_finished() {
+        fail(AuctionError::NotFinished {});
+    } else if context.sender != new_state.contract_owner
+        && new_state.winner() != Some(context.sender)
+    {
+        fail(AuctionError::NotPayoutParty {});
+    }
+    if recipient == context.sender {
+        new_state.payout_recipients.remove(&context.sender);
+    } else {
+        new_state
+            .payout_recipients
+            .insert(context.sender, recipient);
+    }
+    (new_state, vec![])
+}
+
+/// Restore the bundle claim of `claimant` if a transfer of the bundle failed.
+#[callback(shortname = 0x40)]
+pub fn claim_bundle_callback(
+    ctx: ContractContext,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
+    claimant: Address,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if !callback_ctx.success {
+        new_state.bundle_claimant = Some(claimant);
+    }
+    (new_state, vec![])
+}
+
+/// Restore the part of a claim whose outgoing transfer failed, so it can be claimed again, and
+/// record the failure in the retry queue.
+#[callback(shortname = 0x13)]
+pub fn claim_callback(
+    ctx: ContractContext,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
+    claimant: Address,
+    claimed: TokenClaim,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if !callback_ctx.success {
+        // The transfers are made in the same order as in claim, one per nonzero amount
+        let mut results = callback_ctx.results.iter();
+        let bidding_failed = claimed.tokens_for_bidding > 0
+            && !results.next().is_some_and(|result| result.succeeded);
+        let sale_failed =
+            claimed.tokens_for_sale > 0 && !results.next().is_some_and(|result| result.succeeded);
+        let failed_bidding_tokens = if bidding_failed {
+            claimed.tokens_for_bidding
+        } else {
+            0
+        };
+        let failed_sale_tokens = if sale_failed {
+            claimed.tokens_for_sale
+        } else {
+            0
+        };
+        new_state.add_to_claim_map(
+            claimant,
+            TokenClaim {
+                tokens_for_bidding: failed_bidding_tokens,
+                tokens_for_sale: failed_sale_tokens,
+            },
+        );
+        new_state.total_escrowed_bidding = new_state
+            .total_escrowed_bidding
+            .saturating_add(failed_bidding_tokens);
+        new_state.total_escrowed_sale = new_state
+            .total_escrowed_sale
+            .saturating_add(failed_sale_tokens);
+        new_state.record_failed_payout(
+            claimant,
+            TokenClaim {
+                tokens_for_bidding: failed_bidding_tokens,
+                tokens_for_sale: failed_sale_tokens,
+            },
+            ctx.block_production_time,
+        );
+    } else {
+        new_state.failed_payouts.remove(&claimant);
+    }
+    (new_state, vec![])
+}
+
+/// Retry the payouts of up to `REFUND_BATCH_SIZE` claimants whose last payout failed, on
+/// behalf of anyone. Claimants stay in the retry queue until a payout to them succeeds, or
+/// they have nothing left to claim.
+#[action(shortname = 0x43)]
+pub fn retry_failed_payouts(
+    context: ContractContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    new_state.failed_payouts.retain(|claimant, _| {
+        new_state.claim_map.get(claimant).is_some_and(|claimable| {
+            claimable.tokens_for_bidding > 0 || claimable.tokens_for_sale > 0
+        })
+    });
+    let claimants: Vec<Address> = new_state
+        .failed_payouts
+        .keys()
+        .take(REFUND_BATCH_SIZE)
+        .copied()
+        .collect();
+    let mut events = vec![];
+    for claimant in claimants {
+        let (claimed_state, claim_events) = push_claim(new_state, claimant);
+        new_state = claimed_state;
+        events.extend(claim_events);
+    }
+    (new_state, events)
+}
+
+#[action(shortname = 0x06)]
+pub fn execute(
+    context: ContractContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    new_state.advance_schedule(context.block_production_time);
+    let reveal_deadline = new_state
+        .end_time_millis
+        .saturating_add(REVEAL_WINDOW_MILLIS);
+    let settling = matches!(new_state.status, AuctionStatus::Settling {});
+    if context.block_production_time < new_state.end_time_millis {
+        fail(AuctionError::BeforeEndTime {});
+    } else if settling
+        && matches!(new_state.payment_deadline_millis, Some(deadline) if context.block_production_time < deadline)
+    {
+        fail(AuctionError::PaymentPending {});
+    } else if settling {
+        // The winner did not pay in time, offer the sale to the next highest bidder
+        let events = new_state.pass_over_winner(context.block_production_time);
+        (new_state, events)
+    } else if new_state.can_repeat() {
+        // A recurring auction settled early, by a buyout or an accepted Dutch price
+        let events = new_state.repeat(context.block_production_time);
+        (new_state, events)
+    } else if !matches!(
+        new_state.status,
+        AuctionStatus::Bidding {} | AuctionStatus::Revealing {}
+    ) {
+        fail(AuctionError::NotBidding {});
+    } else if new_state.sealed_bid_deposit.is_some()
+        && context.block_production_time < reveal_deadline
+    {
+        fail(AuctionError::BidsNotRevealed {});
+    } else if new_state.reserve_commitment.is_some()
+        && context.block_production_time < reveal_deadline
+    {
+        fail(AuctionError::ReserveNotRevealed {});
+    } else if new_state.candle.is_some() && context.block_production_time < reveal_deadline {
+        fail(AuctionError::CandleNotRevealed {});
+    } else if new_state.reserve_commitment.is_some() || new_state.candle.is_some() {
+        // The owner never revealed the hidden reserve price or candle seed, nothing is sold
+        let events = new_state.fail_auction();
+        (new_state, events)
+    } else if new_state.can_relist(context.block_production_time) {
+        let events = new_state.relist(context.block_production_time);
+        (new_state, events)
+    } else if new_state.has_next_round() {
+        new_state.settled_by = Some(context.sender);
+        let events = new_state.advance_round(context.block_production_time);
+        (new_state, events)
+    } else {
+        // Anyone may settle the auction, and is rewarded with a cut of the proceeds
+        new_state.settled_by = Some(context.sender);
+        new_state.forfeit_unrevealed_bids();
+        let mut events = if new_state.bid_bond.is_some() {
+            new_state.await_payment(context.block_production_time)
+        } else {
+            new_state.conclude()
+        };
+        // Only sales are recorded, an auction whose reserve was not met sold nothing
+        if !new_state.rounds.is_empty()
+            && matches!(new_state.status, AuctionStatus::Ended {})
+            && new_state.highest_bidder.amount > 0
+        {
+            new_state.record_round_result();
+        }
+        if new_state.can_repeat() {
+            events.extend(new_state.repeat(context.block_production_time));
+        }
+        (new_state, events)
+    }
+}
+
+/// Expire an auction that was not started within its setup window, on behalf of anyone, so
+/// the dead listing can be cleaned up.
+#[action(shortname = 0x56)]
+pub fn expire(
+    context: ContractContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if !new_state.setup_expired(context.block_production_time) {
+        fail(AuctionError::SetupNotExpired {});
+    }
+    new_state.status = AuctionStatus::Expired {};
+    (new_state, vec![])
+}
+
+/// Cancel the auction before it ends, returning the escrowed tokens, unless a bid was accepted
+/// and the auction is not cancellable after bids. An auction that was never started can be
+/// cancelled at any time, and there is nothing to return.
+#[action(shortname = 0x07)]
+pub fn cancel(
+    context: ContractContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    new_state.advance_schedule(context.block_production_time);
+    if context.sender != new_state.contract_owner {
+        fail(AuctionError::NotOwner {});
+    } else if matches!(new_state.status, AuctionStatus::Creation {}) {
+        new_state.authorize_owner_action(&OwnerAction::Cancel {}, context.block_production_time);
+        new_state.status = AuctionStatus::Cancelled {};
+        let events = new_state.notify_observer(vec![AuctionNotification::Cancelled {}]);
+        (new_state, events)
+    } else if context.block_production_time >= new_state.end_time_millis {
+        fail(AuctionError::AfterEndTime {});
+    } else if !matches!(
+        new_state.status,
+        AuctionStatus::Bidding {} | AuctionStatus::BiddingScheduled {} | AuctionStatus::Paused {}
+    ) {
+        fail(AuctionError::NotBidding {});
+    } else if !new_state.cancellable_after_bids && new_state.bid_count > 0 {
+        fail(AuctionError::CancelAfterBids {});
+    } else {
+        new_state.authorize_owner_action(&OwnerAction::Cancel {}, context.block_production_time);
+        new_state.status = AuctionStatus::Cancelled {};
+        new_state.return_escrow();
+        let events = new_state.notify_observer(vec![AuctionNotification::Cancelled {}]);
+        (new_state, events)
+    }
+}
+
+#[action(shortname = 0x08)]
+pub fn accept_price(
+    context: ContractContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    state.check_bidder(context.sender);
+    if state.auction_format != DUTCH {
+        fail(AuctionError::NotDutchAuction {});
+    }
+    // Accept the current price of the Dutch auction, create the transfer event
+    // transfer(auctionContract, current_price)
+    let price = state.current_price(context.block_production_time);
+    let bid: Bid = Bid {
+        bidder: context.sender,
+        amount: price,
+    };
+
+    let mut event_group = EventGroup::builder();
+    state.escrow_bidding_tokens(&mut event_group, &context, price);
+    event_group
+        .with_callback(SHORTNAME_ACCEPT_PRICE_CALLBACK)
+        .argument(bid)
+        .done();
+    (state, vec![event_group.build()])
+}
+
+#[callback(shortname = 0x09)]
+pub fn accept_price_callback(
+    ctx: ContractContext,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
+    bid: Bid,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    new_state.advance_schedule(ctx.block_production_time);
+    let bid = Bid {
+        amount: new_state.receive_bidding_tokens(&callback_ctx, bid.amount),
+        ..bid
+    };
+    if !callback_ctx.success {
+        fail(AuctionError::TransferFailed {});
+    }
+    let pays_price = bid.amount >= new_state.current_price(ctx.block_production_time);
+    let rejection = new_state.rejection_reason(
+        &bid,
+        new_state.reserve_price,
+        pays_price,
+        ctx.block_production_time,
+    );
+    let mut events = vec![];
+    if let Some(reason) = rejection {
+        // Another bidder accepted first or the auction is over, the payment is refunded
+        events =
+            new_state.refund_invalid_bid(bid.bidder, bid.amount, reason, ctx.block_production_time);
+    } else {
+        new_state.record_bid(bid.bidder, bid.amount, None, ctx.block_production_time);
+        events = new_state.notify_observer(vec![AuctionNotification::Executed {
+            winner: bid.bidder,
+            price: bid.amount,
+        }]);
+        new_state.highest_bidder = bid;
+        new_state.settle();
+    }
+    (new_state, events)
+}
+
+/// Add a lot auctioned besides the main sale, with its own reserve price and increment. A lot
+/// with an end time closes before the auction does, and can be settled right away.
+#[action(shortname = 0x0a)]
+pub fn add_lot(
+    context: ContractContext,
+    state: AuctionContractState,
+    lot_id: LotId,
+    token_amount_for_sale: u128,
+    reserve_price: u128,
+    min_increment: u128,
+    end_time_millis: Option<i64>,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if context.sender != new_state.contract_owner {
+        fail(AuctionError::NotOwner {});
+    } else if !matches!(new_state.status, AuctionStatus::Creation {}) {
+        fail(AuctionError::NotInCreation {});
+    } else if new_state.auction_format != ENGLISH
+        || new_state.sale_token_id.is_some()
+        || new_state.delivery_timeout_millis.is_some()
+        || !new_state.bundle.is_empty()
+    {
+        fail(AuctionError::LotsNotSupported {});
+    } else if new_state.bid_bond.is_some() {
+        fail(AuctionError::BidBondUnsupported {});
+    } else if new_state.settlement_mode == ALL_PAY {
+        fail(AuctionError::AllPayUnsupported {});
+    } else if !new_state.accepted_currencies.is_empty() {
+        fail(AuctionError::MultiCurrencyUnsupported {});
+    } else if new_state.lots.contains_key(&lot_id) {
+        fail(AuctionError::LotAlreadyExists {});
+    } else if matches!(
+        end_time_millis,
+        Some(end) if end <= new_state.start_time_millis || end > new_state.end_time_millis
+    ) {
+        fail(AuctionError::InvalidLotEndTime {});
+    } else {
+        new_state.lots.insert(
+            lot_id,
+            Lot {
+                token_amount_for_sale,
+                highest_bidder: Bid {
+                    bidder: new_state.contract_owner,
+                    amount: 0,
+                },
+                reserve_price,
+                min_increment,
+                end_time_millis,
+            },
+        );
+        (new_state, vec![])
+    }
+}
+
+#[action(shortname = 0x0b)]
+pub fn bid_lot(
+    context: ContractContext,
+    state: AuctionContractState,
+    lot_id: LotId,
+    bid_amount: u128,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    state.check_bidder(context.sender);
+    // Potential new bid on a lot, create the transfer event
+    // transfer(auctionContract, bid_amount)
+
+    let bid: Bid = Bid {
+        bidder: context.sender,
+        amount: bid_amount,
+    };
+
+    let mut event_group = EventGroup::builder();
+    state.escrow_bidding_tokens(&mut event_group, &context, bid_amount);
+    event_group
+        .with_callback(SHORTNAME_BID_LOT_CALLBACK)
+        .argument(lot_id)
+        .argument(bid)
+        .done();
+    (state, vec![event_group.build()])
+}
+
+#[callback(shortname = 0x0c)]
+pub fn bid_lot_callback(
+    ctx: ContractContext,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
+    lot_id: LotId,
+    bid: Bid,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    new_state.advance_schedule(ctx.block_production_time);
+    let bid = Bid {
+        amount: new_state.receive_bidding_tokens(&callback_ctx, bid.amount),
+        ..bid
+    };
+    if !callback_ctx.success {
+        fail(AuctionError::TransferFailed {});
+    }
+    let events = new_state.accept_lot_bid(lot_id, bid, ctx.block_production_time);
+    (new_state, events)
+}
+
+/// Settle a lot whose end time passed while the auction is still running, on behalf of anyone.
+/// The highest bid is credited to the owner, and the tokens of the lot to the highest bidder.
+#[action(shortname = 0x54)]
+pub fn settle_lot(
+    context: ContractContext,
+    state: AuctionContractState,
+    lot_id: LotId,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if !matches!(
+        new_state.status,
+        AuctionStatus::Bidding {} | AuctionStatus::Paused {}
+    ) {
+        fail(AuctionError::NotBidding {});
+    }
+    match new_state.lots.get(&lot_id) {
+        Some(lot) if lot.has_ended(context.block_production_time) => {}
+        _ => fail(AuctionError::LotNotEnded {}),
+    }
+    if let Some(lot) = new_state.lots.remove(&lot_id) {
+        new_state.settle_lot(&lot);
+    }
+    (new_state, vec![])
+}
+
+/// Bid on several lots at once, escrowing the total of the bids in a single transfer. Each bid
+/// is accepted or refunded as if it was placed with `bid_lot`, in the order given.
+#[action(shortname = 0x52)]
+pub fn bid_batch(
+    context: ContractContext,
+    state: AuctionContractState,
+    bids: Vec<LotBid>,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    state.check_bidder(context.sender);
+    if bids.is_empty() {
+        fail(AuctionError::EmptyBatch {});
+    }
+    let total = bids
+        .iter()
+        .fold(0, |total, lot_bid| add_amounts(total, lot_bid.amount));
+    let mut event_group = EventGroup::builder();
+    state.escrow_bidding_tokens(&mut event_group, &context, total);
+    event_group
+        .with_callback(SHORTNAME_BID_BATCH_CALLBACK)
+        .argument(context.sender)
+        .argument(bids)
+        .done();
+    (state, vec![event_group.build()])
+}
+
+/// Apply the escrowed batch of lot bids. A fee taken on the transfer comes out of the last
+/// bids of the batch.
+#[callback(shortname = 0x53)]
+pub fn bid_batch_callback(
+    ctx: ContractContext,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
+    bidder: Address,
+    bids: Vec<LotBid>,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    new_state.advance_schedule(ctx.block_production_time);
+    let total = bids
+        .iter()
+        .fold(0, |total, lot_bid| add_amounts(total, lot_bid.amount));
+    let mut remaining = new_state.receive_bidding_tokens(&callback_ctx, total);
+    if !callback_ctx.success {
+        fail(AuctionError::TransferFailed {});
+    }
+    let mut events = vec![];
+    for lot_bid in bids {
+        let amount = lot_bid.amount.min(remaining);
+        remaining -= amount;
+        let bid = Bid { bidder, amount };
+        events.extend(new_state.accept_lot_bid(lot_bid.lot_id, bid, ctx.block_production_time));
+    }
+    (new_state, events)
+}
+
+#[callback(shortname = 0x0d)]
+pub fn top_up_callback(
+    ctx: ContractContext,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
+    bid: Bid,
+    top_up: u128,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    new_state.advance_schedule(ctx.block_production_time);
+    let received = new_state.receive_bidding_tokens(&callback_ctx, top_up);
+    let bid = Bid {
+        amount: bid.amount - (top_up - received),
+        ..bid
+    };
+    if !callback_ctx.success {
+        fail(AuctionError::TransferFailed {});
+    }
+    let events = new_state.accept_top_up(bid, received, ctx.block_production_time);
+    (new_state, events)
+}
+
+#[action(shortname = 0x0e)]
+pub fn proxy_bid(
+    context: ContractContext,
+    state: AuctionContractState,
+    max_amount: u128,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    state.check_bidder(context.sender);
+    if context.sender == state.highest_bidder.bidder {
+        fail(AuctionError::HighestBidderProxyBid {});
+    }
+    if state.bid_bond.is_some() {
+        fail(AuctionError::BidBondUnsupported {});
+    } else if state.settlement_mode == ALL_PAY {
+        fail(AuctionError::AllPayUnsupported {});
+    } else if state.penny.is_some() {
+        fail(AuctionError::PennyBidRequired {});
+    } else if !state.accepted_currencies.is_empty() {
+        fail(AuctionError::MultiCurrencyUnsupported {});
+    } else if state.sealed_bid_deposit.is_some() {
+        fail(AuctionError::SealedBidRequired {});
+    }
+    // Escrow the maximum the bidder is willing to pay, create the transfer event
+    // transfer(auctionContract, max_amount)
+
+    let bid: Bid = Bid {
+        bidder: context.sender,
+        amount: max_amount,
+    };
+
+    let mut event_group = EventGroup::builder();
+    state.escrow_bidding_tokens(&mut event_group, &context, max_amount);
+    event_group
+        .with_callback(SHORTNAME_PROXY_BID_CALLBACK)
+        .argument(bid)
+        .done();
+    (state, vec![event_group.build()])
+}
+
+#[callback(shortname = 0x0f)]
+pub fn proxy_bid_callback(
+    ctx: ContractContext,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
+    bid: Bid,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    new_state.advance_schedule(ctx.block_production_time);
+    let bid = Bid {
+        amount: new_state.receive_bidding_tokens(&callback_ctx, bid.amount),
+        ..bid
+    };
+    let mut events = vec![];
+    if !callback_ctx.success {
+        fail(AuctionError::TransferFailed {});
+    }
+    let rejection = if new_state.auction_format != ENGLISH || new_state.candle.is_some() {
+        Some(RejectionReason::Closed {})
+    } else {
+        let raises_enough = bid.amount
+            >= new_state
+                .highest_bidder
+                .amount
+                .saturating_add(new_state.min_increment);
+        new_state.rejection_reason(
+            &bid,
+            new_state.reserve_price,
+            raises_enough,
+            ctx.block_production_time,
+        )
+    };
+    if let Some(reason) = rejection {
+        events =
+            new_state.refund_invalid_bid(bid.bidder, bid.amount, reason, ctx.block_production_time);
+    } else {
+        new_state.resolve_bid(bid.bidder, bid.amount, true);
+        new_state.record_bid(bid.bidder, bid.amount, None, ctx.block_production_time);
+        new_state.settle_if_bought_out();
+        events = new_state.notify_observer(new_state.bid_notifications());
+    }
+    (new_state, events)
+}
+
+#[action(shortname = 0x10)]
+pub fn block_bidder(
+    context: ContractContext,
+    state: AuctionContractState,
+    bidder: Address,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if context.sender != new_state.contract_owner {
+        fail(AuctionError::NotOwner {});
+    } else if bidder == new_state.contract_owner {
+        fail(AuctionError::CannotBlockOwner {});
+    } else {
+        new_state.blocklist.insert(bidder);
+        if matches!(
+            new_state.status,
+            AuctionStatus::Bidding {}
+                | AuctionStatus::BiddingScheduled {}
+                | AuctionStatus::Paused {}
+        ) {
+            new_state.refund_highest_bids_of(bidder);
+            if let Some(paid) = new_state.all_pay_bids.remove(&bidder) {
+                new_state.add_to_claim_map(
+                    bidder,
+                    TokenClaim {
+                        tokens_for_bidding: paid,
+                        tokens_for_sale: 0,
+                    },
+                );
+            }
+        }
+        (new_state, vec![])
+    }
+}
+
+#[action(shortname = 0x11)]
+pub fn unblock_bidder(
+    context: ContractContext,
+    state: AuctionContractState,
+    bidder: Address,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if context.sender != new_state.contract_owner {
+        fail(AuctionError::NotOwner {});
+    }
+    new_state.blocklist.remove(&bidder);
+    (new_state, vec![])
+}
+
+#[action(shortname = 0x12)]
+pub fn set_beneficiaries(
+    context: ContractContext,
+    state: AuctionContractState,
+    beneficiaries: Vec<Beneficiary>,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if context.sender != new_state.contract_owner {
+        fail(AuctionError::NotOwner {});
+    } else if !matches!(new_state.status, AuctionStatus::Creation {}) {
+        fail(AuctionError::NotInCreation {});
+    } else if beneficiaries
+        .iter()
+        .any(|beneficiary| beneficiary.weight == 0)
+    {
+        fail(AuctionError::ZeroBeneficiaryWeight {});
+    } else {
+        new_state.beneficiaries = beneficiaries;
+        (new_state, vec![])
+    }
+}
+
+/// Require `approval_threshold` of the `signers` to approve every cancel, sweep of unclaimed
+/// tokens and update of the terms, before the auction is started.
+#[action(shortname = 0x48)]
+pub fn configure_signers(
+    context: ContractContext,
+    state: AuctionContractState,
+    signers: Vec<Address>,
+    approval_threshold: u32,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    let unique_signers: BTreeSet<&Address> = signers.iter().collect();
+    if context.sender != new_state.contract_owner {
+        fail(AuctionError::NotOwner {});
+    } else if !matches!(new_state.status, AuctionStatus::Creation {}) {
+        fail(AuctionError::NotInCreation {});
+    } else if unique_signers.len() != signers.len()
+        || (!signers.is_empty() && approval_threshold == 0)
+        || approval_threshold as usize > signers.len()
+    {
+        fail(AuctionError::InvalidSigners {});
+    }
+    new_state.owner_signers = signers;
+    new_state.approval_threshold = approval_threshold;
+    (new_state, vec![])
+}
+
+/// Approve `action` as a signer of the owner. The action takes effect once the owner performs
+/// it with the approvals of enough signers.
+#[action(shortname = 0x49)]
+pub fn approve_action(
+    context: ContractContext,
+    state: AuctionContractState,
+    action: OwnerAction,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if !new_state.owner_signers.contains(&context.sender) {
+        fail(AuctionError::NotSigner {});
+    }
+    match new_state
+        .action_approvals
+        .iter_mut()
+        .find(|approval| approval.action == action)
+    {
+        Some(approval) if !approval.approvers.contains(&context.sender) => {
+            approval.approvers.push(context.sender);
+        }
+        Some(_) => {}
+        None => new_state.action_approvals.push(ActionApproval {
+            action,
+            approvers: vec![context.sender],
+        }),
+    }
+    (new_state, vec![])
+}
+
+/// Require every cancel, sweep of unclaimed tokens and update of the terms to be proposed
+/// `timelock_millis` before the owner performs it, before the auction is started.
+#[action(shortname = 0x4a)]
+pub fn configure_timelock(
+    context: ContractContext,
+    state: AuctionContractState,
+    timelock_millis: i64,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if context.sender != new_state.contract_owner {
+        fail(AuctionError::NotOwner {});
+    } else if !matches!(new_state.status, AuctionStatus::Creation {}) {
+        fail(AuctionError::NotInCreation {});
+    } else if timelock_millis < 0 {
+        fail(AuctionError::InvalidTimelock {});
+    }
+    new_state.timelock_millis = timelock_millis;
+    (new_state, vec![])
+}
+
+/// Propose `action` as the owner, which can be performed once the timelock has elapsed. The
+/// pending action is kept in the state, so bidders can see it coming. Proposing an action
+/// again restarts its timelock.
+#[action(shortname = 0x4b)]
+pub fn propose_action(
+    context: ContractContext,
+    state: AuctionContractState,
+    action: OwnerAction,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if context.sender != new_state.contract_owner {
+        fail(AuctionError::NotOwner {});
+    }
+    let executable_at_millis = context
+        .block_production_time
+        .saturating_add(new_state.timelock_millis);
+    new_state
+        .pending_actions
+        .retain(|pending| pending.action != action);
+    new_state.pending_actions.push(PendingAction {
+        action,
+        executable_at_millis,
+    });
+    (new_state, vec![])
+}
+
+/// Credit the owner's share of the proceeds to `proceeds_receiver` instead of the owner, such
+/// as a treasury separate from the key operating the auction. `None` credits the owner again.
+#[action(shortname = 0x47)]
+pub fn set_proceeds_receiver(
+    context: ContractContext,
+    state: AuctionContractState,
+    proceeds_receiver: Option<Address>,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if context.sender != new_state.contract_owner {
+        fail(AuctionError::NotOwner {});
+    }
+    new_state.proceeds_receiver = proceeds_receiver;
+    (new_state, vec![])
+}
+
+/// Push the bidding tokens of up to `REFUND_BATCH_SIZE` claims to their owners, so bidders
+/// do not have to claim their refunds themselves. Call again until no refunds are left.
+#[action(shortname = 0x14)]
+pub fn refund_all(
+    context: ContractContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if context.sender != new_state.contract_owner {
+        fail(AuctionError::NotOwner {});
+    } else if !new_state.is_finished() {
+        fail(AuctionError::NotFinished {});
+    }
+    let events = new_state.push_refunds();
+    (new_state, events)
+}
+
+/// Settle an auction the owner left unsettled a recovery period after its end time, even if
+/// it is paused, and push the refunds like `refund_all`. Open to any participant, so funds are
+/// not stranded by an absent owner.
+#[action(shortname = 0x4c)]
+pub fn recover(
+    context: ContractContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    let recoverable = matches!(
+        new_state.recovery_period_millis,
+        Some(period_millis)
+            if context.block_production_time
+                >= new_state.end_time_millis.saturating_add(period_millis)
+    );
+    if !new_state.participants.contains(&context.sender)
+        && !new_state.claim_map.contains_key(&context.sender)
+    {
+        fail(AuctionError::NotParticipant {});
+    } else if !recoverable {
+        fail(AuctionError::RecoveryUnavailable {});
+    }
+    if matches!(new_state.status, AuctionStatus::Paused {}) {
+        new_state.status = AuctionStatus::Bidding {};
+        new_state.paused_at_millis = None;
+    }
+    let mut events = vec![];
+    if matches!(
+        new_state.status,
+        AuctionStatus::Bidding {} | AuctionStatus::Revealing {} | AuctionStatus::Settling {}
+    ) {
+        let (executed_state, execute_events) = execute(context, new_state);
+        new_state = executed_state;
+        events = execute_events;
+    }
+    if new_state.is_finished() {
+        events.extend(new_state.push_refunds());
+    }
+    (new_state, events)
+}
+
+/// Collect every unclaimed token once the claim deadline has passed, and clear the claim map.
+/// The tokens go to the sweep sink, or to the owner if no sink is configured.
+#[action(shortname = 0x15)]
+pub fn sweep_unclaimed(
+    context: ContractContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    let recipient = new_state.sweep_sink.unwrap_or(new_state.contract_owner);
+    if context.sender != new_state.contract_owner && context.sender != recipient {
+        fail(AuctionError::NotOwner {});
+    } else if !new_state.is_finished() {
+        fail(AuctionError::NotFinished {});
+    } else if !matches!(
+        new_state.claim_deadline_millis,
+        Some(deadline) if context.block_production_time >= deadline
+    ) {
+        fail(AuctionError::ClaimDeadlineNotReached {});
+    }
+    new_state.authorize_owner_action(
+        &OwnerAction::SweepUnclaimed {},
+        context.block_production_time,
+    );
+    let mut unclaimed = TokenClaim {
+        tokens_for_bidding: 0,
+        tokens_for_sale: 0,
+    };
+    for claimable in new_state.claim_map.values() {
+        unclaimed.tokens_for_bidding =
+            add_amounts(unclaimed.tokens_for_bidding, claimable.tokens_for_bidding);
+        unclaimed.tokens_for_sale =
+            add_amounts(unclaimed.tokens_for_sale, claimable.tokens_for_sale);
+    }
+    new_state.claim_map.clear();
+    // The swept claims are no longer owed to their claimants, so their payouts are not retried
+    new_state.failed_payouts.clear();
+    new_state.total_escrowed_bidding = new_state
+        .total_escrowed_bidding
+        .saturating_sub(unclaimed.tokens_for_bidding);
+    new_state.total_escrowed_sale = new_state
+        .total_escrowed_sale
+        .saturating_sub(unclaimed.tokens_for_sale);
+    // Unclaimed currencies become claimable by the recipient
+    for (token, amount) in std::mem::take(&mut new_state.currency_claims)
+        .into_values()
+        .flat_map(BTreeMap::into_iter)
+    {
+        new_state.add_currency_claim(recipient, token, amount);
+    }
+    let mut events = vec![];
+    if unclaimed.tokens_for_bidding > 0 || unclaimed.tokens_for_sale > 0 {
+        let mut event_group = EventGroup::builder();
+        if unclaimed.tokens_for_bidding > 0 {
+            event_group
+                .call(
+                    new_state.token_for_bidding,
+                    new_state.bidding_token_transfer(),
+                )
+                .argument(recipient)
+                .argument(unclaimed.tokens_for_bidding)
+                .done();
+        }
+        if unclaimed.tokens_for_sale > 0 {
+            new_state.transfer_sale_tokens(
+                &mut event_group,
+                None,
+                recipient,
+                unclaimed.tokens_for_sale,
+            );
+        }
+        // Failed transfers are restored as a claim of the recipient
+        event_group
+            .with_callback(SHORTNAME_CLAIM_CALLBACK)
+            .argument(recipient)
+            .argument(unclaimed)
+            .done();
+        events.push(event_group.build());
+    }
+    if new_state.bundle_claimant.is_some() {
+        events.push(new_state.transfer_bundle(recipient, recipient));
+    }
+    (new_state, events)
+}
+
+/// Check the books of the auction, leaving the state unchanged: the bidding tokens owed must
+/// equal the tracked bidding token escrow, and the claimable tokens for sale may not exceed
+/// the tracked escrow of tokens for sale. Fails with the diagnostic error otherwise.
+#[action(shortname = 0x4d)]
+pub fn audit(
+    context: ContractContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let claimable_sale_tokens = state.claim_map.values().fold(0, |total, claimable| {
+        add_amounts(total, claimable.tokens_for_sale)
+    });
+    if state.owed_bidding_tokens() != state.total_escrowed_bidding {
+        fail(AuctionError::BiddingBooksUnbalanced {});
+    } else if claimable_sale_tokens > state.total_escrowed_sale {
+        fail(AuctionError::SaleBooksUnbalanced {});
+    }
+    (state, vec![])
+}
+
+/// Return an `AuctionSummary` of the auction as return data, leaving the state unchanged.
+#[action(shortname = 0x16)]
+pub fn summary(
+    context: ContractContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut event_group = EventGroup::builder();
+    event_group.return_data(AuctionSummary {
+        highest_bid: state.highest_bidder.amount,
+        reserve_price: state.reserve_price,
+        min_increment: state.min_increment,
+        time_remaining_millis: (state.end_time_millis - context.block_production_time).max(0),
+        status: state.status,
+        bidding_token_decimals: state.bidding_token_decimals,
+    });
+    (state, vec![event_group.build()])
+}
+
+/// Return an `AuctionSnapshot` of the auction as return data, leaving the state unchanged.
+#[action(shortname = 0x42)]
+pub fn snapshot(
+    context: ContractContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let (claimable_bidding_tokens, claimable_sale_tokens) =
+        state
+            .claim_map
+            .values()
+            .fold((0, 0), |(bidding_tokens, sale_tokens), claimable| {
+                (
+                    add_amounts(bidding_tokens, claimable.tokens_for_bidding),
+                    add_amounts(sale_tokens, claimable.tokens_for_sale),
+                )
+            });
+    let mut event_group = EventGroup::builder();
+    event_group.return_data(AuctionSnapshot {
+        version: SNAPSHOT_VERSION,
+        status: state.status,
+        highest_bidder: state.highest_bidder.bidder,
+        highest_bid: state.highest_bidder.amount,
+        end_time_millis: state.end_time_millis,
+        bid_count: state.bid_count,
+        bidder_count: state.participants.len() as u32,
+        claimable_bidding_tokens,
+        claimable_sale_tokens,
+    });
+    (state, vec![event_group.build()])
+}
+
+/// Reveal the hidden reserve price committed to at initialization and settle the auction. If
+/// the highest bid is below the reserve price, the auction fails and nothing is sold.
+#[action(shortname = 0x17)]
+pub fn reveal_reserve(
+    context: ContractContext,
+    state: AuctionContractState,
+    reserve_price: u128,
+    salt: Hash,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if context.sender != new_state.contract_owner {
+        fail(AuctionError::NotOwner {});
+    } else if context.block_production_time < new_state.end_time_millis {
+        fail(AuctionError::BeforeEndTime {});
+    } else if !matches!(new_state.status, AuctionStatus::Bidding {}) {
+        fail(AuctionError::NotBidding {});
+    }
+    match new_state.reserve_commitment {
+        None => fail(AuctionError::NoHiddenReserve {}),
+        Some(commitment) if commitment != reserve_commitment(reserve_price, salt) => {
+            fail(AuctionError::InvalidReserveReveal {})
+        }
+        Some(_) => {
+            new_state.reserve_commitment = None;
+            new_state.reserve_price = new_state.reserve_price.max(reserve_price);
+            let events = if new_state.highest_bidder.amount < new_state.reserve_price {
+                new_state.fail_auction()
+            } else {
+                new_state.conclude()
+            };
+            (new_state, events)
+        }
+    }
+}
+
+/// Reveal the candle seed committed to at initialization and settle the auction. The cutoff
+/// is the start of the closing window plus the first eight bytes of the seed, modulo the window
+/// length, and only bids placed at or before the cutoff count.
+#[action(shortname = 0x18)]
+pub fn reveal_candle(
+    context: ContractContext,
+    state: AuctionContractState,
+    seed: Hash,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if context.sender != new_state.contract_owner {
+        fail(AuctionError::NotOwner {});
+    } else if context.block_production_time < new_state.end_time_millis {
+        fail(AuctionError::BeforeEndTime {});
+    } else if !matches!(new_state.status, AuctionStatus::Bidding {}) {
+        fail(AuctionError::NotBidding {});
+    }
+    let window_millis = match &new_state.candle {
+        None => fail(AuctionError::NoCandle {}),
+        Some(candle) if candle.seed_commitment != sha256(&seed) => {
+            fail(AuctionError::InvalidCandleReveal {})
+        }
+        Some(candle) => candle.window_millis,
+    };
+    let mut offset_bytes = [0u8; 8];
+    offset_bytes.copy_from_slice(&seed[..8]);
+    let offset_millis = u64::from_be_bytes(offset_bytes) % window_millis as u64;
+    let cutoff_millis = new_state.end_time_millis - window_millis + offset_millis as i64;
+    new_state.candle_cutoff_millis = Some(cutoff_millis);
+    new_state.resolve_candle(cutoff_millis);
+    let events = new_state.conclude();
+    (new_state, events)
+}
+
+/// Place an order for `quantity` units at `unit_price` each in a multi-unit auction. The full
+/// `quantity * unit_price` is escrowed, and whatever is not paid for is refunded at settlement.
+#[action(shortname = 0x19)]
+pub fn place_order(
+    context: ContractContext,
+    state: AuctionContractState,
+    quantity: u128,
+    unit_price: u128,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    state.check_bidder(context.sender);
+    if !state.is_multi_unit() {
+        fail(AuctionError::NotMultiUnitAuction {});
+    }
+    // transfer(auctionContract, quantity * unit_price)
+    let order = Order {
+        bidder: context.sender,
+        quantity,
+        unit_price,
+    };
+
+    let mut event_group = EventGroup::builder();
+    state.escrow_bidding_tokens(&mut event_group, &context, order.escrow());
+    event_group
+        .with_callback(SHORTNAME_PLACE_ORDER_CALLBACK)
+        .argument(order)
+        .done();
+    (state, vec![event_group.build()])
+}
+
+#[callback(shortname = 0x1a)]
+pub fn place_order_callback(
+    ctx: ContractContext,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
+    order: Order,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    new_state.advance_schedule(ctx.block_production_time);
+    let received = new_state.receive_bidding_tokens(&callback_ctx, order.escrow());
+    let fixed_price = new_state.auction_format == FIXED_PRICE;
+    if !callback_ctx.success {
+        fail(AuctionError::TransferFailed {});
+    }
+    let bid = Bid {
+        bidder: order.bidder,
+        amount: order.unit_price,
+    };
+    let rejection = if !new_state.is_multi_unit() {
+        Some(RejectionReason::Closed {})
+    } else {
+        new_state
+            .rejection_reason(
+                &bid,
+                new_state.reserve_price,
+                true,
+                ctx.block_production_time,
+            )
+            .or_else(|| {
+                if order.quantity == 0
+                    || (order.quantity > new_state.token_amount_for_sale && !fixed_price)
+                {
+                    Some(RejectionReason::InvalidOrder {})
+                } else if received < order.escrow() {
+                    Some(RejectionReason::Underpaid {})
+                } else {
+                    None
+                }
+            })
+    };
+    let mut events = vec![];
+    if let Some(reason) = rejection {
+        events =
+            new_state.refund_invalid_bid(order.bidder, received, reason, ctx.block_production_time);
+    } else if fixed_price {
+        events = new_state.fill_order(order, ctx.block_production_time);
+    } else {
+        new_state.record_bid(
+            order.bidder,
+            order.escrow(),
+            None,
+            ctx.block_production_time,
+        );
+        new_state.orders.push(order);
+    }
+    (new_state, events)
+}
+
+/// Propose a new contract owner. The ownership is only transferred once the proposed owner
+/// accepts it, so a mistyped address can never take control of the auction.
+#[action(shortname = 0x1b)]
+pub fn propose_new_owner(
+    context: ContractContext,
+    state: AuctionContractState,
+    new_owner: Address,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if context.sender != new_state.contract_owner {
+        fail(AuctionError::NotOwner {});
+    }
+    new_state.pending_owner = Some(new_owner);
+    (new_state, vec![])
+}
+
+/// Accept the ownership proposed by `propose_new_owner`, making the sender the contract owner.
+#[action(shortname = 0x1c)]
+pub fn accept_ownership(
+    context: ContractContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if new_state.pending_owner != Some(context.sender) {
+        fail(AuctionError::NotPendingOwner {});
+    }
+    new_state.contract_owner = context.sender;
+    new_state.pending_owner = None;
+    new_state.blocklist.remove(&context.sender);
+    (new_state, vec![])
+}
+
+/// Pause the bidding, for instance while the bidding token is being investigated. Bids
+/// arriving while the auction is paused are refunded.
+#[action(shortname = 0x1d)]
+pub fn pause(
+    context: ContractContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    new_state.advance_schedule(context.block_production_time);
+    if context.sender != new_state.contract_owner {
+        fail(AuctionError::NotOwner {});
+    } else if context.block_production_time >= new_state.end_time_millis {
+        fail(AuctionError::AfterEndTime {});
+    } else if !matches!(new_state.status, AuctionStatus::Bidding {}) {
+        fail(AuctionError::NotBidding {});
+    }
+    new_state.status = AuctionStatus::Paused {};
+    new_state.paused_at_millis = Some(context.block_production_time);
+    let events = new_state.notify_observer(vec![AuctionNotification::Paused {}]);
+    (new_state, events)
+}
+
+/// Resume the bidding of a paused auction. If `extend_end_time` is set, the end time is pushed
+/// back by the time the auction spent paused.
+#[action(shortname = 0x1e)]
+pub fn resume(
+    context: ContractContext,
+    state: AuctionContractState,
+    extend_end_time: bool,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if context.sender != new_state.contract_owner {
+        fail(AuctionError::NotOwner {});
+    }
+    let paused_at_millis = match (new_state.status, new_state.paused_at_millis) {
+        (AuctionStatus::Paused {}, Some(paused_at_millis)) => paused_at_millis,
+        _ => fail(AuctionError::NotPaused {}),
+    };
+    if extend_end_time {
+        new_state.end_time_millis = new_state
+            .end_time_millis
+            .checked_add(context.block_production_time - paused_at_millis)
+            .unwrap_or_else(|| fail(AuctionError::ArithmeticOverflow {}));
+    }
+    new_state.status = AuctionStatus::Bidding {};
+    new_state.paused_at_millis = None;
+    let events = new_state.notify_observer(vec![AuctionNotification::Resumed {
+        end_time_millis: new_state.end_time_millis,
+    }]);
+    (new_state, events)
+}
+
+/// Push back the end time of the auction by `extra_hours`. The extensions of an auction may
+/// not add up to more than the maximum total extension set at initialization.
+#[action(shortname = 0x1f)]
+pub fn extend_auction(
+    context: ContractContext,
+    state: AuctionContractState,
+    extra_hours: u32,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    new_state.advance_schedule(context.block_production_time);
+    let extension_millis = i64::from(extra_hours) * MILLIS_PER_HOUR;
+    let total_extension_millis = new_state.total_extension_millis + extension_millis;
+    if context.sender != new_state.contract_owner {
+        fail(AuctionError::NotOwner {});
+    } else if context.block_production_time >= new_state.end_time_millis {
+        fail(AuctionError::AfterEndTime {});
+    } else if !matches!(new_state.status, AuctionStatus::Bidding {}) {
+        fail(AuctionError::NotBidding {});
+    } else if total_extension_millis > new_state.max_extension_millis {
+        fail(AuctionError::ExtensionExceedsMaximum {});
+    } else if new_state.in_candle_window(context.block_production_time) {
+        fail(AuctionError::CandleWindowOpen {});
+    }
+    let end_time_millis = new_state
+        .end_time_millis
+        .checked_add(extension_millis)
+        .unwrap_or_else(|| fail(AuctionError::ArithmeticOverflow {}));
+    if matches!(new_state.claim_deadline_millis, Some(deadline) if deadline < end_time_millis) {
+        fail(AuctionError::ClaimDeadlineBeforeEnd {});
+    }
+    new_state.end_time_millis = end_time_millis;
+    new_state.total_extension_millis = total_extension_millis;
+    (new_state, vec![])
+}
+
+/// Correct the reserve price and minimum increment of the auction, as long as no bid has been
+/// placed yet.
+#[action(shortname = 0x20)]
+pub fn update_terms(
+    context: ContractContext,
+    state: AuctionContractState,
+    reserve_price: u128,
+    min_increment: u128,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if context.sender != new_state.contract_owner {
+        fail(AuctionError::NotOwner {});
+    } else if new_state.is_finished() || matches!(new_state.status, AuctionStatus::PendingStart {})
+    {
+        fail(AuctionError::NotBidding {});
+    } else if new_state.bid_count > 0 {
+        fail(AuctionError::BidAlreadyPlaced {});
+    }
+    check_terms(
+        new_state.auction_format,
+        new_state.tie_policy,
+        new_state.price_schedule.as_ref(),
+        new_state.buyout_price,
+        reserve_price,
+        min_increment,
+    );
+    new_state.authorize_owner_action(
+        &OwnerAction::UpdateTerms {
+            reserve_price,
+            min_increment,
+        },
+        context.block_production_time,
+    );
+    new_state.reserve_price = reserve_price;
+    new_state.min_increment = min_increment;
+    (new_state, vec![])
+}
+
+/// Re-parameterize an auction that has not been started yet from a fresh `AuctionConfig`.
+/// The owner, lots, blocklist and beneficiaries are kept.
+#[action(shortname = 0x21)]
+pub fn configure(
+    context: ContractContext,
+    state: AuctionContractState,
+    config: AuctionConfig,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    if context.sender != state.contract_owner {
+        fail(AuctionError::NotOwner {});
+    } else if !matches!(state.status, AuctionStatus::Creation {}) {
+        fail(AuctionError::NotInCreation {});
+    }
+    let mut new_state = new_auction(state.contract_owner, context.block_production_time, config);
+    new_state.pending_owner = state.pending_owner;
+    new_state.lots = state.lots;
+    new_state.blocklist = state.blocklist;
+    new_state.beneficiaries = state.beneficiaries;
+    new_state.owner_signers = state.owner_signers;
+    new_state.approval_threshold = state.approval_threshold;
+    new_state.timelock_millis = state.timelock_millis;
+    // Verifications only carry over while the same registry is asked
+    if matches!(
+        (&state.kyc_registry, &new_state.kyc_registry),
+        (Some(old), Some(new)) if old.address == new.address
+    ) {
+        new_state.verified_bidders = state.verified_bidders;
+    }
+    if matches!(
+        (&state.membership_gate, &new_state.membership_gate),
+        (Some(old), Some(new)) if old.address == new.address
+    ) {
+        new_state.members = state.members;
+    }
+    (new_state, vec![])
+}
+
+/// Update the title, description and metadata URI front ends render the listing from.
+#[action(shortname = 0x22)]
+pub fn update_metadata(
+    context: ContractContext,
+    state: AuctionContractState,
+    title: Option<String>,
+    description: Option<String>,
+    metadata_uri: Option<String>,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if context.sender != new_state.contract_owner {
+        fail(AuctionError::NotOwner {});
+    } else if !matches!(new_state.status, AuctionStatus::Creation {}) {
+        fail(AuctionError::NotInCreation {});
+    }
+    new_state.title = title;
+    new_state.description = description;
+    new_state.metadata_uri = metadata_uri;
+    (new_state, vec![])
+}
+
+#[callback(shortname = 0x23)]
+pub fn bond_callback(
+    ctx: ContractContext,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
+    bid: Bid,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    new_state.advance_schedule(ctx.block_production_time);
+    if !callback_ctx.success {
+        fail(AuctionError::TransferFailed {});
+    }
+    let bond = new_state
+        .bid_bond
+        .as_ref()
+        .map_or(0, |bid_bond| bid_bond.bond);
+    new_state.total_escrowed_bidding = new_state.total_escrowed_bidding.saturating_add(bond);
+    let events = new_state.accept_bond(bid, ctx.block_production_time);
+    (new_state, events)
+}
+
+/// Pay the rest of the winning bid of a bid bond auction, completing the sale.
+#[action(shortname = 0x24)]
+pub fn complete_payment(
+    context: ContractContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    if !matches!(state.status, AuctionStatus::Settling {}) {
+        fail(AuctionError::NotSettling {});
+    } else if context.sender != state.highest_bidder.bidder {
+        fail(AuctionError::NotWinner {});
+    } else if matches!(state.payment_deadline_millis, Some(deadline) if context.block_production_time >= deadline)
+    {
+        fail(AuctionError::PaymentDeadlinePassed {});
+    }
+    // transfer(auctionContract, winning_bid - bond)
+    let bond = state.bid_bond.as_ref().map_or(0, |bid_bond| bid_bond.bond);
+    let payment = state.highest_bidder.amount.saturating_sub(bond);
+    let winning_bid = Bid {
+        bidder: context.sender,
+        amount: state.highest_bidder.amount,
+    };
+    let mut event_group = EventGroup::builder();
+    state.escrow_bidding_tokens(&mut event_group, &context, payment);
+    event_group
+        .with_callback(SHORTNAME_COMPLETE_PAYMENT_CALLBACK)
+        .argument(winning_bid)
+        .argument(payment)
+        .done();
+    (state, vec![event_group.build()])
+}
+
+#[callback(shortname = 0x25)]
+pub fn complete_payment_callback(
+    ctx: ContractContext,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
+    winning_bid: Bid,
+    payment: u128,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    let mut events = vec![];
+    if !callback_ctx.success {
+        fail(AuctionError::TransferFailed {});
+    }
+    new_state.total_escrowed_bidding = new_state.total_escrowed_bidding.saturating_add(payment);
+    if !matches!(new_state.status, AuctionStatus::Settling {})
+        || new_state.highest_bidder.bidder != winning_bid.bidder
+        || new_state.highest_bidder.amount != winning_bid.amount
+    {
+        // The winner was passed over in the meantime, the payment can be claimed back
+        new_state.add_to_claim_map(
+            winning_bid.bidder,
+            TokenClaim {
+                tokens_for_bidding: payment,
+                tokens_for_sale: 0,
+            },
+        );
+    } else {
+        // The bond and the payment make up the winning bid, the other bonds are refunded
+        new_state.bonded_bids.remove(&winning_bid.bidder);
+        for bidder in std::mem::take(&mut new_state.bonded_bids).into_keys() {
+            new_state.refund_bond(bidder);
+        }
+        new_state.payment_deadline_millis = None;
+        events = new_state.notify_observer(vec![AuctionNotification::Executed {
+            winner: winning_bid.bidder,
+            price: winning_bid.amount,
+        }]);
+        new_state.settle();
+    }
+    (new_state, events)
+}
+
+/// Deposit bidding tokens into the internal balance of the sender. Bids are escrowed from the
+/// balance without a transfer while it covers them.
+#[action(shortname = 0x26)]
+pub fn deposit(
+    context: ContractContext,
+    state: AuctionContractState,
+    amount: u128,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    if matches!(state.status, AuctionStatus::Creation {}) {
+        fail(AuctionError::DepositBeforeStart {});
+    } else if matches!(state.status, AuctionStatus::Finalized {}) {
+        fail(AuctionError::AuctionFinalized {});
+    }
+    // transfer(auctionContract, amount)
+    let mut event_group = EventGroup::builder();
+    state.escrow_bidding_tokens(&mut event_group, &context, amount);
+    event_group
+        .with_callback(SHORTNAME_DEPOSIT_CALLBACK)
+        .argument(context.sender)
+        .argument(amount)
+        .done();
+    (state, vec![event_group.build()])
+}
+
+#[callback(shortname = 0x27)]
+pub fn deposit_callback(
+    ctx: ContractContext,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
+    depositor: Address,
+    amount: u128,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if !callback_ctx.success {
+        fail(AuctionError::TransferFailed {});
+    }
+    let received = new_state.receive_bidding_tokens(&callback_ctx, amount);
+    let balance = new_state.balances.entry(depositor).or_insert(0);
+    *balance = balance.saturating_add(received);
+    new_state.advance_schedule(ctx.block_production_time);
+    (new_state, vec![])
+}
+
+/// Withdraw bidding tokens from the internal balance of the sender.
+#[action(shortname = 0x28)]
+pub fn withdraw(
+    context: ContractContext,
+    state: AuctionContractState,
+    amount: u128,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if !new_state.draw_balance(context.sender, amount) {
+        fail(AuctionError::InsufficientBalance {});
+    }
+    new_state.total_escrowed_bidding = new_state.total_escrowed_bidding.saturating_sub(amount);
+    let mut event_group = EventGroup::builder();
+    event_group
+        .call(
+            new_state.token_for_bidding,
+            new_state.bidding_token_transfer(),
+        )
+        .argument(context.sender)
+        .argument(amount)
+        .done();
+    event_group
+        .with_callback(SHORTNAME_WITHDRAW_CALLBACK)
+        .argument(context.sender)
+        .argument(amount)
+        .done();
+    (new_state, vec![event_group.build()])
+}
+
+/// Restore the withdrawn amount to the balance if the outgoing transfer failed.
+#[callback(shortname = 0x29)]
+pub fn withdraw_callback(
+    ctx: ContractContext,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
+    withdrawer: Address,
+    amount: u128,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if !callback_ctx.success {
+        let balance = new_state.balances.entry(withdrawer).or_insert(0);
+        *balance = balance.saturating_add(amount);
+        new_state.total_escrowed_bidding = new_state.total_escrowed_bidding.saturating_add(amount);
+    }
+    (new_state, vec![])
+}
+
+/// Retract the bids of the sender before the end of the auction. Bids that are not winning,
+/// such as orders or candle bids, are refunded in full. A retracted highest bid forfeits the
+/// retraction penalty to the owner.
+#[action(shortname = 0x2a)]
+pub fn retract_bid(
+    context: ContractContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    new_state.advance_schedule(context.block_production_time);
+    let penalty_bps = new_state
+        .retraction_penalty_bps
+        .unwrap_or_else(|| fail(AuctionError::RetractionDisabled {}));
+    if !matches!(new_state.status, AuctionStatus::Bidding {}) {
+        fail(AuctionError::NotBidding {});
+    } else if context.block_production_time >= new_state.end_time_millis {
+        fail(AuctionError::AfterEndTime {});
+    }
+    let bidder = context.sender;
+    let mut highest_bids = new_state
+        .lots
+        .values()
+        .filter(|lot| lot.highest_bidder.bidder == bidder)
+        .fold(0, |sum, lot| add_amounts(sum, lot.highest_bidder.amount));
+    if new_state.highest_bidder.bidder == bidder {
+        highest_bids = add_amounts(highest_bids, new_state.highest_bidder.amount);
+    }
+    let claimable = |state: &AuctionContractState| {
+        state
+            .claim_map
+            .get(&bidder)
+            .map_or(0, |claim| claim.tokens_for_bidding)
+    };
+    // While bidding, no payout recipient is registered, so every refund is credited to the
+    // claim of the bidder
+    let claimable_before = claimable(&new_state);
+    new_state.refund_highest_bids_of(bidder);
+    let refunded = claimable(&new_state) - claimable_before;
+    if refunded == 0 {
+        fail(AuctionError::NoBidToRetract {});
+    }
+    // A bid bond auction only escrows the bond, which caps the penalty
+    let penalty = mul_div(
+        highest_bids,
+        u128::from(penalty_bps),
+        u128::from(BASIS_POINTS),
+    )
+    .min(refunded);
+    if penalty > 0 {
+        if let Some(claim) = new_state.claim_map.get_mut(&bidder) {
+            claim.tokens_for_bidding -= penalty;
+        }
+        new_state.add_to_claim_map(
+            new_state.contract_owner,
+            TokenClaim {
+                tokens_for_bidding: penalty,
+                tokens_for_sale: 0,
+            },
+        );
+    }
+    let events =
+        new_state.notify_observer(vec![AuctionNotification::BidRetracted { bidder, penalty }]);
+    (new_state, events)
+}
+
+/// Ask the KYC registry whether the sender is verified. A verified sender is remembered, and
+/// may bid from then on.
+#[action(shortname = 0x2b)]
+pub fn verify_bidder(
+    context: ContractContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let registry = state
+        .kyc_registry
+        .as_ref()
+        .unwrap_or_else(|| fail(AuctionError::NoKycRegistry {}));
+    let mut event_group = EventGroup::builder();
+    event_group
+        .call(
+            registry.address,
+            Shortname::from_u32(registry.is_verified_shortname),
+        )
+        .argument(context.sender)
+        .done();
+    event_group
+        .with_callback(SHORTNAME_VERIFY_BIDDER_CALLBACK)
+        .argument(context.sender)
+        .done();
+    (state, vec![event_group.build()])
+}
+
+#[callback(shortname = 0x2c)]
+pub fn verify_bidder_callback(
+    ctx: ContractContext,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
+    bidder: Address,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    let verified = matches!(
+        callback_ctx.results.first(),
+        Some(result) if result.succeeded && bool::rpc_read_from(&mut result.return_data.as_slice())
+    );
+    if verified {
+        new_state.verified_bidders.insert(bidder);
+    }
+    (new_state, vec![])
+}
+
+/// Ask the membership token whether the sender holds a token. A holder is remembered, and may
+/// bid from then on, even if they part with the token later.
+#[action(shortname = 0x50)]
+pub fn verify_membership(
+    context: ContractContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let gate = state
+        .membership_gate
+        .as_ref()
+        .unwrap_or_else(|| fail(AuctionError::NoMembershipGate {}));
+    let mut event_group = EventGroup::builder();
+    event_group
+        .call(gate.address, Shortname::from_u32(gate.balance_shortname))
+        .argument(context.sender)
+        .done();
+    event_group
+        .with_callback(SHORTNAME_VERIFY_MEMBERSHIP_CALLBACK)
+        .argument(context.sender)
+        .done();
+    (state, vec![event_group.build()])
+}
+
+#[callback(shortname = 0x51)]
+pub fn verify_membership_callback(
+    ctx: ContractContext,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
+    bidder: Address,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    let holds_token = match callback_ctx.results.first() {
+        Some(result) if result.succeeded => {
+            u128::rpc_read_from(&mut result.return_data.as_slice()) > 0
+        }
+        _ => false,
+    };
+    if holds_token {
+        new_state.members.insert(bidder);
+    }
+    (new_state, vec![])
+}
+
+/// Clear the residual state of a finished auction once every claim and deposited balance was
+/// paid out, so the contract can be archived. No tokens can be deposited from then on.
+#[action(shortname = 0x2e)]
+pub fn finalize(
+    context: ContractContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if context.sender != new_state.contract_owner {
+        fail(AuctionError::NotOwner {});
+    } else if !new_state.is_finished() {
+        fail(AuctionError::NotFinished {});
+    } else if new_state
+        .claim_map
+        .values()
+        .any(|claimable| claimable.tokens_for_bidding > 0 || claimable.tokens_for_sale > 0)
+        || new_state.balances.values().any(|balance| *balance > 0)
+        || !new_state.currency_claims.is_empty()
+        || new_state.held_proceeds > 0
+        || new_state.bundle_claimant.is_some()
+    {
+        fail(AuctionError::ClaimsOutstanding {});
+    }
+    new_state.status = AuctionStatus::Finalized {};
+    new_state.claim_map.clear();
+    new_state.balances.clear();
+    new_state.bid_history.clear();
+    new_state.leaderboard.clear();
+    new_state.participants.clear();
+    new_state.verified_bidders.clear();
+    new_state.members.clear();
+    new_state.failed_payouts.clear();
+    new_state.rejected_bids.clear();
+    (new_state, vec![])
+}
+
+/// Bid `bid_amount` like `bid`, naming the `referrer` who brought the sender to the auction.
+/// The first referrer named by a bidder is kept, and is rewarded with a share of the proceeds
+/// if the bidder wins.
+#[action(shortname = 0x41)]
+pub fn bid_with_referrer(
+    context: ContractContext,
+    state: AuctionContractState,
+    bid_amount: u128,
+    referrer: Address,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    if referrer == context.sender {
+        fail(AuctionError::InvalidReferrer {});
+    }
+    let mut new_state = state;
+    new_state
+        .referrers
+        .entry(context.sender)
+        .or_insert(referrer);
+    bid(context, new_state, bid_amount)
+}
+
+/// Bid on a penny auction at the next price, paying the bid fee. The fee and the price are
+/// taken from the deposited balance if it covers them, and transferred otherwise.
+#[action(shortname = 0x2f)]
+pub fn penny_bid(
+    context: ContractContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    state.check_bidder(context.sender);
+    let penny = state
+        .penny
+        .as_ref()
+        .unwrap_or_else(|| fail(AuctionError::NotPennyAuction {}));
+    let scheduled_start = matches!(state.status, AuctionStatus::BiddingScheduled {})
+        && context.block_production_time >= state.start_time_millis;
+    if !matches!(state.status, AuctionStatus::Bidding {}) && !scheduled_start {
+        fail(AuctionError::NotBidding {});
+    } else if context.block_production_time >= state.end_time_millis {
+        fail(AuctionError::AfterEndTime {});
+    }
+    let bid = Bid {
+        bidder: context.sender,
+        amount: state.next_penny_price(penny),
+    };
+    let payment = add_amounts(bid.amount, penny.bid_fee);
+    let mut new_state = state;
+    new_state.advance_schedule(context.block_production_time);
+    if new_state.draw_balance(context.sender, payment) {
+        let events = new_state.accept_penny_bid(bid, payment, context.block_production_time);
+        return (new_state, events);
     }
+    // transfer(auctionContract, price + bid_fee)
+    let mut event_group = EventGroup::builder();
+    new_state.escrow_bidding_tokens(&mut event_group, &context, payment);
+    event_group
+        .with_callback(SHORTNAME_PENNY_BID_CALLBACK)
+        .argument(bid)
+        .argument(payment)
+        .done();
+    (new_state, vec![event_group.build()])
 }
 
-
-#[init]
-pub fn initialize(
+#[callback(shortname = 0x30)]
+pub fn penny_bid_callback(
     ctx: ContractContext,
-    token_amount_for_sale: u128,
-    token_for_sale: Address,
-    token_for_bidding: Address,
-    reserve_price: u128,
-    min_increment: u128,
-    auction_duration_hours: u32,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
+    bid: Bid,
+    payment: u128,
 ) -> (AuctionContractState, Vec<EventGroup>) {
-    if token_for_sale.address_type != AddressType::PublicContract {
-        panic!("Tried to create a contract selling a non publicContract token");
-    }
-    if token_for_bidding.address_type != AddressType::PublicContract {
-        panic!("Tried to create a contract buying a non publicContract token");
+    let mut new_state = state;
+    new_state.advance_schedule(ctx.block_production_time);
+    let received = new_state.receive_bidding_tokens(&callback_ctx, payment);
+    if !callback_ctx.success {
+        fail(AuctionError::TransferFailed {});
     }
-    let duration_millis = i64::from(auction_duration_hours) * 60 * 60 * 1000;
-    let end_time_millis = ctx.block_production_time + duration_millis;
-    let state = AuctionContractState {
-        contract_owner: ctx.sender,
-        start_time_millis: ctx.block_production_time,
-        end_time_millis,
-        token_amount_for_sale,
-        token_for_sale,
-        token_for_bidding,
-        highest_bidder: Bid {
-            bidder: ctx.sender,
-            amount: 0,
-        },
-        reserve_price,
-        min_increment,
-        claim_map: BTreeMap::new(),
-        status: CREATION,
-    };
-
-    (state, vec![])
+    let events = new_state.accept_penny_bid(bid, received, ctx.block_production_time);
+    (new_state, events)
 }
 
-#[action(shortname = 0x01)]
-pub fn start(
+/// Commit to a sealed bid by its commitment, escrowing the sealed bid deposit. The bid is
+/// revealed with `reveal_bid` after the end time, and the deposit of a bid that is never
+/// revealed goes to the owner.
+#[action(shortname = 0x31)]
+pub fn commit_bid(
     context: ContractContext,
     state: AuctionContractState,
+    commitment: Hash,
 ) -> (AuctionContractState, Vec<EventGroup>) {
-    if context.sender != state.contract_owner {
-        panic!("Start can only be called by the creator of the contract");
+    state.check_bidder(context.sender);
+    let deposit = state
+        .sealed_bid_deposit
+        .unwrap_or_else(|| fail(AuctionError::InvalidSealedAuction {}));
+    let scheduled_start = matches!(state.status, AuctionStatus::BiddingScheduled {})
+        && context.block_production_time >= state.start_time_millis;
+    if !matches!(state.status, AuctionStatus::Bidding {}) && !scheduled_start {
+        fail(AuctionError::NotBidding {});
+    } else if context.block_production_time >= state.end_time_millis {
+        fail(AuctionError::AfterEndTime {});
+    } else if state.sealed_commitments.contains_key(&context.sender) {
+        fail(AuctionError::AlreadyCommitted {});
     }
-    if state.status != CREATION {
-        panic!("Start should only be called while setting up the contract");
+    let mut new_state = state;
+    new_state.advance_schedule(context.block_production_time);
+    if new_state.draw_balance(context.sender, deposit) {
+        new_state
+            .sealed_commitments
+            .insert(context.sender, commitment);
+        return (new_state, vec![]);
     }
-   
-
+    // transfer(auctionContract, deposit)
     let mut event_group = EventGroup::builder();
-
-    event_group.with_callback(SHORTNAME_START_CALLBACK).done();
-
+    new_state.escrow_bidding_tokens(&mut event_group, &context, deposit);
     event_group
-        .call(state.token_for_sale, token_contract_transfer_from())
+        .with_callback(SHORTNAME_COMMIT_BID_CALLBACK)
         .argument(context.sender)
-        .argument(context.contract_address)
-        .argument(state.token_amount_for_sale)
+        .argument(commitment)
         .done();
-
-    (state, vec![event_group.build()])
+    (new_state, vec![event_group.build()])
 }
 
-
-#[callback(shortname = 0x02)]
-pub fn start_callback(
+#[callback(shortname = 0x32)]
+pub fn commit_bid_callback(
     ctx: ContractContext,
     callback_ctx: CallbackContext,
     state: AuctionContractState,
+    bidder: Address,
+    commitment: Hash,
 ) -> (AuctionContractState, Vec<EventGroup>) {
     let mut new_state = state;
+    new_state.advance_schedule(ctx.block_production_time);
+    let deposit = new_state.sealed_bid_deposit.unwrap_or(0);
+    let received = new_state.receive_bidding_tokens(&callback_ctx, deposit);
     if !callback_ctx.success {
-        panic!("Transfer event did not succeed for start");
+        fail(AuctionError::TransferFailed {});
     }
-    new_state.status = BIDDING;
-    (new_state, vec![])
+    let deposit_bid = Bid {
+        bidder,
+        amount: received,
+    };
+    let rejection = new_state
+        .rejection_reason(&deposit_bid, 0, true, ctx.block_production_time)
+        .or_else(|| {
+            if new_state.sealed_commitments.contains_key(&bidder) {
+                Some(RejectionReason::AlreadyCommitted {})
+            } else if received < deposit {
+                Some(RejectionReason::Underpaid {})
+            } else {
+                None
+            }
+        });
+    let mut events = vec![];
+    if let Some(reason) = rejection {
+        events = new_state.refund_invalid_bid(bidder, received, reason, ctx.block_production_time);
+    } else {
+        new_state.sealed_commitments.insert(bidder, commitment);
+    }
+    (new_state, events)
 }
 
-
-#[action(shortname = 0x03)]
-pub fn bid(
+/// Reveal a sealed bid in the reveal window after the end time, escrowing the bid. The deposit
+/// is refunded once the bid is revealed, and a bid that cannot win is refunded right away.
+#[action(shortname = 0x33)]
+pub fn reveal_bid(
     context: ContractContext,
     state: AuctionContractState,
     bid_amount: u128,
+    salt: Hash,
 ) -> (AuctionContractState, Vec<EventGroup>) {
-    // Potential new bid, create the transfer event
+    let mut new_state = state;
+    if !new_state.in_reveal_window(context.block_production_time) {
+        fail(AuctionError::NotInRevealWindow {});
+    }
+    match new_state.sealed_commitments.get(&context.sender) {
+        None => fail(AuctionError::NoCommitment {}),
+        Some(commitment) if *commitment != reserve_commitment(bid_amount, salt) => {
+            fail(AuctionError::InvalidBidReveal {})
+        }
+        Some(_) => {}
+    }
+    new_state.status = AuctionStatus::Revealing {};
+    if bid_amount < new_state.reserve_price || bid_amount <= new_state.highest_bidder.amount {
+        // The bid cannot win, only the deposit is refunded
+        new_state.sealed_commitments.remove(&context.sender);
+        new_state.refund_sealed_bid_deposit(context.sender);
+        return (new_state, vec![]);
+    }
     // transfer(auctionContract, bid_amount)
-
-    let bid: Bid = Bid {
+    let bid = Bid {
         bidder: context.sender,
         amount: bid_amount,
     };
+    let mut event_group = EventGroup::builder();
+    new_state.escrow_bidding_tokens(&mut event_group, &context, bid_amount);
+    event_group
+        .with_callback(SHORTNAME_REVEAL_BID_CALLBACK)
+        .argument(bid)
+        .done();
+    (new_state, vec![event_group.build()])
+}
+
+#[callback(shortname = 0x34)]
+pub fn reveal_bid_callback(
+    ctx: ContractContext,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
+    bid: Bid,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    let received = new_state.receive_bidding_tokens(&callback_ctx, bid.amount);
+    let mut events = vec![];
+    if !callback_ctx.success {
+        fail(AuctionError::TransferFailed {});
+    }
+    let committed = new_state.sealed_commitments.remove(&bid.bidder).is_some();
+    if committed {
+        new_state.refund_sealed_bid_deposit(bid.bidder);
+    }
+    let rejection = if !committed {
+        Some(RejectionReason::Closed {})
+    } else if !new_state.in_reveal_window(ctx.block_production_time) {
+        Some(RejectionReason::Ended {})
+    } else if received < bid.amount {
+        Some(RejectionReason::Underpaid {})
+    } else if bid.amount <= new_state.highest_bidder.amount {
+        // A higher bid was revealed in the meantime
+        Some(RejectionReason::Outbid {})
+    } else {
+        None
+    };
+    if let Some(reason) = rejection {
+        events =
+            new_state.refund_invalid_bid(bid.bidder, received, reason, ctx.block_production_time);
+    } else {
+        new_state.record_bid(bid.bidder, bid.amount, None, ctx.block_production_time);
+        new_state.resolve_bid(bid.bidder, bid.amount, false);
+        events = new_state.notify_observer(new_state.bid_notifications());
+    }
+    (new_state, events)
+}
 
+/// Bid `bid_amount` units of the accepted currency `token`, valued in the bidding token at the
+/// currency's exchange rate. A highest bid in another currency is replaced, not raised, and
+/// every refund is paid in the currency of the bid.
+#[action(shortname = 0x35)]
+pub fn bid_in_currency(
+    context: ContractContext,
+    state: AuctionContractState,
+    token: Address,
+    bid_amount: u128,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    state.check_bidder(context.sender);
+    let value = state.currency_value(token, bid_amount);
+    state.check_bid(context.block_production_time, value, None);
+    let mut new_state = state;
+    new_state.advance_schedule(context.block_production_time);
+    // transfer(auctionContract, bid_amount)
     let mut event_group = EventGroup::builder();
     event_group
-        .call(state.token_for_bidding, token_contract_transfer_from())
+        .call(token, token_contract_transfer_from())
         .argument(context.sender)
         .argument(context.contract_address)
         .argument(bid_amount)
         .done();
     event_group
-        .with_callback(SHORTNAME_BID_CALLBACK)
-        .argument(bid)
+        .with_callback(SHORTNAME_BID_IN_CURRENCY_CALLBACK)
+        .argument(context.sender)
+        .argument(token)
+        .argument(bid_amount)
         .done();
-    (state, vec![event_group.build()])
+    (new_state, vec![event_group.build()])
 }
 
-#[callback(shortname = 0x04)]
-pub fn bid_callback(
+#[callback(shortname = 0x36)]
+pub fn bid_in_currency_callback(
     ctx: ContractContext,
     callback_ctx: CallbackContext,
     state: AuctionContractState,
-    bid: Bid,
+    bidder: Address,
+    token: Address,
+    amount: u128,
 ) -> (AuctionContractState, Vec<EventGroup>) {
-    let mut new_state = state;
     if !callback_ctx.success {
-        panic!("Transfer event did not succeed for bid");
-    } else if new_state.status != BIDDING
-        || ctx.block_production_time >= new_state.end_time_millis
-        || bid.amount < new_state.highest_bidder.amount + new_state.min_increment
-        || bid.amount < new_state.reserve_price
-    {
+        fail(AuctionError::TransferFailed {});
+    }
+    let mut new_state = state;
+    new_state.advance_schedule(ctx.block_production_time);
+    let events = new_state.accept_currency_bid(
+        bidder,
+        CurrencyEscrow { token, amount },
+        ctx.block_production_time,
+    );
+    (new_state, events)
+}
 
-        new_state.add_to_claim_map(
-            bid.bidder,
-            TokenClaim {
-                tokens_for_bidding: bid.amount,
-                tokens_for_sale: 0,
-            },
-        );
-    } else {
-        let prev_highest_bidder = new_state.highest_bidder;
+/// Transfer the claimable amount of the accepted currency `token` to the sender.
+#[action(shortname = 0x37)]
+pub fn claim_currency(
+    context: ContractContext,
+    state: AuctionContractState,
+    token: Address,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    let claimable = match new_state.currency_claims.get_mut(&context.sender) {
+        Some(claims) => {
+            let claimable = claims.remove(&token).unwrap_or(0);
+            if claims.is_empty() {
+                new_state.currency_claims.remove(&context.sender);
+            }
+            claimable
+        }
+        None => 0,
+    };
+    if claimable == 0 {
+        return (new_state, vec![]);
+    }
+    let recipient = *new_state
+        .payout_recipients
+        .get(&context.sender)
+        .unwrap_or(&context.sender);
+    let mut event_group = EventGroup::builder();
+    event_group
+        .call(token, token_contract_transfer())
+        .argument(recipient)
+        .argument(claimable)
+        .done();
+    event_group
+        .with_callback(SHORTNAME_CLAIM_CURRENCY_CALLBACK)
+        .argument(context.sender)
+        .argument(token)
+        .argument(claimable)
+        .done();
+    (new_state, vec![event_group.build()])
+}
 
-        new_state.highest_bidder = bid;
-        new_state.add_to_claim_map(
-            prev_highest_bidder.bidder,
-            TokenClaim {
-                tokens_for_bidding: prev_highest_bidder.amount,
-                tokens_for_sale: 0,
-            },
-        );
+/// Restore a currency claim whose outgoing transfer failed, so it can be claimed again.
+#[callback(shortname = 0x38)]
+pub fn claim_currency_callback(
+    ctx: ContractContext,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
+    claimant: Address,
+    token: Address,
+    claimed: u128,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if !callback_ctx.success {
+        new_state.add_currency_claim(claimant, token, claimed);
     }
     (new_state, vec![])
 }
-#[action(shortname = 0x05)]
-pub fn claim(
+
+/// Fetch the rate of the reserve oracle again and convert the reserve price at it, on behalf
+/// of anyone, as long as no bid has been placed. The rate is also fetched at `start`. An
+/// increment in the reference unit can be refreshed throughout the auction.
+#[action(shortname = 0x4e)]
+pub fn refresh_reserve(
     context: ContractContext,
     state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let increment_in_reference = matches!(
+        &state.reserve_oracle,
+        Some(oracle) if oracle.reference_increment.is_some()
+    );
+    if state.reserve_oracle.is_none() || (state.bid_count > 0 && !increment_in_reference) {
+        fail(AuctionError::ReserveLocked {});
+    }
+    let events = state.reserve_rate_event().into_iter().collect();
+    (state, events)
+}
+
+/// Cache the rate returned by the reserve oracle and convert the reserve price and the
+/// minimum increment at it. A failed query or a zero rate keeps the last rate.
+#[callback(shortname = 0x4f)]
+pub fn refresh_reserve_callback(
+    ctx: ContractContext,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
 ) -> (AuctionContractState, Vec<EventGroup>) {
     let mut new_state = state;
-    let opt_claimable = new_state.claim_map.get(&context.sender);
-    match opt_claimable {
-        None => (new_state, vec![]),
-        Some(claimable) => {
-            let mut event_group = EventGroup::builder();
-            if claimable.tokens_for_bidding > 0 {
-                event_group
-                    .call(new_state.token_for_bidding, token_contract_transfer())
-                    .argument(context.sender)
-                    .argument(claimable.tokens_for_bidding)
-                    .done();
+    match callback_ctx.results.first() {
+        Some(result) if result.succeeded => {
+            let rate = u128::rpc_read_from(&mut result.return_data.as_slice());
+            if rate > 0 {
+                new_state.reference_rate = Some(rate);
             }
-            if claimable.tokens_for_sale > 0 {
-                event_group
-                    .call(new_state.token_for_sale, token_contract_transfer())
-                    .argument(context.sender)
-                    .argument(claimable.tokens_for_sale)
-                    .done();
+        }
+        _ => {}
+    }
+    new_state.apply_reference_rate();
+    (new_state, vec![])
+}
+
+/// Cache the decimals returned by the token for sale and the bidding token. A failed query
+/// leaves the decimals of that token unknown.
+#[callback(shortname = 0x55)]
+pub fn decimals_callback(
+    ctx: ContractContext,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    let decimals = |index: usize| match callback_ctx.results.get(index) {
+        Some(result) if result.succeeded => {
+            Some(u8::rpc_read_from(&mut result.return_data.as_slice()))
+        }
+        _ => None,
+    };
+    new_state.sale_token_decimals = decimals(0);
+    new_state.bidding_token_decimals = decimals(1);
+    (new_state, vec![])
+}
+
+/// Fetch the exchange rate of an accepted currency from its oracle. Anyone can refresh the
+/// rate, and bids are valued at the last fetched rate.
+#[action(shortname = 0x39)]
+pub fn refresh_rate(
+    context: ContractContext,
+    state: AuctionContractState,
+    token: Address,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let currency = state
+        .accepted_currencies
+        .iter()
+        .find(|currency| currency.token == token)
+        .unwrap_or_else(|| fail(AuctionError::CurrencyNotAccepted {}));
+    let mut events = vec![];
+    if let ExchangeRate::Oracle {
+        address,
+        rate_shortname,
+    } = currency.rate
+    {
+        let mut event_group = EventGroup::builder();
+        event_group
+            .call(address, Shortname::from_u32(rate_shortname))
+            .argument(token)
+            .done();
+        event_group
+            .with_callback(SHORTNAME_REFRESH_RATE_CALLBACK)
+            .argument(token)
+            .done();
+        events.push(event_group.build());
+    }
+    (state, events)
+}
+
+#[callback(shortname = 0x3a)]
+pub fn refresh_rate_callback(
+    ctx: ContractContext,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
+    token: Address,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    match callback_ctx.results.first() {
+        Some(result) if result.succeeded => {
+            let rate = u128::rpc_read_from(&mut result.return_data.as_slice());
+            if rate > 0 {
+                new_state.oracle_rates.insert(token, rate);
             }
-            new_state.claim_map.insert(
-                context.sender,
-                TokenClaim {
-                    tokens_for_bidding: 0,
-                    tokens_for_sale: 0,
-                },
-            );
-            (new_state, vec![event_group.build()])
         }
+        _ => {}
     }
+    (new_state, vec![])
 }
-#[action(shortname = 0x06)]
-pub fn execute(
+
+/// Confirm the receipt of the goods as the winner, releasing the held payment to the seller.
+/// Confirming the receipt also withdraws an open dispute.
+#[action(shortname = 0x3b)]
+pub fn confirm_receipt(
     context: ContractContext,
     state: AuctionContractState,
 ) -> (AuctionContractState, Vec<EventGroup>) {
     let mut new_state = state;
-    if context.block_production_time < new_state.end_time_millis {
-        panic!("Tried to execute the auction before auction end block time");
-    } else if new_state.status != BIDDING {
-        panic!("Tried to execute the auction when the status isn't Bidding");
-    } else {
-        new_state.status = ENDED;
-        new_state.add_to_claim_map(
-            new_state.contract_owner,
-            TokenClaim {
-                tokens_for_bidding: new_state.highest_bidder.amount,
-                tokens_for_sale: 0,
-            },
-        );
-        new_state.add_to_claim_map(
-            new_state.highest_bidder.bidder,
-            TokenClaim {
-                tokens_for_bidding: 0,
-                tokens_for_sale: new_state.token_amount_for_sale,
-            },
-        );
-        (new_state, vec![])
+    if new_state.held_proceeds == 0 {
+        fail(AuctionError::NoHeldPayment {});
+    } else if context.sender != new_state.highest_bidder.bidder {
+        fail(AuctionError::NotBuyer {});
     }
+    new_state.disputed = false;
+    new_state.release_held_proceeds();
+    (new_state, vec![])
 }
-#[action(shortname = 0x07)]
-pub fn cancel(
+
+/// Release the held payment to the seller once the delivery timeout has elapsed without a
+/// confirmation of the winner, on behalf of anyone.
+#[action(shortname = 0x3c)]
+pub fn release_payment(
     context: ContractContext,
     state: AuctionContractState,
 ) -> (AuctionContractState, Vec<EventGroup>) {
     let mut new_state = state;
-    if context.sender != new_state.contract_owner {
-        panic!("Only the contract owner can cancel the auction");
-    } else if context.block_production_time >= new_state.end_time_millis {
-        panic!("Tried to cancel the auction after auction end block time");
-    } else if new_state.status != BIDDING {
-        panic!("Tried to cancel the auction when the status isn't Bidding");
-    } else {
-        new_state.status = CANCELLED;
-        new_state.add_to_claim_map(
-            new_state.highest_bidder.bidder,
-            TokenClaim {
-                tokens_for_bidding: new_state.highest_bidder.amount,
-                tokens_for_sale: 0,
-            },
-        );
-        new_state.add_to_claim_map(
-            new_state.contract_owner,
-            TokenClaim {
-                tokens_for_bidding: 0,
-                tokens_for_sale: new_state.token_amount_for_sale,
-            },
-        );
-        (new_state, vec![])
+    if new_state.held_proceeds == 0 {
+        fail(AuctionError::NoHeldPayment {});
+    } else if new_state.disputed {
+        fail(AuctionError::DisputeOpen {});
+    } else if matches!(
+        new_state.delivery_deadline_millis(),
+        Some(deadline) if context.block_production_time < deadline
+    ) {
+        fail(AuctionError::DeliveryTimeoutPending {});
+    }
+    new_state.release_held_proceeds();
+    (new_state, vec![])
+}
+
+/// Dispute the delivery of the goods as the winner or the seller before the delivery timeout.
+/// The held payment is kept until the arbiter resolves the dispute.
+#[action(shortname = 0x3d)]
+pub fn open_dispute(
+    context: ContractContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if new_state.arbiter.is_none() {
+        fail(AuctionError::NoArbiter {});
+    } else if new_state.held_proceeds == 0 {
+        fail(AuctionError::NoHeldPayment {});
+    } else if context.sender != new_state.highest_bidder.bidder
+        && context.sender != new_state.contract_owner
+    {
+        fail(AuctionError::NotDisputeParty {});
+    } else if new_state.disputed {
+        fail(AuctionError::DisputeOpen {});
+    } else if matches!(
+        new_state.delivery_deadline_millis(),
+        Some(deadline) if context.block_production_time >= deadline
+    ) {
+        fail(AuctionError::DisputeWindowClosed {});
+    }
+    new_state.disputed = true;
+    (new_state, vec![])
+}
+
+/// Resolve an open dispute as the arbiter, refunding `award` of the held payment to the winner
+/// and releasing the rest to the seller.
+#[action(shortname = 0x3e)]
+pub fn resolve_dispute(
+    context: ContractContext,
+    state: AuctionContractState,
+    award: u128,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let mut new_state = state;
+    if new_state.arbiter != Some(context.sender) {
+        fail(AuctionError::NotArbiter {});
+    } else if !new_state.disputed {
+        fail(AuctionError::NoDispute {});
+    } else if award > new_state.held_proceeds {
+        fail(AuctionError::InvalidAward {});
     }
+    new_state.disputed = false;
+    new_state.held_proceeds -= award;
+    new_state.add_to_claim_map(
+        new_state.highest_bidder.bidder,
+        TokenClaim {
+            tokens_for_bidding: award,
+            tokens_for_sale: 0,
+        },
+    );
+    new_state.release_held_proceeds();
+    (new_state, vec![])
 }
@@ -0,0 +1,140 @@
+//! The rules deciding an English auction as pure functions of plain inputs: whether a bid is
+//! valid, what the winner pays and how the proceeds are split. The contract decides with these
+//! same functions, so front ends and bots can simulate outcomes off-chain.
+
+use crate::{mul_div, SettlementMode, TiePolicy, BASIS_POINTS, SECOND_PRICE};
+
+/// The bidding terms a new bid is checked against.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BidRules {
+    pub highest_bid: u128,
+    pub min_increment: u128,
+    pub reserve_price: u128,
+    pub buyout_price: Option<u128>,
+    pub tie_policy: TiePolicy,
+}
+
+/// Whether a bid of `bid_amount` is accepted under `rules`: it meets the reserve price, and
+/// outbids the highest bid by the minimum increment or meets the buyout price. A bid tying with
+/// the highest bid is only accepted if the tie policy does not favor the first bid.
+pub fn bid_is_valid(rules: &BidRules, bid_amount: u128) -> bool {
+    let meets_buyout = matches!(rules.buyout_price, Some(price) if bid_amount >= price);
+    let raises_enough = bid_amount >= rules.highest_bid.saturating_add(rules.min_increment);
+    let ties = bid_amount > 0 && bid_amount == rules.highest_bid;
+    bid_amount >= rules.reserve_price
+        && (raises_enough || meets_buyout)
+        && !(ties && matches!(rules.tie_policy, TiePolicy::FirstReceived {}))
+}
+
+/// The amount the highest bidder pays. In second price settlement this is the second highest
+/// bid, but never less than the reserve price nor more than the highest bid.
+pub fn clearing_price(
+    settlement_mode: SettlementMode,
+    highest_bid: u128,
+    second_highest_bid: u128,
+    reserve_price: u128,
+) -> u128 {
+    match settlement_mode {
+        SECOND_PRICE => second_highest_bid.max(reserve_price).min(highest_bid),
+        _ => highest_bid,
+    }
+}
+
+/// The shares of the proceeds of a sale, in basis points, and the weights of the beneficiaries
+/// splitting the rest. A zero keeper or referral share means nobody is rewarded. The withheld
+/// shares, such as taxes or donations, are paid to their own receivers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProceedsRates {
+    pub keeper_reward_bps: u32,
+    pub referral_bps: u32,
+    pub fee_bps: u32,
+    pub royalty_bps: u32,
+    pub withholding_bps: Vec<u32>,
+    pub beneficiary_weights: Vec<u32>,
+}
+
+/// How the proceeds of a sale are paid out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProceedsSplit {
+    pub keeper_reward: u128,
+    pub referral_reward: u128,
+    pub fee: u128,
+    pub royalty: u128,
+    pub withholdings: Vec<u128>,
+    pub beneficiary_shares: Vec<u128>,
+    /// The rest of the proceeds, including any rounding remainder.
+    pub owner_share: u128,
+}
+
+/// Split `proceeds` by `rates`. All shares are computed from the full proceeds, and the
+/// beneficiaries split what is left by weight.
+pub fn split_proceeds(proceeds: u128, rates: &ProceedsRates) -> ProceedsSplit {
+    let share = |bps: u32| mul_div(proceeds, u128::from(bps), u128::from(BASIS_POINTS));
+    let keeper_reward = share(rates.keeper_reward_bps);
+    let referral_reward = share(rates.referral_bps);
+    let fee = share(rates.fee_bps);
+    let royalty = share(rates.royalty_bps);
+    let withholdings: Vec<u128> = rates
+        .withholding_bps
+        .iter()
+        .map(|bps| share(*bps))
+        .collect();
+    let remainder = proceeds
+        - fee
+        - royalty
+        - keeper_reward
+        - referral_reward
+        - withholdings.iter().sum::<u128>();
+    let total_weight: u128 = rates
+        .beneficiary_weights
+        .iter()
+        .map(|weight| u128::from(*weight))
+        .sum();
+    let beneficiary_shares: Vec<u128> = rates
+        .beneficiary_weights
+        .iter()
+        .map(|weight| mul_div(remainder, u128::from(*weight), total_weight))
+        .collect();
+    let owner_share = remainder - beneficiary_shares.iter().sum::<u128>();
+    ProceedsSplit {
+        keeper_reward,
+        referral_reward,
+        fee,
+        royalty,
+        withholdings,
+        beneficiary_shares,
+        owner_share,
+    }
+}
+
+/// The outcome of settling an auction with a sale.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SettlementOutcome {
+    pub price: u128,
+    /// The part of the escrow of the winner exceeding the price, which is refunded.
+    pub winner_refund: u128,
+    pub proceeds: ProceedsSplit,
+}
+
+/// Settle a sale to the highest bidder, who escrowed `highest_bid_escrow` for a highest bid of
+/// `highest_bid`. The outbid bids an all-pay auction adds to the proceeds are not included.
+pub fn settle(
+    settlement_mode: SettlementMode,
+    highest_bid: u128,
+    highest_bid_escrow: u128,
+    second_highest_bid: u128,
+    reserve_price: u128,
+    rates: &ProceedsRates,
+) -> SettlementOutcome {
+    let price = clearing_price(
+        settlement_mode,
+        highest_bid,
+        second_highest_bid,
+        reserve_price,
+    );
+    SettlementOutcome {
+        price,
+        winner_refund: highest_bid_escrow.saturating_sub(price),
+        proceeds: split_proceeds(price, rates),
+    }
+}
@@ -1,13 +1,53 @@
 #![allow(deprecated)]
 #![cfg(test)]
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+
 use pbc_contract_common::address::{Address, AddressType, ShortnameCallback};
 use pbc_contract_common::context::{CallbackContext, ContractContext, ExecutionResult};
 use pbc_contract_common::events::EventGroup;
 use pbc_contract_common::Hash;
 
+use proptest::prelude::*;
+
+use crate::invariants::{
+    bid_sequences, claims_never_exceed_deposits, owed_bidding_tokens, total_escrowed_consistent,
+    Transfer,
+};
+use crate::simulation::{
+    bid_is_valid, clearing_price, settle as simulate_settlement, BidRules, ProceedsRates,
+    ProceedsSplit, SettlementOutcome,
+};
+use crate::testutils::{
+    assert_event_groups, callback_context, callback_context_returning, AuctionBuilder,
+    ContextBuilder,
+};
+use crate::units::{compare_normalized, format_units, normalize_amount, to_base_units};
 use crate::{
-    bid, bid_callback, cancel, claim, execute, initialize, start, start_callback,
-    AuctionContractState, Bid, Shortname, TokenClaim, BIDDING, CANCELLED, ENDED,
+    accept_ownership, accept_price, accept_price_callback, add_lot, approve_action, audit, bid,
+    bid_allowance_callback, bid_batch, bid_batch_callback, bid_callback, bid_for, bid_in_currency,
+    bid_in_currency_callback, bid_lot, bid_lot_callback, bid_with_referrer, block_bidder,
+    bond_callback, cancel, claim, claim_bundle_callback, claim_callback, claim_currency,
+    claim_currency_callback, claim_for, commit_bid, commit_bid_callback, complete_payment,
+    complete_payment_callback, configure, configure_signers, configure_timelock, confirm_receipt,
+    decimals_callback, delegate_bidding, deposit, deposit_callback, execute, expire,
+    extend_auction, finalize, initialize, open_dispute, pause, penny_bid, penny_bid_callback,
+    place_order, place_order_callback, propose_action, propose_new_owner, proxy_bid,
+    proxy_bid_callback, recover, refresh_rate, refresh_rate_callback, refresh_reserve,
+    refresh_reserve_callback, refund_all, release_payment, reserve_commitment, reset_start,
+    resolve_dispute, resume, retract_bid, retry_failed_payouts, reveal_bid, reveal_bid_callback,
+    reveal_candle, reveal_reserve, set_beneficiaries, set_payout_recipient, set_proceeds_receiver,
+    settle_lot, sha256, snapshot, start, start_callback, summary, sweep_unclaimed, top_up_callback,
+    unblock_bidder, update_metadata, update_terms, verify_bidder, verify_bidder_callback,
+    verify_membership, verify_membership_callback, withdraw, withdraw_callback, AcceptedCurrency,
+    AuctionConfig, AuctionContractState, AuctionError, AuctionNotification, AuctionSnapshot,
+    AuctionStatus, AuctionSummary, Beneficiary, Bid, BidBondConfig, BidOutcome, BidRecord,
+    BidderStats, BundleItem, CandleBid, CandleConfig, CurrencyEscrow, DecayCurve, ExchangeRate,
+    FailedPayout, KycRegistry, LeaderboardEntry, Lot, LotBid, MembershipGate, Order, OwnerAction,
+    PendingAction, PennyConfig, PriceSchedule, RecurrenceConfig, RejectedBid, RejectionReason,
+    RelistConfig, ReserveOracle, RoundConfig, RoundResult, Shortname, TiePolicy, TokenClaim,
+    TokenInterface, Withholding, ALL_PAY, DUTCH, ENGLISH, FIRST_PRICE, FIXED_PRICE, PAY_AS_BID,
+    RATE_SCALE, SECOND_PRICE, UNIFORM_PRICE,
 };
 
 fn create_ctx(sender: Address, block_time: i64) -> ContractContext {
@@ -81,6 +121,17 @@ fn create_callback_ctx(success: bool) -> CallbackContext {
     ctx
 }
 
+/// Start `state` in the transaction of `ctx`, and complete the escrow of the tokens for sale
+/// with `callback_ctx`.
+fn start_with_escrow(
+    ctx: ContractContext,
+    callback_ctx: CallbackContext,
+    state: AuctionContractState,
+) -> (AuctionContractState, Vec<EventGroup>) {
+    let (pending_state, _) = start(create_ctx(ctx.sender, ctx.block_time), state);
+    start_callback(ctx, callback_ctx, pending_state)
+}
+
 fn initialize_contract() -> (AuctionContractState, Vec<EventGroup>) {
     let sender = get_owner_address();
     let commodity_token = get_commodity_token_address();
@@ -88,12 +139,40 @@ fn initialize_contract() -> (AuctionContractState, Vec<EventGroup>) {
     let ctx = create_ctx(sender, 2);
     initialize(
         ctx,
-        100_000,
-        commodity_token,
-        currency_token,
-        1_000,
-        100,
-        100,
+        AuctionConfig {
+            token_amount_for_sale: 100_000,
+            token_for_sale: commodity_token,
+            token_for_bidding: currency_token,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            ..english_auction_config()
+        },
+    )
+}
+
+fn initialize_dutch_contract() -> (AuctionContractState, Vec<EventGroup>) {
+    let sender = get_owner_address();
+    let commodity_token = get_commodity_token_address();
+    let currency_token = get_currency_token_address();
+    let ctx = create_ctx(sender, 2);
+    initialize(
+        ctx,
+        AuctionConfig {
+            token_amount_for_sale: 100_000,
+            token_for_sale: commodity_token,
+            token_for_bidding: currency_token,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            auction_format: DUTCH,
+            price_schedule: Some(PriceSchedule {
+                start_price: 11_000,
+                decrement_per_hour: 100,
+                curve: DecayCurve::Linear {},
+            }),
+            ..english_auction_config()
+        },
     )
 }
 
@@ -105,15 +184,18 @@ pub fn test_initialize() {
     let ctx = create_ctx(sender, 2);
     let (state, events) = initialize(
         ctx,
-        100_000,
-        commodity_token,
-        currency_token,
-        1_000,
-        100,
-        100,
+        AuctionConfig {
+            token_amount_for_sale: 100_000,
+            token_for_sale: commodity_token,
+            token_for_bidding: currency_token,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            ..english_auction_config()
+        },
     );
     assert_eq!(0, events.len());
-    assert_eq!(0, state.status);
+    assert_eq!(AuctionStatus::Creation {}, state.status);
     assert_eq!(sender, state.contract_owner);
     assert_eq!(commodity_token, state.token_for_sale);
     assert_eq!(currency_token, state.token_for_bidding);
@@ -140,12 +222,15 @@ pub fn test_initialize_wrong_commodity() {
     let ctx = create_ctx(sender, 2);
     let (state, events) = initialize(
         ctx,
-        100_000,
-        commodity_token,
-        currency_token,
-        1_000,
-        100,
-        100,
+        AuctionConfig {
+            token_amount_for_sale: 100_000,
+            token_for_sale: commodity_token,
+            token_for_bidding: currency_token,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            ..english_auction_config()
+        },
     );
 }
 
@@ -161,12 +246,15 @@ pub fn test_initialize_wrong_currency() {
     let ctx = create_ctx(sender, 2);
     let (state, events) = initialize(
         ctx,
-        100_000,
-        commodity_token,
-        currency_token,
-        1_000,
-        100,
-        100,
+        AuctionConfig {
+            token_amount_for_sale: 100_000,
+            token_for_sale: commodity_token,
+            token_for_bidding: currency_token,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            ..english_auction_config()
+        },
     );
 }
 
@@ -176,7 +264,7 @@ pub fn test_start() {
     let sender = get_owner_address();
     let ctx = create_ctx(sender, 3);
     let (start_state, start_events) = start(ctx, state.clone());
-    assert_eq!(start_state, state);
+    assert_eq!(start_state.status, AuctionStatus::PendingStart {});
     assert_eq!(start_events.len(), 1);
     let transfer_event = start_events.get(0).unwrap();
     let mut expected = EventGroup::builder();
@@ -197,7 +285,7 @@ pub fn test_start() {
 pub fn test_start_not_creation() {
     let (mut state, _) = initialize_contract();
     let sender = get_owner_address();
-    state.status = 1;
+    state.status = AuctionStatus::Bidding {};
     let ctx = create_ctx(sender, 3);
     start(ctx, state);
 }
@@ -220,12 +308,11 @@ pub fn test_start_callback() {
     let callback_ctx = create_callback_ctx(true);
     let start_ctx_2 = create_ctx(owner, 4);
     let (start_callback_state, events) = start_callback(start_ctx_2, callback_ctx, start_state);
-    assert_eq!(start_callback_state.status, BIDDING);
+    assert_eq!(start_callback_state.status, AuctionStatus::Bidding {});
     assert_eq!(events.len(), 0);
 }
 
 #[test]
-#[should_panic]
 pub fn test_start_callback_transfer_unsuccessful() {
     let (init_state, _) = initialize_contract();
     let owner = get_owner_address();
@@ -233,7 +320,89 @@ pub fn test_start_callback_transfer_unsuccessful() {
     let (start_state, _) = start(start_ctx, init_state);
     let callback_ctx = create_callback_ctx(false);
     let start_ctx_2 = create_ctx(owner, 4);
-    start_callback(start_ctx_2, callback_ctx, start_state);
+    let (failed_state, events) = start_callback(start_ctx_2, callback_ctx, start_state);
+    assert_eq!(failed_state.status, AuctionStatus::PendingStart {});
+    assert!(failed_state.start_failed);
+    assert_eq!(failed_state.total_escrowed_sale, 0);
+    assert_eq!(events.len(), 0);
+}
+
+#[test]
+#[should_panic(expected = "[2] The action is only allowed while setting up the contract")]
+pub fn test_start_twice() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (start_state, _) = start(create_ctx(owner, 3), init_state);
+    start(create_ctx(owner, 3), start_state);
+}
+
+#[test]
+pub fn test_reset_start_after_failed_escrow() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (start_state, _) = start(create_ctx(owner, 3), init_state);
+    let (failed_state, _) = start_callback(
+        create_ctx(owner, 3),
+        create_callback_ctx(false),
+        start_state,
+    );
+    let (reset_state, _) = reset_start(create_ctx(owner, 4), failed_state);
+    assert_eq!(reset_state.status, AuctionStatus::Creation {});
+    assert!(!reset_state.start_failed);
+    let (restart_state, _) = start(create_ctx(owner, 4), reset_state);
+    let (started_state, _) = start_callback(
+        create_ctx(owner, 4),
+        create_callback_ctx(true),
+        restart_state,
+    );
+    assert_eq!(started_state.status, AuctionStatus::Bidding {});
+    assert_eq!(started_state.total_escrowed_sale, 100_000);
+}
+
+#[test]
+#[should_panic(
+    expected = "[137] Tried to reset a start whose escrow of the tokens for sale has not failed"
+)]
+pub fn test_reset_start_while_escrow_in_flight() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (start_state, _) = start(create_ctx(owner, 3), init_state);
+    reset_start(create_ctx(owner, 4), start_state);
+}
+
+#[test]
+#[should_panic(
+    expected = "[136] The action is only allowed while the start of the auction is pending"
+)]
+pub fn test_start_callback_after_start() {
+    let state = started_contract_with_bid(2000);
+    start_callback(
+        create_ctx(get_owner_address(), 6),
+        create_callback_ctx(true),
+        state,
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "[136] The action is only allowed while the start of the auction is pending"
+)]
+pub fn test_start_callback_without_start() {
+    let (init_state, _) = initialize_contract();
+    start_callback(
+        create_ctx(get_owner_address(), 3),
+        create_callback_ctx(true),
+        init_state,
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "[136] The action is only allowed while the start of the auction is pending"
+)]
+pub fn test_reset_start_not_pending() {
+    let (init_state, _) = initialize_contract();
+    reset_start(create_ctx(get_owner_address(), 3), init_state);
 }
 
 #[test]
@@ -247,7 +416,7 @@ pub fn test_bid() {
     let (start_callback_state, _) = start_callback(start_ctx_2, callback_ctx, start_state);
     let bidder = get_bidder_address();
     let bid_ctx = create_ctx(bidder, 5);
-    let (bid_state, events) = bid(bid_ctx, start_callback_state.clone(), 10);
+    let (bid_state, events) = bid(bid_ctx, start_callback_state.clone(), 2_000);
     assert_eq!(bid_state, start_callback_state);
     assert_eq!(events.len(), 1);
     let bid_event = events.get(0).unwrap();
@@ -256,23 +425,29 @@ pub fn test_bid() {
         .call(get_currency_token_address(), Shortname::from_u32(3))
         .argument(get_bidder_address())
         .argument(get_contract_address())
-        .argument(10u128)
+        .argument(2_000u128)
         .done();
     expected_event
         .with_callback(ShortnameCallback::from_u32(4))
         .argument(bidder)
-        .argument(10u128)
+        .argument(2_000u128)
         .done();
     assert_eq!(*bid_event, expected_event.build());
 }
 
+fn bid_outcome_event(outcome: BidOutcome) -> EventGroup {
+    let mut event_group = EventGroup::builder();
+    event_group.return_data(outcome);
+    event_group.build()
+}
+
 #[test]
 pub fn test_bid_callback_new_highest_bid() {
     let (init_state, _) = initialize_contract();
     let owner = get_owner_address();
     let start_ctx = create_ctx(owner, 3);
     let start_callback_ctx = create_callback_ctx(true);
-    let (start_callback_state, _) = start_callback(start_ctx, start_callback_ctx, init_state);
+    let (start_callback_state, _) = start_with_escrow(start_ctx, start_callback_ctx, init_state);
     let bidder = get_bidder_address();
     let bid_ctx = create_ctx(bidder, 4);
     let bid_callback_ctx = create_callback_ctx(true);
@@ -283,7 +458,10 @@ pub fn test_bid_callback_new_highest_bid() {
     assert_eq!(start_callback_state.claim_map.len(), 0);
     let (bid_callback_state, bid_callback_events) =
         bid_callback(bid_ctx, bid_callback_ctx, start_callback_state, bid.clone());
-    assert_eq!(bid_callback_events.len(), 0);
+    assert_eq!(
+        bid_callback_events,
+        vec![bid_outcome_event(BidOutcome::HighestBid { amount: 1000 })]
+    );
     // previous bid is added to claim map (owner, currency: 0)
     assert_eq!(bid_callback_state.claim_map.len(), 1);
     let claim_map_entry = bid_callback_state.claim_map.get(&owner);
@@ -313,7 +491,10 @@ pub fn test_bid_callback_not_bidding() {
     assert_eq!(init_state.claim_map.len(), 0);
     let (bid_callback_state, bid_callback_events) =
         bid_callback(bid_ctx, bid_callback_ctx, init_state, bid);
-    assert_eq!(bid_callback_events.len(), 0);
+    assert_eq!(
+        bid_callback_events,
+        vec![bid_outcome_event(BidOutcome::Refunded { amount: 1000 })]
+    );
     // bid is added to claim map (bidder, currency: 0)
     assert_eq!(bid_callback_state.claim_map.len(), 1);
     let claim_map_entry = bid_callback_state.claim_map.get(&bidder);
@@ -340,7 +521,7 @@ pub fn test_bid_callback_end_time_reached() {
     let owner = get_owner_address();
     let start_ctx = create_ctx(owner, 3);
     let start_callback_ctx = create_callback_ctx(true);
-    let (start_callback_state, _) = start_callback(start_ctx, start_callback_ctx, init_state);
+    let (start_callback_state, _) = start_with_escrow(start_ctx, start_callback_ctx, init_state);
     let bidder = get_bidder_address();
     // contract init at block time 2 with duration 100
     let bid_ctx = create_ctx(bidder, 102);
@@ -352,7 +533,10 @@ pub fn test_bid_callback_end_time_reached() {
     assert_eq!(start_callback_state.claim_map.len(), 0);
     let (bid_callback_state, bid_callback_events) =
         bid_callback(bid_ctx, bid_callback_ctx, start_callback_state, bid);
-    assert_eq!(bid_callback_events.len(), 0);
+    assert_eq!(
+        bid_callback_events,
+        vec![bid_outcome_event(BidOutcome::Refunded { amount: 1000 })]
+    );
     // bid is added to claim map (bidder, currency: 0)
     assert_eq!(bid_callback_state.claim_map.len(), 1);
     let claim_map_entry = bid_callback_state.claim_map.get(&bidder);
@@ -379,7 +563,7 @@ pub fn test_bid_callback_multiple_claimable_bids() {
     let owner = get_owner_address();
     let start_ctx = create_ctx(owner, 3);
     let start_callback_ctx = create_callback_ctx(true);
-    let (start_callback_state, _) = start_callback(start_ctx, start_callback_ctx, init_state);
+    let (start_callback_state, _) = start_with_escrow(start_ctx, start_callback_ctx, init_state);
     let bidder = get_bidder_address();
     // contract init at block time 2 with duration 100
     let bid_ctx = create_ctx(bidder, 102);
@@ -395,7 +579,10 @@ pub fn test_bid_callback_multiple_claimable_bids() {
     let bid_callback_ctx = create_callback_ctx(true);
     let (bid2_callback_state, bid2_callback_events) =
         bid_callback(bid_ctx, bid_callback_ctx, bid_callback_state, bid);
-    assert_eq!(bid2_callback_events.len(), 0);
+    assert_eq!(
+        bid2_callback_events,
+        vec![bid_outcome_event(BidOutcome::Refunded { amount: 1000 })]
+    );
     // bid is added to claim map (bidder, currency: 0)
     assert_eq!(bid2_callback_state.claim_map.len(), 1);
     let claim_map_entry = bid2_callback_state.claim_map.get(&bidder);
@@ -425,7 +612,7 @@ pub fn test_bid_callback_not_highest_bid_cause_increment() {
     let owner = get_owner_address();
     let start_ctx = create_ctx(owner, 3);
     let start_callback_ctx = create_callback_ctx(true);
-    let (start_callback_state, _) = start_callback(start_ctx, start_callback_ctx, init_state);
+    let (start_callback_state, _) = start_with_escrow(start_ctx, start_callback_ctx, init_state);
     let bidder = get_bidder_address();
     let bid_ctx = create_ctx(bidder, 101);
     let bid_callback_ctx = create_callback_ctx(true);
@@ -433,7 +620,10 @@ pub fn test_bid_callback_not_highest_bid_cause_increment() {
     assert_eq!(start_callback_state.claim_map.len(), 0);
     let (bid_callback_state, bid_callback_events) =
         bid_callback(bid_ctx, bid_callback_ctx, start_callback_state, bid);
-    assert_eq!(bid_callback_events.len(), 0);
+    assert_eq!(
+        bid_callback_events,
+        vec![bid_outcome_event(BidOutcome::Refunded { amount: 99 })]
+    );
     // bid is added to claim map (bidder, currency: 0)
     assert_eq!(bid_callback_state.claim_map.len(), 1);
     let claim_map_entry = bid_callback_state.claim_map.get(&bidder);
@@ -454,6 +644,65 @@ pub fn test_bid_callback_not_highest_bid_cause_increment() {
     );
 }
 
+#[test]
+pub fn test_bid_callback_records_rejection_reasons() {
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let state = started_contract_with_bid(2000);
+    let (low_state, _) = bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder: third_party,
+            amount: 2050,
+        },
+    );
+    assert_eq!(
+        *low_state.rejected_bids.get(&third_party).unwrap(),
+        RejectedBid {
+            amount: 2050,
+            reason: RejectionReason::TooLow {},
+            timestamp_millis: 6 * 60 * 60 * 1000,
+        }
+    );
+    let (late_state, _) = bid_callback(
+        create_ctx(bidder, 102),
+        create_callback_ctx(true),
+        low_state,
+        Bid {
+            bidder,
+            amount: 3000,
+        },
+    );
+    assert_eq!(
+        late_state.rejected_bids.get(&bidder).unwrap().reason,
+        RejectionReason::Ended {}
+    );
+}
+
+#[test]
+pub fn test_bid_callback_records_rejection_below_reserve() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    let bidder = get_bidder_address();
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_state,
+        Bid {
+            bidder,
+            amount: 999,
+        },
+    );
+    assert_eq!(
+        bid_state.rejected_bids.get(&bidder).unwrap().reason,
+        RejectionReason::BelowReserve {}
+    );
+}
+
 #[test]
 pub fn test_bid_callback_not_highest_bid_cause_reserve() {
     let (mut init_state, _) = initialize_contract();
@@ -463,7 +712,7 @@ pub fn test_bid_callback_not_highest_bid_cause_reserve() {
     let owner = get_owner_address();
     let start_ctx = create_ctx(owner, 3);
     let start_callback_ctx = create_callback_ctx(true);
-    let (start_callback_state, _) = start_callback(start_ctx, start_callback_ctx, init_state);
+    let (start_callback_state, _) = start_with_escrow(start_ctx, start_callback_ctx, init_state);
     let bidder = get_bidder_address();
     let bid_ctx = create_ctx(bidder, 101);
     let bid_callback_ctx = create_callback_ctx(true);
@@ -474,7 +723,10 @@ pub fn test_bid_callback_not_highest_bid_cause_reserve() {
     assert_eq!(start_callback_state.claim_map.len(), 0);
     let (bid_callback_state, bid_callback_events) =
         bid_callback(bid_ctx, bid_callback_ctx, start_callback_state, bid);
-    assert_eq!(bid_callback_events.len(), 0);
+    assert_eq!(
+        bid_callback_events,
+        vec![bid_outcome_event(BidOutcome::Refunded { amount: 999 })]
+    );
     // bid is added to claim map (bidder, currency: 0)
     assert_eq!(bid_callback_state.claim_map.len(), 1);
     let claim_map_entry = bid_callback_state.claim_map.get(&bidder);
@@ -495,6 +747,27 @@ pub fn test_bid_callback_not_highest_bid_cause_reserve() {
     );
 }
 
+#[test]
+pub fn test_bid_callback_outbid() {
+    let mut state = started_contract_with_bid(2000);
+    // the highest bidder is a proxy bidder with a higher maximum
+    state.highest_bidder_max = Some(5000);
+    let third_party = get_third_party_address();
+    let (_, events) = bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder: third_party,
+            amount: 3000,
+        },
+    );
+    assert_eq!(
+        events,
+        vec![bid_outcome_event(BidOutcome::Outbid { highest_bid: 3100 })]
+    );
+}
+
 #[test]
 #[should_panic]
 pub fn test_bid_callback_transfer_unsuccessful() {
@@ -502,7 +775,7 @@ pub fn test_bid_callback_transfer_unsuccessful() {
     let owner = get_owner_address();
     let start_ctx = create_ctx(owner, 3);
     let start_callback_ctx = create_callback_ctx(true);
-    let (start_callback_state, _) = start_callback(start_ctx, start_callback_ctx, init_state);
+    let (start_callback_state, _) = start_with_escrow(start_ctx, start_callback_ctx, init_state);
     let bidder = get_bidder_address();
     let bid_ctx = create_ctx(bidder, 4);
     let bid_callback_ctx = create_callback_ctx(false);
@@ -571,6 +844,12 @@ pub fn test_claim_currency() {
         .argument(get_owner_address())
         .argument(1000u128)
         .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x13))
+        .argument(get_owner_address())
+        .argument(1000u128)
+        .argument(0u128)
+        .done();
     assert_eq!(*event, expected_event.build());
 }
 
@@ -605,6 +884,12 @@ pub fn test_claim_commodity() {
         .argument(get_owner_address())
         .argument(100u128)
         .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x13))
+        .argument(get_owner_address())
+        .argument(0u128)
+        .argument(100u128)
+        .done();
     assert_eq!(*event, expected_event.build());
 }
 
@@ -644,15 +929,203 @@ pub fn test_claim_both() {
         .argument(get_owner_address())
         .argument(100u128)
         .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x13))
+        .argument(get_owner_address())
+        .argument(1000u128)
+        .argument(100u128)
+        .done();
     assert_eq!(*event, expected_event.build());
 }
 
+#[test]
+pub fn test_claim_callback_restores_failed_claim() {
+    let (mut init_state, _) = initialize_contract();
+    let address = get_owner_address();
+    let claimed = TokenClaim {
+        tokens_for_bidding: 1000,
+        tokens_for_sale: 100,
+    };
+    init_state.add_to_claim_map(address, claimed.clone());
+    let (claim_state, _) = claim(create_ctx(address, 4), init_state);
+    let (callback_state, _) = claim_callback(
+        create_ctx(address, 5),
+        create_callback_ctx(false),
+        claim_state,
+        address,
+        claimed.clone(),
+    );
+    assert_eq!(*callback_state.claim_map.get(&address).unwrap(), claimed);
+}
+
+#[test]
+pub fn test_claim_callback_restores_only_failed_transfer() {
+    let (init_state, _) = initialize_contract();
+    let address = get_owner_address();
+    let callback_ctx = CallbackContext {
+        success: false,
+        results: vec![
+            ExecutionResult {
+                succeeded: true,
+                return_data: vec![],
+            },
+            ExecutionResult {
+                succeeded: false,
+                return_data: vec![],
+            },
+        ],
+    };
+    let (callback_state, _) = claim_callback(
+        create_ctx(address, 5),
+        callback_ctx,
+        init_state,
+        address,
+        TokenClaim {
+            tokens_for_bidding: 1000,
+            tokens_for_sale: 100,
+        },
+    );
+    assert_eq!(
+        *callback_state.claim_map.get(&address).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 100,
+        }
+    );
+}
+
+#[test]
+pub fn test_claim_callback_success() {
+    let (init_state, _) = initialize_contract();
+    let address = get_owner_address();
+    let (callback_state, _) = claim_callback(
+        create_ctx(address, 5),
+        create_callback_ctx(true),
+        init_state.clone(),
+        address,
+        TokenClaim {
+            tokens_for_bidding: 1000,
+            tokens_for_sale: 0,
+        },
+    );
+    assert_eq!(callback_state, init_state);
+}
+
+#[test]
+pub fn test_claim_callback_queues_failed_payout() {
+    let (mut init_state, _) = initialize_contract();
+    let address = get_owner_address();
+    let claimed = TokenClaim {
+        tokens_for_bidding: 1000,
+        tokens_for_sale: 0,
+    };
+    init_state.add_to_claim_map(address, claimed.clone());
+    let (claim_state, _) = claim(create_ctx(address, 4), init_state);
+    let (failed_state, _) = claim_callback(
+        create_ctx(address, 5),
+        create_callback_ctx(false),
+        claim_state,
+        address,
+        claimed.clone(),
+    );
+    let (retried_state, events) =
+        retry_failed_payouts(create_ctx(get_bidder_address(), 6), failed_state);
+    assert_eq!(events.len(), 1);
+    let (failed_again_state, _) = claim_callback(
+        create_ctx(address, 7),
+        create_callback_ctx(false),
+        retried_state,
+        address,
+        claimed.clone(),
+    );
+    assert_eq!(
+        *failed_again_state.failed_payouts.get(&address).unwrap(),
+        FailedPayout {
+            failed: claimed.clone(),
+            attempts: 2,
+            last_attempt_millis: 7 * 60 * 60 * 1000,
+        }
+    );
+    assert_eq!(
+        *failed_again_state.claim_map.get(&address).unwrap(),
+        claimed
+    );
+}
+
+#[test]
+pub fn test_claim_callback_success_clears_failed_payout() {
+    let (mut init_state, _) = initialize_contract();
+    let address = get_owner_address();
+    let claimed = TokenClaim {
+        tokens_for_bidding: 1000,
+        tokens_for_sale: 0,
+    };
+    init_state.add_to_claim_map(address, claimed.clone());
+    let (claim_state, _) = claim(create_ctx(address, 4), init_state);
+    let (failed_state, _) = claim_callback(
+        create_ctx(address, 5),
+        create_callback_ctx(false),
+        claim_state,
+        address,
+        claimed.clone(),
+    );
+    let (retried_state, _) = retry_failed_payouts(create_ctx(address, 6), failed_state);
+    let (callback_state, _) = claim_callback(
+        create_ctx(address, 7),
+        create_callback_ctx(true),
+        retried_state,
+        address,
+        claimed,
+    );
+    assert!(callback_state.failed_payouts.is_empty());
+    assert_eq!(
+        *callback_state.claim_map.get(&address).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+pub fn test_retry_failed_payouts_skips_settled_claims() {
+    let (mut init_state, _) = initialize_contract();
+    let address = get_owner_address();
+    let claimed = TokenClaim {
+        tokens_for_bidding: 1000,
+        tokens_for_sale: 0,
+    };
+    init_state.add_to_claim_map(address, claimed.clone());
+    let (claim_state, _) = claim(create_ctx(address, 4), init_state);
+    let (mut failed_state, _) = claim_callback(
+        create_ctx(address, 5),
+        create_callback_ctx(false),
+        claim_state,
+        address,
+        claimed,
+    );
+    failed_state.claim_map.remove(&address);
+    let (retried_state, events) =
+        retry_failed_payouts(create_ctx(get_bidder_address(), 6), failed_state);
+    assert!(events.is_empty());
+    assert!(retried_state.failed_payouts.is_empty());
+}
+
+#[test]
+pub fn test_retry_failed_payouts_without_failures() {
+    let (init_state, _) = initialize_contract();
+    let (retried_state, events) =
+        retry_failed_payouts(create_ctx(get_bidder_address(), 6), init_state.clone());
+    assert!(events.is_empty());
+    assert_eq!(retried_state, init_state);
+}
+
 #[test]
 pub fn test_execute() {
     let (init_state, _) = initialize_contract();
     let owner = get_owner_address();
     let (started_state, _) =
-        start_callback(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
     let bidder = get_bidder_address();
     let bid = Bid {
         bidder,
@@ -670,7 +1143,7 @@ pub fn test_execute() {
     let ctx = create_ctx(third_party, 102);
     let (execute_state, execute_events) = execute(ctx, bid_state);
     assert_eq!(execute_events.len(), 0);
-    assert_eq!(execute_state.status, ENDED);
+    assert_eq!(execute_state.status, AuctionStatus::Ended {});
     // both owner and bidder should have valid claims
     assert_eq!(execute_state.claim_map.len(), 2);
     let owner_claim = execute_state.claim_map.get(&owner);
@@ -699,7 +1172,7 @@ pub fn test_execute_early() {
     let (init_state, _) = initialize_contract();
     let owner = get_owner_address();
     let (started_state, _) =
-        start_callback(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
     let bidder = get_bidder_address();
     let bid = Bid {
         bidder,
@@ -734,7 +1207,7 @@ pub fn test_cancel() {
     let (init_state, _) = initialize_contract();
     let owner = get_owner_address();
     let (started_state, _) =
-        start_callback(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
     let bidder = get_bidder_address();
     let bid = Bid {
         bidder,
@@ -750,7 +1223,7 @@ pub fn test_cancel() {
     let ctx = create_ctx(owner, 101);
     let (cancel_state, cancel_events) = cancel(ctx, bid_state);
     assert_eq!(cancel_events.len(), 0);
-    assert_eq!(cancel_state.status, CANCELLED);
+    assert_eq!(cancel_state.status, AuctionStatus::Cancelled {});
     // both owner and bidder should have valid claims
     assert_eq!(cancel_state.claim_map.len(), 2);
     let owner_claim = cancel_state.claim_map.get(&owner);
@@ -779,7 +1252,7 @@ pub fn test_cancel_not_owner() {
     let (init_state, _) = initialize_contract();
     let owner = get_owner_address();
     let (started_state, _) =
-        start_callback(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
     let bidder = get_bidder_address();
     let bid = Bid {
         bidder,
@@ -802,7 +1275,7 @@ pub fn test_cancel_after_end_time() {
     let (init_state, _) = initialize_contract();
     let owner = get_owner_address();
     let (started_state, _) =
-        start_callback(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
     let bidder = get_bidder_address();
     let bid = Bid {
         bidder,
@@ -820,13 +1293,88 @@ pub fn test_cancel_after_end_time() {
 }
 
 #[test]
-#[should_panic]
-pub fn test_cancel_not_bidding() {
+#[should_panic(
+    expected = "[132] Tried to cancel an auction that cannot be cancelled once a bid was accepted"
+)]
+pub fn test_cancel_after_bids_forbidden() {
+    let mut state = started_contract_with_bid(2000);
+    state.cancellable_after_bids = false;
+    cancel(create_ctx(get_owner_address(), 6), state);
+}
+
+#[test]
+pub fn test_cancel_without_bids_when_forbidden_after_bids() {
     let (init_state, _) = initialize_contract();
     let owner = get_owner_address();
-    // need block time <102 since this is end time
-    let ctx = create_ctx(owner, 101);
-    cancel(ctx, init_state);
+    let mut config = english_auction_config();
+    config.cancellable_after_bids = false;
+    let (configured_state, _) = configure(create_ctx(owner, 1), init_state, config);
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 2),
+        create_callback_ctx(true),
+        configured_state,
+    );
+    let (cancelled_state, _) = cancel(create_ctx(owner, 6), started_state);
+    assert_eq!(cancelled_state.status, AuctionStatus::Cancelled {});
+}
+
+fn contract_with_setup_window() -> AuctionContractState {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.setup_window_millis = Some(24 * 3_600_000);
+    let (configured_state, _) = configure(create_ctx(get_owner_address(), 1), init_state, config);
+    configured_state
+}
+
+#[test]
+pub fn test_expire() {
+    let (expired_state, events) = expire(
+        create_ctx(get_third_party_address(), 25),
+        contract_with_setup_window(),
+    );
+    assert_eq!(events.len(), 0);
+    assert_eq!(expired_state.status, AuctionStatus::Expired {});
+}
+
+#[test]
+pub fn test_finalize_expired() {
+    let (expired_state, _) = expire(
+        create_ctx(get_third_party_address(), 25),
+        contract_with_setup_window(),
+    );
+    let (finalized_state, _) = finalize(create_ctx(get_owner_address(), 26), expired_state);
+    assert_eq!(finalized_state.status, AuctionStatus::Finalized {});
+}
+
+#[test]
+#[should_panic(
+    expected = "[135] Tried to expire an auction that was started or is within its setup window"
+)]
+pub fn test_expire_within_setup_window() {
+    expire(
+        create_ctx(get_third_party_address(), 24),
+        contract_with_setup_window(),
+    );
+}
+
+#[test]
+#[should_panic(expected = "[134] The setup window of the auction has passed")]
+pub fn test_start_after_setup_window() {
+    start(
+        create_ctx(get_owner_address(), 25),
+        contract_with_setup_window(),
+    );
+}
+
+#[test]
+pub fn test_cancel_in_creation() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    // an abandoned setup can be closed out even after its end time
+    let (cancelled_state, events) = cancel(create_ctx(owner, 150), init_state);
+    assert_eq!(events.len(), 0);
+    assert_eq!(cancelled_state.status, AuctionStatus::Cancelled {});
+    assert!(cancelled_state.claim_map.is_empty());
 }
 
 #[test]
@@ -835,7 +1383,7 @@ pub fn test_cancel_after_execute() {
     let (init_state, _) = initialize_contract();
     let owner = get_owner_address();
     let (started_state, _) =
-        start_callback(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
     let bidder = get_bidder_address();
     let bid = Bid {
         bidder,
@@ -855,3 +1403,7649 @@ pub fn test_cancel_after_execute() {
     let cancel_ctx = create_ctx(owner, 103);
     cancel(cancel_ctx, execute_state);
 }
+
+#[test]
+pub fn test_initialize_dutch() {
+    let (state, events) = initialize_dutch_contract();
+    assert_eq!(0, events.len());
+    assert_eq!(DUTCH, state.auction_format);
+    assert_eq!(
+        Some(PriceSchedule {
+            start_price: 11_000,
+            decrement_per_hour: 100,
+            curve: DecayCurve::Linear {},
+        }),
+        state.price_schedule
+    );
+}
+
+#[test]
+#[should_panic]
+pub fn test_initialize_dutch_without_schedule() {
+    let ctx = create_ctx(get_owner_address(), 2);
+    initialize(
+        ctx,
+        AuctionConfig {
+            token_amount_for_sale: 100_000,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            auction_format: DUTCH,
+            ..english_auction_config()
+        },
+    );
+}
+
+#[test]
+#[should_panic]
+pub fn test_initialize_dutch_start_below_reserve() {
+    let ctx = create_ctx(get_owner_address(), 2);
+    initialize(
+        ctx,
+        AuctionConfig {
+            token_amount_for_sale: 100_000,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            auction_format: DUTCH,
+            price_schedule: Some(PriceSchedule {
+                start_price: 999,
+                decrement_per_hour: 100,
+                curve: DecayCurve::Linear {},
+            }),
+            ..english_auction_config()
+        },
+    );
+}
+
+#[test]
+pub fn test_dutch_current_price() {
+    let (state, _) = initialize_dutch_contract();
+    // contract init at block time 2
+    assert_eq!(state.current_price(2 * 3_600_000), 11_000);
+    assert_eq!(state.current_price(12 * 3_600_000), 10_000);
+    assert_eq!(state.current_price(12 * 3_600_000 + 1_800_000), 9_950);
+    // the price never drops below the reserve
+    assert_eq!(state.current_price(200 * 3_600_000), 1_000);
+}
+
+#[test]
+pub fn test_dutch_stepped_price() {
+    let (mut state, _) = initialize_dutch_contract();
+    state.price_schedule = Some(PriceSchedule {
+        start_price: 11_000,
+        decrement_per_hour: 100,
+        curve: DecayCurve::Stepped {
+            interval_millis: 10 * 3_600_000,
+        },
+    });
+    assert_eq!(state.current_price(11 * 3_600_000), 11_000);
+    assert_eq!(state.current_price(12 * 3_600_000), 10_000);
+    assert_eq!(state.current_price(21 * 3_600_000), 10_000);
+    assert_eq!(state.current_price(200 * 3_600_000), 1_000);
+}
+
+#[test]
+pub fn test_dutch_exponential_price() {
+    let (mut state, _) = initialize_dutch_contract();
+    state.price_schedule = Some(PriceSchedule {
+        start_price: 11_000,
+        decrement_per_hour: 0,
+        curve: DecayCurve::Exponential {
+            interval_millis: 3_600_000,
+            decay_bps: 1_000,
+        },
+    });
+    assert_eq!(state.current_price(2 * 3_600_000), 11_000);
+    assert_eq!(state.current_price(3 * 3_600_000), 9_900);
+    assert_eq!(state.current_price(4 * 3_600_000 + 1_800_000), 8_910);
+    assert_eq!(state.current_price(100 * 3_600_000), 1_000);
+}
+
+#[test]
+#[should_panic(
+    expected = "[69] Tried to create a price decay with an empty interval or a decay above 100%"
+)]
+pub fn test_initialize_dutch_empty_decay_interval() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.auction_format = DUTCH;
+    config.price_schedule = Some(PriceSchedule {
+        start_price: 11_000,
+        decrement_per_hour: 100,
+        curve: DecayCurve::Stepped { interval_millis: 0 },
+    });
+    configure(create_ctx(get_owner_address(), 2), init_state, config);
+}
+
+#[test]
+pub fn test_accept_price() {
+    let (init_state, _) = initialize_dutch_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    let bidder = get_bidder_address();
+    let (accept_state, events) = accept_price(create_ctx(bidder, 12), started_state.clone());
+    assert_eq!(accept_state, started_state);
+    assert_eq!(events.len(), 1);
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_currency_token_address(), Shortname::from_u32(3))
+        .argument(bidder)
+        .argument(get_contract_address())
+        .argument(10_000u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(9))
+        .argument(bidder)
+        .argument(10_000u128)
+        .done();
+    assert_eq!(*events.first().unwrap(), expected_event.build());
+}
+
+#[test]
+#[should_panic]
+pub fn test_accept_price_english() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    accept_price(create_ctx(get_bidder_address(), 12), started_state);
+}
+
+#[test]
+pub fn test_accept_price_callback() {
+    let (init_state, _) = initialize_dutch_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    let bidder = get_bidder_address();
+    let bid = Bid {
+        bidder,
+        amount: 10_000,
+    };
+    let (accept_state, events) = accept_price_callback(
+        create_ctx(bidder, 12),
+        create_callback_ctx(true),
+        started_state,
+        bid.clone(),
+    );
+    assert_eq!(events.len(), 0);
+    assert_eq!(accept_state.status, AuctionStatus::Ended {});
+    assert_eq!(accept_state.highest_bidder, bid);
+    assert_eq!(
+        *accept_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 10_000,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        *accept_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 100_000,
+        }
+    );
+}
+
+#[test]
+pub fn test_accept_price_callback_already_accepted() {
+    let (init_state, _) = initialize_dutch_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    let bidder = get_bidder_address();
+    let (accepted_state, _) = accept_price_callback(
+        create_ctx(bidder, 12),
+        create_callback_ctx(true),
+        started_state,
+        Bid {
+            bidder,
+            amount: 10_000,
+        },
+    );
+    let third_party = get_third_party_address();
+    let (late_state, _) = accept_price_callback(
+        create_ctx(third_party, 13),
+        create_callback_ctx(true),
+        accepted_state,
+        Bid {
+            bidder: third_party,
+            amount: 9_900,
+        },
+    );
+    assert_eq!(late_state.highest_bidder.bidder, bidder);
+    assert_eq!(
+        late_state.rejected_bids.get(&third_party).unwrap().reason,
+        RejectionReason::Closed {}
+    );
+    assert_eq!(
+        *late_state.claim_map.get(&third_party).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 9_900,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+pub fn test_bid_callback_dutch_refunded() {
+    let (init_state, _) = initialize_dutch_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    let bidder = get_bidder_address();
+    let bid = Bid {
+        bidder,
+        amount: 20_000,
+    };
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_state,
+        bid,
+    );
+    assert_eq!(bid_state.highest_bidder.amount, 0);
+    assert_eq!(
+        *bid_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 20_000,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+fn initialize_second_price_contract() -> (AuctionContractState, Vec<EventGroup>) {
+    let ctx = create_ctx(get_owner_address(), 2);
+    initialize(
+        ctx,
+        AuctionConfig {
+            token_amount_for_sale: 100_000,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            settlement_mode: SECOND_PRICE,
+            ..english_auction_config()
+        },
+    )
+}
+
+#[test]
+#[should_panic]
+pub fn test_initialize_dutch_second_price() {
+    let ctx = create_ctx(get_owner_address(), 2);
+    initialize(
+        ctx,
+        AuctionConfig {
+            token_amount_for_sale: 100_000,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            auction_format: DUTCH,
+            price_schedule: Some(PriceSchedule {
+                start_price: 11_000,
+                decrement_per_hour: 100,
+                curve: DecayCurve::Linear {},
+            }),
+            settlement_mode: SECOND_PRICE,
+            ..english_auction_config()
+        },
+    );
+}
+
+#[test]
+pub fn test_bid_callback_tracks_second_highest_bid() {
+    let (init_state, _) = initialize_second_price_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let (first_bid_state, _) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_state,
+        Bid {
+            bidder,
+            amount: 2000,
+        },
+    );
+    let (second_bid_state, _) = bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        first_bid_state,
+        Bid {
+            bidder: third_party,
+            amount: 5000,
+        },
+    );
+    assert_eq!(
+        second_bid_state.highest_bidder,
+        Bid {
+            bidder: third_party,
+            amount: 5000,
+        }
+    );
+    assert_eq!(
+        second_bid_state.second_highest_bidder,
+        Bid {
+            bidder,
+            amount: 2000,
+        }
+    );
+}
+
+#[test]
+pub fn test_execute_second_price() {
+    let (init_state, _) = initialize_second_price_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let (first_bid_state, _) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_state,
+        Bid {
+            bidder,
+            amount: 2000,
+        },
+    );
+    let (second_bid_state, _) = bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        first_bid_state,
+        Bid {
+            bidder: third_party,
+            amount: 5000,
+        },
+    );
+    let (execute_state, _) = execute(create_ctx(third_party, 102), second_bid_state);
+    assert_eq!(execute_state.status, AuctionStatus::Ended {});
+    // owner receives the second highest bid
+    assert_eq!(
+        *execute_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 2000,
+            tokens_for_sale: 0,
+        }
+    );
+    // outbid bidder is refunded in full
+    assert_eq!(
+        *execute_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 2000,
+            tokens_for_sale: 0,
+        }
+    );
+    // winner is refunded the difference to the clearing price
+    assert_eq!(
+        *execute_state.claim_map.get(&third_party).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 3000,
+            tokens_for_sale: 100_000,
+        }
+    );
+}
+
+#[test]
+pub fn test_execute_second_price_single_bid_pays_reserve() {
+    let (init_state, _) = initialize_second_price_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    let bidder = get_bidder_address();
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_state,
+        Bid {
+            bidder,
+            amount: 2000,
+        },
+    );
+    let (execute_state, _) = execute(create_ctx(bidder, 102), bid_state);
+    assert_eq!(
+        *execute_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 1000,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        *execute_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 1000,
+            tokens_for_sale: 100_000,
+        }
+    );
+}
+
+fn initialize_buyout_contract() -> (AuctionContractState, Vec<EventGroup>) {
+    let ctx = create_ctx(get_owner_address(), 2);
+    initialize(
+        ctx,
+        AuctionConfig {
+            token_amount_for_sale: 100_000,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            buyout_price: Some(5_000),
+            ..english_auction_config()
+        },
+    )
+}
+
+#[test]
+#[should_panic]
+pub fn test_initialize_buyout_below_reserve() {
+    let ctx = create_ctx(get_owner_address(), 2);
+    initialize(
+        ctx,
+        AuctionConfig {
+            token_amount_for_sale: 100_000,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            buyout_price: Some(999),
+            ..english_auction_config()
+        },
+    );
+}
+
+#[test]
+pub fn test_bid_callback_buyout() {
+    let (init_state, _) = initialize_buyout_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let (first_bid_state, _) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_state,
+        Bid {
+            bidder,
+            amount: 4_950,
+        },
+    );
+    // meets the buyout price even though it is below the minimum increment
+    let (buyout_state, _) = bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        first_bid_state,
+        Bid {
+            bidder: third_party,
+            amount: 5_000,
+        },
+    );
+    assert_eq!(buyout_state.status, AuctionStatus::Ended {});
+    assert_eq!(
+        *buyout_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 5_000,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        *buyout_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 4_950,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        *buyout_state.claim_map.get(&third_party).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 100_000,
+        }
+    );
+}
+
+#[test]
+pub fn test_bid_callback_buyout_refunds_excess() {
+    let (init_state, _) = initialize_buyout_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    let bidder = get_bidder_address();
+    let (buyout_state, _) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_state,
+        Bid {
+            bidder,
+            amount: 6_000,
+        },
+    );
+    assert_eq!(buyout_state.status, AuctionStatus::Ended {});
+    assert_eq!(
+        *buyout_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 1_000,
+            tokens_for_sale: 100_000,
+        }
+    );
+}
+
+#[test]
+pub fn test_bid_callback_below_buyout_keeps_bidding() {
+    let (init_state, _) = initialize_buyout_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    let bidder = get_bidder_address();
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_state,
+        Bid {
+            bidder,
+            amount: 4_999,
+        },
+    );
+    assert_eq!(bid_state.status, AuctionStatus::Bidding {});
+    assert_eq!(bid_state.highest_bidder.amount, 4_999);
+}
+
+fn initialize_nft_contract() -> (AuctionContractState, Vec<EventGroup>) {
+    let ctx = create_ctx(get_owner_address(), 2);
+    initialize(
+        ctx,
+        AuctionConfig {
+            token_amount_for_sale: 1,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            sale_token_id: Some(42),
+            ..english_auction_config()
+        },
+    )
+}
+
+#[test]
+#[should_panic]
+pub fn test_initialize_nft_amount_not_one() {
+    let ctx = create_ctx(get_owner_address(), 2);
+    initialize(
+        ctx,
+        AuctionConfig {
+            token_amount_for_sale: 2,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            sale_token_id: Some(42),
+            ..english_auction_config()
+        },
+    );
+}
+
+#[test]
+pub fn test_start_nft() {
+    let (state, _) = initialize_nft_contract();
+    let owner = get_owner_address();
+    let (_, events) = start(create_ctx(owner, 3), state);
+    let mut expected = EventGroup::builder();
+    expected
+        .call(get_commodity_token_address(), Shortname::from_u32(3))
+        .argument(owner)
+        .argument(get_contract_address())
+        .argument(42u128)
+        .done();
+    expected
+        .with_callback(ShortnameCallback::from_u32(2))
+        .done();
+    assert_eq!(*events.first().unwrap(), expected.build());
+}
+
+#[test]
+pub fn test_claim_nft() {
+    let (init_state, _) = initialize_nft_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    let bidder = get_bidder_address();
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_state,
+        Bid {
+            bidder,
+            amount: 2000,
+        },
+    );
+    let (execute_state, _) = execute(create_ctx(bidder, 102), bid_state);
+    assert_eq!(
+        *execute_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 1,
+        }
+    );
+    let (_, claim_events) = claim(create_ctx(bidder, 103), execute_state);
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_commodity_token_address(), Shortname::from_u32(1))
+        .argument(bidder)
+        .argument(42u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x13))
+        .argument(bidder)
+        .argument(0u128)
+        .argument(1u128)
+        .done();
+    assert_eq!(*claim_events.first().unwrap(), expected_event.build());
+}
+
+fn started_contract_with_lot() -> AuctionContractState {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (lot_state, _) = add_lot(create_ctx(owner, 2), init_state, 7, 500, 200, 50, None);
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), lot_state);
+    started_state
+}
+
+#[test]
+pub fn test_add_lot() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (lot_state, events) = add_lot(create_ctx(owner, 2), init_state, 7, 500, 200, 50, None);
+    assert_eq!(events.len(), 0);
+    assert_eq!(
+        *lot_state.lots.get(&7).unwrap(),
+        Lot {
+            token_amount_for_sale: 500,
+            highest_bidder: Bid {
+                bidder: owner,
+                amount: 0,
+            },
+            reserve_price: 200,
+            min_increment: 50,
+            end_time_millis: None,
+        }
+    );
+    // start escrows the tokens for the main sale and every lot
+    let (_, start_events) = start(create_ctx(owner, 3), lot_state);
+    let mut expected = EventGroup::builder();
+    expected
+        .call(get_commodity_token_address(), Shortname::from_u32(3))
+        .argument(owner)
+        .argument(get_contract_address())
+        .argument(100_500u128)
+        .done();
+    expected
+        .with_callback(ShortnameCallback::from_u32(2))
+        .done();
+    assert_eq!(*start_events.first().unwrap(), expected.build());
+}
+
+#[test]
+#[should_panic]
+pub fn test_add_lot_not_owner() {
+    let (init_state, _) = initialize_contract();
+    add_lot(
+        create_ctx(get_third_party_address(), 2),
+        init_state,
+        7,
+        500,
+        200,
+        50,
+        None,
+    );
+}
+
+#[test]
+#[should_panic]
+pub fn test_add_lot_duplicate_id() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (lot_state, _) = add_lot(create_ctx(owner, 2), init_state, 7, 500, 200, 50, None);
+    add_lot(create_ctx(owner, 2), lot_state, 7, 100, 200, 50, None);
+}
+
+#[test]
+pub fn test_bid_lot() {
+    let state = started_contract_with_lot();
+    let bidder = get_bidder_address();
+    let (bid_state, events) = bid_lot(create_ctx(bidder, 5), state.clone(), 7, 300);
+    assert_eq!(bid_state, state);
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_currency_token_address(), Shortname::from_u32(3))
+        .argument(bidder)
+        .argument(get_contract_address())
+        .argument(300u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x0c))
+        .argument(7u32)
+        .argument(bidder)
+        .argument(300u128)
+        .done();
+    assert_eq!(*events.first().unwrap(), expected_event.build());
+}
+
+#[test]
+pub fn test_bid_lot_callback() {
+    let state = started_contract_with_lot();
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let (first_state, _) = bid_lot_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        state,
+        7,
+        Bid {
+            bidder,
+            amount: 300,
+        },
+    );
+    // below the lot's minimum increment
+    let (second_state, _) = bid_lot_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        first_state,
+        7,
+        Bid {
+            bidder: third_party,
+            amount: 340,
+        },
+    );
+    let (third_state, _) = bid_lot_callback(
+        create_ctx(third_party, 7),
+        create_callback_ctx(true),
+        second_state,
+        7,
+        Bid {
+            bidder: third_party,
+            amount: 350,
+        },
+    );
+    assert_eq!(
+        third_state.lots.get(&7).unwrap().highest_bidder,
+        Bid {
+            bidder: third_party,
+            amount: 350,
+        }
+    );
+    assert_eq!(
+        *third_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 300,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        *third_state.claim_map.get(&third_party).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 340,
+            tokens_for_sale: 0,
+        }
+    );
+    // the main sale is unaffected
+    assert_eq!(third_state.highest_bidder.amount, 0);
+}
+
+#[test]
+pub fn test_bid_lot_callback_unknown_lot() {
+    let state = started_contract_with_lot();
+    let bidder = get_bidder_address();
+    let (bid_state, _) = bid_lot_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        state,
+        8,
+        Bid {
+            bidder,
+            amount: 300,
+        },
+    );
+    assert_eq!(
+        *bid_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 300,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+fn started_contract_with_timed_lot() -> AuctionContractState {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (lot_state, _) = add_lot(
+        create_ctx(owner, 2),
+        init_state,
+        7,
+        500,
+        200,
+        50,
+        Some(50 * 3_600_000),
+    );
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), lot_state);
+    started_state
+}
+
+#[test]
+pub fn test_settle_lot() {
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let (bid_state, _) = bid_lot_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_contract_with_timed_lot(),
+        7,
+        Bid {
+            bidder,
+            amount: 300,
+        },
+    );
+    // the lot ended, later bids are refunded
+    let (late_state, _) = bid_lot_callback(
+        create_ctx(third_party, 60),
+        create_callback_ctx(true),
+        bid_state,
+        7,
+        Bid {
+            bidder: third_party,
+            amount: 400,
+        },
+    );
+    assert_eq!(
+        late_state.rejected_bids.get(&third_party).unwrap().reason,
+        RejectionReason::Ended {}
+    );
+    let (settled_state, events) = settle_lot(create_ctx(third_party, 60), late_state, 7);
+    assert_eq!(events.len(), 0);
+    assert!(settled_state.lots.is_empty());
+    assert_eq!(
+        *settled_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 500,
+        }
+    );
+    assert_eq!(
+        settled_state
+            .claim_map
+            .get(&get_owner_address())
+            .unwrap()
+            .tokens_for_bidding,
+        300
+    );
+    assert_eq!(settled_state.status, AuctionStatus::Bidding {});
+}
+
+#[test]
+#[should_panic(expected = "[131] Tried to settle a lot that is unknown or has not ended yet")]
+pub fn test_settle_lot_before_end() {
+    settle_lot(
+        create_ctx(get_bidder_address(), 40),
+        started_contract_with_timed_lot(),
+        7,
+    );
+}
+
+#[test]
+#[should_panic(expected = "[130] Tried to add a lot ending outside of the auction")]
+pub fn test_add_lot_ending_after_auction() {
+    let (init_state, _) = initialize_contract();
+    add_lot(
+        create_ctx(get_owner_address(), 2),
+        init_state,
+        7,
+        500,
+        200,
+        50,
+        Some(200 * 3_600_000),
+    );
+}
+
+#[test]
+pub fn test_bid_batch() {
+    let state = started_contract_with_lot();
+    let bidder = get_bidder_address();
+    let bids = vec![
+        LotBid {
+            lot_id: 7,
+            amount: 300,
+        },
+        LotBid {
+            lot_id: 8,
+            amount: 400,
+        },
+    ];
+    let (_, events) = bid_batch(create_ctx(bidder, 5), state, bids.clone());
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_currency_token_address(), Shortname::from_u32(3))
+        .argument(bidder)
+        .argument(get_contract_address())
+        .argument(700u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x53))
+        .argument(bidder)
+        .argument(bids)
+        .done();
+    assert_eq!(events, vec![expected_event.build()]);
+}
+
+#[test]
+pub fn test_bid_batch_callback() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (lot_state, _) = add_lot(create_ctx(owner, 2), init_state, 7, 500, 200, 50, None);
+    let (lot_state, _) = add_lot(create_ctx(owner, 2), lot_state, 8, 500, 200, 50, None);
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), lot_state);
+    let bidder = get_bidder_address();
+    let (bid_state, _) = bid_batch_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_state,
+        bidder,
+        vec![
+            LotBid {
+                lot_id: 7,
+                amount: 300,
+            },
+            LotBid {
+                lot_id: 8,
+                amount: 400,
+            },
+            LotBid {
+                lot_id: 9,
+                amount: 100,
+            },
+        ],
+    );
+    assert_eq!(
+        bid_state.lots.get(&7).unwrap().highest_bidder,
+        Bid {
+            bidder,
+            amount: 300,
+        }
+    );
+    assert_eq!(bid_state.lots.get(&8).unwrap().highest_bidder.amount, 400);
+    // the bid on the unknown lot is refunded
+    assert_eq!(
+        bid_state.claim_map.get(&bidder).unwrap().tokens_for_bidding,
+        100
+    );
+    assert_eq!(bid_state.total_escrowed_bidding, 800);
+}
+
+#[test]
+#[should_panic(expected = "[129] Tried to bid on no lots")]
+pub fn test_bid_batch_empty() {
+    let state = started_contract_with_lot();
+    bid_batch(create_ctx(get_bidder_address(), 5), state, vec![]);
+}
+
+#[test]
+pub fn test_execute_settles_lots() {
+    let state = started_contract_with_lot();
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let (bid_state, _) = bid_lot_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        state,
+        7,
+        Bid {
+            bidder,
+            amount: 300,
+        },
+    );
+    let (execute_state, _) = execute(create_ctx(bidder, 102), bid_state);
+    assert_eq!(
+        *execute_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 500,
+        }
+    );
+    // owner keeps the unsold main sale and receives the lot's winning bid
+    assert_eq!(
+        *execute_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 300,
+            tokens_for_sale: 100_000,
+        }
+    );
+}
+
+#[test]
+pub fn test_execute_reserve_not_met() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    let (execute_state, _) = execute(create_ctx(get_third_party_address(), 102), started_state);
+    assert_eq!(execute_state.status, AuctionStatus::ReserveNotMet {});
+    // the owner gets the tokens for sale back, and nothing else is paid out
+    assert_eq!(execute_state.claim_map.len(), 1);
+    assert_eq!(
+        *execute_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 100_000,
+        }
+    );
+}
+
+#[test]
+pub fn test_cancel_refunds_lots() {
+    let state = started_contract_with_lot();
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let (bid_state, _) = bid_lot_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        state,
+        7,
+        Bid {
+            bidder,
+            amount: 300,
+        },
+    );
+    let (cancel_state, _) = cancel(create_ctx(owner, 6), bid_state);
+    assert_eq!(
+        *cancel_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 300,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        *cancel_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 100_500,
+        }
+    );
+}
+
+fn started_contract_with_bid(amount: u128) -> AuctionContractState {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    let bidder = get_bidder_address();
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_state,
+        Bid { bidder, amount },
+    );
+    bid_state
+}
+
+#[test]
+pub fn test_bid_top_up() {
+    let state = started_contract_with_bid(2000);
+    let bidder = get_bidder_address();
+    let (_, events) = bid(create_ctx(bidder, 6), state, 2500);
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_currency_token_address(), Shortname::from_u32(3))
+        .argument(bidder)
+        .argument(get_contract_address())
+        .argument(500u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x0d))
+        .argument(bidder)
+        .argument(2500u128)
+        .argument(500u128)
+        .done();
+    assert_eq!(*events.first().unwrap(), expected_event.build());
+}
+
+#[test]
+#[should_panic]
+pub fn test_bid_top_up_not_higher() {
+    let state = started_contract_with_bid(2000);
+    bid(create_ctx(get_bidder_address(), 6), state, 2000);
+}
+
+#[test]
+pub fn test_top_up_callback() {
+    let state = started_contract_with_bid(2000);
+    let bidder = get_bidder_address();
+    let (top_up_state, events) = top_up_callback(
+        create_ctx(bidder, 6),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder,
+            amount: 2500,
+        },
+        500,
+    );
+    assert_eq!(events.len(), 0);
+    assert_eq!(
+        top_up_state.highest_bidder,
+        Bid {
+            bidder,
+            amount: 2500,
+        }
+    );
+    // nothing is double locked
+    assert_eq!(top_up_state.claim_map.get(&bidder), None);
+}
+
+#[test]
+pub fn test_top_up_callback_outbid_in_meantime() {
+    let state = started_contract_with_bid(2000);
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let (outbid_state, _) = bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder: third_party,
+            amount: 3000,
+        },
+    );
+    let (top_up_state, _) = top_up_callback(
+        create_ctx(bidder, 7),
+        create_callback_ctx(true),
+        outbid_state,
+        Bid {
+            bidder,
+            amount: 2500,
+        },
+        500,
+    );
+    assert_eq!(top_up_state.highest_bidder.bidder, third_party);
+    assert_eq!(
+        *top_up_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 2500,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+pub fn test_proxy_bid() {
+    let state = started_contract_with_bid(2000);
+    let third_party = get_third_party_address();
+    let (_, events) = proxy_bid(create_ctx(third_party, 6), state, 5000);
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_currency_token_address(), Shortname::from_u32(3))
+        .argument(third_party)
+        .argument(get_contract_address())
+        .argument(5000u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x0f))
+        .argument(third_party)
+        .argument(5000u128)
+        .done();
+    assert_eq!(*events.first().unwrap(), expected_event.build());
+}
+
+#[test]
+#[should_panic]
+pub fn test_proxy_bid_by_highest_bidder() {
+    let state = started_contract_with_bid(2000);
+    proxy_bid(create_ctx(get_bidder_address(), 6), state, 5000);
+}
+
+#[test]
+pub fn test_proxy_bid_callback_bids_only_needed_amount() {
+    let state = started_contract_with_bid(2000);
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let (proxy_state, _) = proxy_bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder: third_party,
+            amount: 5000,
+        },
+    );
+    assert_eq!(
+        proxy_state.highest_bidder,
+        Bid {
+            bidder: third_party,
+            amount: 2100,
+        }
+    );
+    assert_eq!(proxy_state.highest_bidder_max, Some(5000));
+    assert_eq!(
+        *proxy_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 2000,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+pub fn test_proxy_bid_auto_raises_against_bid() {
+    let state = started_contract_with_bid(2000);
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let (proxy_state, _) = proxy_bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder: third_party,
+            amount: 5000,
+        },
+    );
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 7),
+        create_callback_ctx(true),
+        proxy_state,
+        Bid {
+            bidder,
+            amount: 3000,
+        },
+    );
+    assert_eq!(
+        bid_state.highest_bidder,
+        Bid {
+            bidder: third_party,
+            amount: 3100,
+        }
+    );
+    assert_eq!(
+        *bid_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 5000,
+            tokens_for_sale: 0,
+        }
+    );
+    // the winner is refunded the unused part of their maximum
+    let (execute_state, _) = execute(create_ctx(bidder, 102), bid_state);
+    assert_eq!(
+        *execute_state.claim_map.get(&third_party).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 1900,
+            tokens_for_sale: 100_000,
+        }
+    );
+}
+
+#[test]
+pub fn test_proxy_bid_outbid_by_higher_proxy() {
+    let state = started_contract_with_bid(2000);
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let (proxy_state, _) = proxy_bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder: third_party,
+            amount: 5000,
+        },
+    );
+    let (second_proxy_state, _) = proxy_bid_callback(
+        create_ctx(bidder, 7),
+        create_callback_ctx(true),
+        proxy_state,
+        Bid {
+            bidder,
+            amount: 8000,
+        },
+    );
+    assert_eq!(
+        second_proxy_state.highest_bidder,
+        Bid {
+            bidder,
+            amount: 5100,
+        }
+    );
+    assert_eq!(second_proxy_state.highest_bidder_max, Some(8000));
+    assert_eq!(
+        *second_proxy_state.claim_map.get(&third_party).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 5000,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+pub fn test_cancel_refunds_proxy_maximum() {
+    let state = started_contract_with_bid(2000);
+    let third_party = get_third_party_address();
+    let (proxy_state, _) = proxy_bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder: third_party,
+            amount: 5000,
+        },
+    );
+    let (cancel_state, _) = cancel(create_ctx(get_owner_address(), 7), proxy_state);
+    assert_eq!(
+        *cancel_state.claim_map.get(&third_party).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 5000,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+fn initialize_scheduled_contract() -> (AuctionContractState, Vec<EventGroup>) {
+    let ctx = create_ctx(get_owner_address(), 2);
+    initialize(
+        ctx,
+        AuctionConfig {
+            token_amount_for_sale: 100_000,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            start_time_millis: Some(10 * 3_600_000),
+            ..english_auction_config()
+        },
+    )
+}
+
+#[test]
+pub fn test_initialize_scheduled_start() {
+    let (state, _) = initialize_scheduled_contract();
+    assert_eq!(10 * 3_600_000, state.start_time_millis);
+    assert_eq!(110 * 3_600_000, state.end_time_millis);
+}
+
+#[test]
+#[should_panic]
+pub fn test_initialize_scheduled_start_in_past() {
+    let ctx = create_ctx(get_owner_address(), 2);
+    initialize(
+        ctx,
+        AuctionConfig {
+            token_amount_for_sale: 100_000,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            start_time_millis: Some(3_600_000),
+            ..english_auction_config()
+        },
+    );
+}
+
+#[test]
+pub fn test_start_callback_scheduled() {
+    let (init_state, _) = initialize_scheduled_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    assert_eq!(started_state.status, AuctionStatus::BiddingScheduled {});
+}
+
+#[test]
+pub fn test_bid_callback_before_scheduled_start() {
+    let (init_state, _) = initialize_scheduled_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    let bidder = get_bidder_address();
+    let (early_state, _) = bid_callback(
+        create_ctx(bidder, 9),
+        create_callback_ctx(true),
+        started_state,
+        Bid {
+            bidder,
+            amount: 2000,
+        },
+    );
+    assert_eq!(early_state.status, AuctionStatus::BiddingScheduled {});
+    assert_eq!(early_state.highest_bidder.amount, 0);
+    assert_eq!(
+        *early_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 2000,
+            tokens_for_sale: 0,
+        }
+    );
+    let (open_state, _) = bid_callback(
+        create_ctx(bidder, 10),
+        create_callback_ctx(true),
+        early_state,
+        Bid {
+            bidder,
+            amount: 2000,
+        },
+    );
+    assert_eq!(open_state.status, AuctionStatus::Bidding {});
+    assert_eq!(open_state.highest_bidder.amount, 2000);
+}
+
+#[test]
+pub fn test_cancel_before_scheduled_start() {
+    let (init_state, _) = initialize_scheduled_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    let (cancel_state, _) = cancel(create_ctx(owner, 4), started_state);
+    assert_eq!(cancel_state.status, AuctionStatus::Cancelled {});
+}
+
+#[test]
+pub fn test_block_bidder_refunds_highest_bid() {
+    let state = started_contract_with_bid(2000);
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let (blocked_state, events) = block_bidder(create_ctx(owner, 6), state, bidder);
+    assert_eq!(events.len(), 0);
+    assert!(blocked_state.blocklist.contains(&bidder));
+    assert_eq!(
+        blocked_state.highest_bidder,
+        Bid {
+            bidder: owner,
+            amount: 0,
+        }
+    );
+    assert_eq!(
+        *blocked_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 2000,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+#[should_panic]
+pub fn test_block_bidder_not_owner() {
+    let state = started_contract_with_bid(2000);
+    block_bidder(
+        create_ctx(get_third_party_address(), 6),
+        state,
+        get_bidder_address(),
+    );
+}
+
+#[test]
+#[should_panic]
+pub fn test_bid_blocked() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let third_party = get_third_party_address();
+    let (blocked_state, _) = block_bidder(create_ctx(owner, 2), init_state, third_party);
+    bid(create_ctx(third_party, 5), blocked_state, 2000);
+}
+
+#[test]
+pub fn test_bid_callback_blocked_refunded() {
+    let state = started_contract_with_bid(2000);
+    let owner = get_owner_address();
+    let third_party = get_third_party_address();
+    let (blocked_state, _) = block_bidder(create_ctx(owner, 6), state, third_party);
+    let (bid_state, _) = bid_callback(
+        create_ctx(third_party, 7),
+        create_callback_ctx(true),
+        blocked_state,
+        Bid {
+            bidder: third_party,
+            amount: 3000,
+        },
+    );
+    assert_eq!(bid_state.highest_bidder.bidder, get_bidder_address());
+    assert_eq!(
+        *bid_state.claim_map.get(&third_party).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 3000,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+pub fn test_unblock_bidder() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let third_party = get_third_party_address();
+    let (blocked_state, _) = block_bidder(create_ctx(owner, 2), init_state, third_party);
+    let (unblocked_state, _) = unblock_bidder(create_ctx(owner, 2), blocked_state, third_party);
+    assert!(unblocked_state.blocklist.is_empty());
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        unblocked_state,
+    );
+    let (_, events) = bid(create_ctx(third_party, 5), started_state, 2000);
+    assert_eq!(events.len(), 1);
+}
+
+fn get_royalty_receiver_address() -> Address {
+    Address {
+        address_type: AddressType::Account,
+        identifier: [0u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 7],
+    }
+}
+
+#[test]
+#[should_panic]
+pub fn test_initialize_royalty_without_receiver() {
+    let ctx = create_ctx(get_owner_address(), 2);
+    initialize(
+        ctx,
+        AuctionConfig {
+            token_amount_for_sale: 100_000,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            royalty_bps: 500,
+            ..english_auction_config()
+        },
+    );
+}
+
+#[test]
+#[should_panic]
+pub fn test_initialize_royalty_above_full_amount() {
+    let ctx = create_ctx(get_owner_address(), 2);
+    initialize(
+        ctx,
+        AuctionConfig {
+            token_amount_for_sale: 100_000,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            royalty_receiver: Some(get_royalty_receiver_address()),
+            royalty_bps: 10_001,
+            ..english_auction_config()
+        },
+    );
+}
+
+#[test]
+pub fn test_execute_pays_royalty() {
+    let mut state = started_contract_with_bid(2000);
+    let receiver = get_royalty_receiver_address();
+    state.royalty_receiver = Some(receiver);
+    state.royalty_bps = 250;
+    let owner = get_owner_address();
+    let (execute_state, _) = execute(create_ctx(owner, 102), state);
+    assert_eq!(
+        *execute_state.claim_map.get(&receiver).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 50,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        *execute_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 1950,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+fn get_fee_collector_address() -> Address {
+    Address {
+        address_type: AddressType::Account,
+        identifier: [0u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 8],
+    }
+}
+
+#[test]
+#[should_panic]
+pub fn test_initialize_fee_without_collector() {
+    let ctx = create_ctx(get_owner_address(), 2);
+    initialize(
+        ctx,
+        AuctionConfig {
+            token_amount_for_sale: 100_000,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            fee_bps: 100,
+            ..english_auction_config()
+        },
+    );
+}
+
+#[test]
+#[should_panic]
+pub fn test_initialize_fee_and_royalty_above_full_amount() {
+    let ctx = create_ctx(get_owner_address(), 2);
+    initialize(
+        ctx,
+        AuctionConfig {
+            token_amount_for_sale: 100_000,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            royalty_receiver: Some(get_royalty_receiver_address()),
+            royalty_bps: 6_000,
+            fee_collector: Some(get_fee_collector_address()),
+            fee_bps: 4_001,
+            ..english_auction_config()
+        },
+    );
+}
+
+#[test]
+pub fn test_execute_pays_fee_and_royalty() {
+    let mut state = started_contract_with_bid(2000);
+    let receiver = get_royalty_receiver_address();
+    let collector = get_fee_collector_address();
+    state.royalty_receiver = Some(receiver);
+    state.royalty_bps = 250;
+    state.fee_collector = Some(collector);
+    state.fee_bps = 100;
+    let owner = get_owner_address();
+    let (execute_state, _) = execute(create_ctx(owner, 102), state);
+    assert_eq!(
+        *execute_state.claim_map.get(&collector).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 20,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        *execute_state.claim_map.get(&receiver).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 50,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        *execute_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 1930,
+            tokens_for_sale: 0,
+        }
+    );
+    // the fee collector claims through the regular claim flow
+    let (claim_state, claim_events) = claim(create_ctx(collector, 103), execute_state);
+    assert_eq!(claim_events.len(), 1);
+    assert_eq!(
+        *claim_state.claim_map.get(&collector).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+pub fn test_execute_withholds_shares() {
+    let mut state = started_contract_with_bid(2000);
+    let tax_authority = get_fee_collector_address();
+    let charity = get_third_party_address();
+    state.withholdings = vec![
+        Withholding {
+            receiver: tax_authority,
+            bps: 200,
+        },
+        Withholding {
+            receiver: charity,
+            bps: 500,
+        },
+    ];
+    let owner = get_owner_address();
+    let (execute_state, _) = execute(create_ctx(owner, 102), state);
+    let claimable = |address: &Address| {
+        execute_state
+            .claim_map
+            .get(address)
+            .unwrap()
+            .tokens_for_bidding
+    };
+    assert_eq!(claimable(&tax_authority), 40);
+    assert_eq!(claimable(&charity), 100);
+    assert_eq!(claimable(&owner), 1860);
+}
+
+#[test]
+#[should_panic(expected = "[17] Tried to create a royalty, fee and keeper reward above 100%")]
+pub fn test_initialize_withholdings_exceed_proceeds() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.withholdings = vec![
+        Withholding {
+            receiver: get_fee_collector_address(),
+            bps: 6_000,
+        },
+        Withholding {
+            receiver: get_third_party_address(),
+            bps: 5_000,
+        },
+    ];
+    configure(create_ctx(get_owner_address(), 2), init_state, config);
+}
+
+#[test]
+pub fn test_bid_with_referrer() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    let bidder = get_bidder_address();
+    let referrer = get_third_party_address();
+    let (bid_state, events) =
+        bid_with_referrer(create_ctx(bidder, 5), started_state, 2000, referrer);
+    assert_eq!(events.len(), 1);
+    assert_eq!(bid_state.referrers.get(&bidder), Some(&referrer));
+    // the first referrer named by the bidder is kept
+    let (bid_state, _) = bid_with_referrer(create_ctx(bidder, 6), bid_state, 2500, owner);
+    assert_eq!(bid_state.referrers.get(&bidder), Some(&referrer));
+}
+
+#[test]
+#[should_panic(expected = "[102] Bidders cannot refer themselves")]
+pub fn test_bid_with_referrer_self() {
+    let state = started_contract_with_bid(2000);
+    let bidder = get_bidder_address();
+    bid_with_referrer(create_ctx(bidder, 6), state, 2500, bidder);
+}
+
+#[test]
+pub fn test_bid_for_delegator() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    let bidder = get_bidder_address();
+    let custodian = get_third_party_address();
+    let (delegated_state, _) = delegate_bidding(create_ctx(bidder, 4), started_state, custodian);
+    let (_, events) = bid_for(create_ctx(custodian, 5), delegated_state, bidder, 2000);
+    // The escrow is taken from the delegator, who places the bid
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_currency_token_address(), Shortname::from_u32(3))
+        .argument(bidder)
+        .argument(get_contract_address())
+        .argument(2_000u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(4))
+        .argument(bidder)
+        .argument(2_000u128)
+        .done();
+    assert_eq!(events, vec![expected_event.build()]);
+}
+
+#[test]
+#[should_panic(expected = "[108] Only the delegate of a bidder can bid on their behalf")]
+pub fn test_bid_for_revoked_delegate() {
+    let bidder = get_bidder_address();
+    let custodian = get_third_party_address();
+    let (delegated_state, _) = delegate_bidding(
+        create_ctx(bidder, 5),
+        started_contract_with_bid(2000),
+        custodian,
+    );
+    let (revoked_state, _) = delegate_bidding(create_ctx(bidder, 6), delegated_state, bidder);
+    assert!(revoked_state.bidding_delegates.is_empty());
+    bid_for(create_ctx(custodian, 7), revoked_state, bidder, 2500);
+}
+
+#[test]
+pub fn test_execute_pays_referral_reward() {
+    let mut state = started_contract_with_bid(2000);
+    let referrer = get_third_party_address();
+    state.referral_bps = 500;
+    state.referrers.insert(get_bidder_address(), referrer);
+    let owner = get_owner_address();
+    let (execute_state, _) = execute(create_ctx(owner, 102), state);
+    assert_eq!(
+        *execute_state.claim_map.get(&referrer).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 100,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        *execute_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 1900,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+pub fn test_execute_credits_proceeds_receiver() {
+    let owner = get_owner_address();
+    let treasury = get_third_party_address();
+    let (receiver_state, _) = set_proceeds_receiver(
+        create_ctx(owner, 6),
+        started_contract_with_bid(2000),
+        Some(treasury),
+    );
+    let (execute_state, _) = execute(create_ctx(owner, 102), receiver_state);
+    assert_eq!(
+        *execute_state.claim_map.get(&treasury).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 2000,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        execute_state
+            .claim_map
+            .get(&owner)
+            .map_or(0, |claimable| claimable.tokens_for_bidding),
+        0
+    );
+}
+
+#[test]
+#[should_panic(expected = "[1] Only the contract owner can perform this action")]
+pub fn test_set_proceeds_receiver_not_owner() {
+    let bidder = get_bidder_address();
+    set_proceeds_receiver(
+        create_ctx(bidder, 6),
+        started_contract_with_bid(2000),
+        Some(bidder),
+    );
+}
+
+#[test]
+pub fn test_execute_no_referral_reward_for_losing_referral() {
+    let mut state = started_contract_with_bid(2000);
+    let referrer = get_third_party_address();
+    state.referral_bps = 500;
+    state
+        .referrers
+        .insert(get_royalty_receiver_address(), referrer);
+    let owner = get_owner_address();
+    let (execute_state, _) = execute(create_ctx(owner, 102), state);
+    assert!(!execute_state.claim_map.contains_key(&referrer));
+    assert_eq!(
+        *execute_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 2000,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+pub fn test_execute_splits_proceeds() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let receiver = get_royalty_receiver_address();
+    let collector = get_fee_collector_address();
+    let (beneficiary_state, _) = set_beneficiaries(
+        create_ctx(owner, 2),
+        init_state,
+        vec![
+            Beneficiary {
+                receiver,
+                weight: 2,
+            },
+            Beneficiary {
+                receiver: collector,
+                weight: 1,
+            },
+        ],
+    );
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        beneficiary_state,
+    );
+    let bidder = get_bidder_address();
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_state,
+        Bid {
+            bidder,
+            amount: 2000,
+        },
+    );
+    let (execute_state, _) = execute(create_ctx(owner, 102), bid_state);
+    assert_eq!(
+        *execute_state.claim_map.get(&receiver).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 1333,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        *execute_state.claim_map.get(&collector).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 666,
+            tokens_for_sale: 0,
+        }
+    );
+    // the rounding remainder goes to the owner
+    assert_eq!(
+        *execute_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 1,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+#[should_panic]
+pub fn test_set_beneficiaries_not_owner() {
+    let (init_state, _) = initialize_contract();
+    set_beneficiaries(
+        create_ctx(get_third_party_address(), 2),
+        init_state,
+        vec![Beneficiary {
+            receiver: get_third_party_address(),
+            weight: 1,
+        }],
+    );
+}
+
+#[test]
+#[should_panic]
+pub fn test_set_beneficiaries_after_start() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    set_beneficiaries(
+        create_ctx(owner, 4),
+        started_state,
+        vec![Beneficiary {
+            receiver: get_third_party_address(),
+            weight: 1,
+        }],
+    );
+}
+
+#[test]
+#[should_panic]
+pub fn test_set_beneficiaries_zero_weight() {
+    let (init_state, _) = initialize_contract();
+    set_beneficiaries(
+        create_ctx(get_owner_address(), 2),
+        init_state,
+        vec![Beneficiary {
+            receiver: get_third_party_address(),
+            weight: 0,
+        }],
+    );
+}
+
+#[test]
+pub fn test_error_codes() {
+    assert_eq!(AuctionError::NotOwner {}.code(), 1);
+    assert_eq!(AuctionError::BeforeEndTime {}.code(), 4);
+    assert_eq!(AuctionError::ZeroBeneficiaryWeight {}.code(), 28);
+}
+
+#[test]
+pub fn test_error_codes_are_unique() {
+    let codes: BTreeSet<u8> = AuctionError::ALL.iter().map(AuctionError::code).collect();
+    assert_eq!(codes.len(), AuctionError::ALL.len());
+    assert!(!codes.contains(&0));
+}
+
+#[test]
+#[should_panic(expected = "[1] Only the contract owner can perform this action")]
+pub fn test_start_not_owner_error_code() {
+    let (state, _) = initialize_contract();
+    start(create_ctx(get_third_party_address(), 3), state);
+}
+
+#[test]
+#[should_panic(expected = "[4] Tried to execute the auction before auction end block time")]
+pub fn test_execute_early_error_code() {
+    let state = started_contract_with_bid(2000);
+    execute(create_ctx(get_third_party_address(), 101), state);
+}
+
+#[test]
+#[should_panic(expected = "[6] Transfer event did not succeed")]
+pub fn test_bid_callback_transfer_unsuccessful_error_code() {
+    let state = started_contract_with_bid(2000);
+    let third_party = get_third_party_address();
+    bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(false),
+        state,
+        Bid {
+            bidder: third_party,
+            amount: 3000,
+        },
+    );
+}
+
+#[test]
+#[should_panic(expected = "[29] An amount exceeded the supported range")]
+pub fn test_claim_map_overflow_reverts() {
+    let mut state = started_contract_with_bid(2000);
+    let third_party = get_third_party_address();
+    state.claim_map.insert(
+        get_bidder_address(),
+        TokenClaim {
+            tokens_for_bidding: u128::MAX - 1000,
+            tokens_for_sale: 0,
+        },
+    );
+    bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder: third_party,
+            amount: 3000,
+        },
+    );
+}
+
+#[test]
+pub fn test_bid_near_overflow() {
+    let mut state = started_contract_with_bid(2000);
+    state.highest_bidder.amount = u128::MAX - 50;
+    let third_party = get_third_party_address();
+    let (bid_state, _) = bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder: third_party,
+            amount: u128::MAX,
+        },
+    );
+    assert_eq!(
+        bid_state.highest_bidder,
+        Bid {
+            bidder: third_party,
+            amount: u128::MAX,
+        }
+    );
+}
+
+#[test]
+pub fn test_execute_fee_on_near_overflow_proceeds() {
+    let mut state = started_contract_with_bid(2000);
+    let collector = get_fee_collector_address();
+    state.highest_bidder.amount = u128::MAX;
+    state.fee_collector = Some(collector);
+    state.fee_bps = 5_000;
+    let (execute_state, _) = execute(create_ctx(get_owner_address(), 102), state);
+    assert_eq!(
+        *execute_state.claim_map.get(&collector).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: u128::MAX / 2,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        *execute_state.claim_map.get(&get_owner_address()).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: u128::MAX / 2 + 1,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+pub fn test_refund_all() {
+    let state = started_contract_with_bid(2000);
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let (cancel_state, _) = cancel(create_ctx(owner, 6), state);
+    let (refund_state, refund_events) = refund_all(create_ctx(owner, 7), cancel_state);
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_currency_token_address(), Shortname::from_u32(1))
+        .argument(bidder)
+        .argument(2000u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x13))
+        .argument(bidder)
+        .argument(2000u128)
+        .argument(0u128)
+        .done();
+    assert_eq!(refund_events, vec![expected_event.build()]);
+    assert_eq!(
+        *refund_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 0,
+        }
+    );
+    // the owner still claims back the tokens for sale
+    assert_eq!(
+        *refund_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 100_000,
+        }
+    );
+}
+
+#[test]
+pub fn test_refund_all_in_batches() {
+    let (mut init_state, _) = initialize_contract();
+    init_state.status = AuctionStatus::Ended {};
+    for i in 0..60u8 {
+        init_state.add_to_claim_map(
+            Address {
+                address_type: AddressType::Account,
+                identifier: [i, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9],
+            },
+            TokenClaim {
+                tokens_for_bidding: 10,
+                tokens_for_sale: 0,
+            },
+        );
+    }
+    let owner = get_owner_address();
+    let (first_state, first_events) = refund_all(create_ctx(owner, 7), init_state);
+    assert_eq!(first_events.len(), 50);
+    let (second_state, second_events) = refund_all(create_ctx(owner, 8), first_state);
+    assert_eq!(second_events.len(), 10);
+    let (_, third_events) = refund_all(create_ctx(owner, 9), second_state);
+    assert_eq!(third_events.len(), 0);
+}
+
+#[test]
+#[should_panic(
+    expected = "[30] The action is only allowed after the auction has ended, failed or been cancelled"
+)]
+pub fn test_refund_all_while_bidding() {
+    let state = started_contract_with_bid(2000);
+    refund_all(create_ctx(get_owner_address(), 6), state);
+}
+
+#[test]
+#[should_panic(expected = "[1] Only the contract owner can perform this action")]
+pub fn test_refund_all_not_owner() {
+    let state = started_contract_with_bid(2000);
+    let (cancel_state, _) = cancel(create_ctx(get_owner_address(), 6), state);
+    refund_all(create_ctx(get_third_party_address(), 7), cancel_state);
+}
+
+#[test]
+#[should_panic(expected = "[31] Tried to create a claim deadline before the auction end time")]
+pub fn test_initialize_claim_deadline_before_end() {
+    let ctx = create_ctx(get_owner_address(), 2);
+    initialize(
+        ctx,
+        AuctionConfig {
+            token_amount_for_sale: 100_000,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            claim_deadline_millis: Some(50 * 3_600_000),
+            ..english_auction_config()
+        },
+    );
+}
+
+fn ended_contract_with_claim_deadline() -> AuctionContractState {
+    let mut state = started_contract_with_bid(2000);
+    state.claim_deadline_millis = Some(200 * 3_600_000);
+    state.sweep_sink = Some(get_third_party_address());
+    let (execute_state, _) = execute(create_ctx(get_owner_address(), 102), state);
+    execute_state
+}
+
+#[test]
+pub fn test_sweep_unclaimed() {
+    let state = ended_contract_with_claim_deadline();
+    let sink = get_third_party_address();
+    let (sweep_state, sweep_events) = sweep_unclaimed(create_ctx(sink, 200), state);
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_currency_token_address(), Shortname::from_u32(1))
+        .argument(sink)
+        .argument(2000u128)
+        .done();
+    expected_event
+        .call(get_commodity_token_address(), Shortname::from_u32(1))
+        .argument(sink)
+        .argument(100_000u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x13))
+        .argument(sink)
+        .argument(2000u128)
+        .argument(100_000u128)
+        .done();
+    assert_eq!(sweep_events, vec![expected_event.build()]);
+    assert!(sweep_state.claim_map.is_empty());
+}
+
+#[test]
+pub fn test_sweep_unclaimed_clears_failed_payouts() {
+    let state = ended_contract_with_claim_deadline();
+    let owner = get_owner_address();
+    let claimed = state.claim_map.get(&owner).unwrap().clone();
+    let (claim_state, _) = claim(create_ctx(owner, 103), state);
+    let (failed_state, _) = claim_callback(
+        create_ctx(owner, 104),
+        create_callback_ctx(false),
+        claim_state,
+        owner,
+        claimed,
+    );
+    assert!(failed_state.failed_payouts.contains_key(&owner));
+    let (sweep_state, _) = sweep_unclaimed(create_ctx(owner, 200), failed_state);
+    assert!(sweep_state.failed_payouts.is_empty());
+    let (_, events) = retry_failed_payouts(create_ctx(get_bidder_address(), 201), sweep_state);
+    assert!(events.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "[32] Tried to sweep unclaimed tokens before the claim deadline")]
+pub fn test_sweep_unclaimed_before_deadline() {
+    let state = ended_contract_with_claim_deadline();
+    sweep_unclaimed(create_ctx(get_owner_address(), 199), state);
+}
+
+#[test]
+#[should_panic(expected = "[32] Tried to sweep unclaimed tokens before the claim deadline")]
+pub fn test_sweep_unclaimed_without_deadline() {
+    let state = started_contract_with_bid(2000);
+    let owner = get_owner_address();
+    let (execute_state, _) = execute(create_ctx(owner, 102), state);
+    sweep_unclaimed(create_ctx(owner, 1_000), execute_state);
+}
+
+#[test]
+#[should_panic(expected = "[1] Only the contract owner can perform this action")]
+pub fn test_sweep_unclaimed_not_owner() {
+    let state = ended_contract_with_claim_deadline();
+    sweep_unclaimed(create_ctx(get_bidder_address(), 200), state);
+}
+
+#[test]
+pub fn test_summary() {
+    let state = started_contract_with_bid(2000);
+    let (summary_state, summary_events) =
+        summary(create_ctx(get_third_party_address(), 52), state.clone());
+    assert_eq!(summary_state, state);
+    let mut expected_event = EventGroup::builder();
+    expected_event.return_data(AuctionSummary {
+        highest_bid: 2000,
+        reserve_price: 1_000,
+        min_increment: 100,
+        time_remaining_millis: 50 * 3_600_000,
+        status: AuctionStatus::Bidding {},
+        bidding_token_decimals: None,
+    });
+    assert_eq!(summary_events, vec![expected_event.build()]);
+}
+
+#[test]
+pub fn test_start_queries_decimals() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.decimals_shortname = Some(0x11);
+    let owner = get_owner_address();
+    let (configured_state, _) = configure(create_ctx(owner, 1), init_state, config);
+    let (start_state, events) = start(create_ctx(owner, 2), configured_state);
+    assert_eq!(events.len(), 2);
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_commodity_token_address(), Shortname::from_u32(0x11))
+        .done();
+    expected_event
+        .call(get_currency_token_address(), Shortname::from_u32(0x11))
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x55))
+        .done();
+    assert_eq!(events[1], expected_event.build());
+    let decimals_ctx = CallbackContext {
+        success: false,
+        results: vec![
+            ExecutionResult {
+                succeeded: false,
+                return_data: vec![],
+            },
+            ExecutionResult {
+                succeeded: true,
+                return_data: vec![6],
+            },
+        ],
+    };
+    let (decimals_state, _) = decimals_callback(create_ctx(owner, 2), decimals_ctx, start_state);
+    assert_eq!(decimals_state.sale_token_decimals, None);
+    assert_eq!(decimals_state.bidding_token_decimals, Some(6));
+}
+
+#[test]
+pub fn test_units() {
+    assert_eq!(to_base_units(15, 6), Some(15_000_000));
+    assert_eq!(to_base_units(u128::MAX, 1), None);
+    assert_eq!(
+        normalize_amount(1_500_000, 6, 18),
+        Some(1_500_000_000_000_000_000)
+    );
+    assert_eq!(normalize_amount(1_999, 3, 0), Some(1));
+    assert_eq!(compare_normalized(1_000, 3, 10, 1), Ordering::Equal);
+    assert_eq!(compare_normalized(u128::MAX, 0, 1, 18), Ordering::Greater);
+    assert_eq!(format_units(1_500, 3), "1.5");
+    assert_eq!(format_units(7, 3), "0.007");
+    assert_eq!(format_units(2_000, 3), "2");
+    assert_eq!(format_units(42, 0), "42");
+}
+
+#[test]
+pub fn test_summary_after_end() {
+    let state = started_contract_with_bid(2000);
+    let (_, summary_events) = summary(create_ctx(get_third_party_address(), 150), state);
+    let mut expected_event = EventGroup::builder();
+    expected_event.return_data(AuctionSummary {
+        highest_bid: 2000,
+        reserve_price: 1_000,
+        min_increment: 100,
+        time_remaining_millis: 0,
+        status: AuctionStatus::Bidding {},
+        bidding_token_decimals: None,
+    });
+    assert_eq!(summary_events, vec![expected_event.build()]);
+}
+
+#[test]
+pub fn test_snapshot() {
+    let state = started_contract_with_bid(2000);
+    let third_party = get_third_party_address();
+    let (state, _) = bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder: third_party,
+            amount: 3000,
+        },
+    );
+    let (snapshot_state, snapshot_events) =
+        snapshot(create_ctx(get_owner_address(), 7), state.clone());
+    assert_eq!(snapshot_state, state);
+    let mut expected_event = EventGroup::builder();
+    expected_event.return_data(AuctionSnapshot {
+        version: 1,
+        status: AuctionStatus::Bidding {},
+        highest_bidder: third_party,
+        highest_bid: 3000,
+        end_time_millis: 102 * 3_600_000,
+        bid_count: 2,
+        bidder_count: 2,
+        claimable_bidding_tokens: 2000,
+        claimable_sale_tokens: 0,
+    });
+    assert_eq!(snapshot_events, vec![expected_event.build()]);
+}
+
+#[test]
+pub fn test_bid_history() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (mut started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    started_state.max_bid_history = 2;
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let mut state = started_state;
+    for (i, (bidder, amount)) in [(bidder, 2000), (third_party, 2500), (bidder, 3000)]
+        .into_iter()
+        .enumerate()
+    {
+        let (bid_state, _) = bid_callback(
+            create_ctx(bidder, 5 + i as i64),
+            create_callback_ctx(true),
+            state,
+            Bid { bidder, amount },
+        );
+        state = bid_state;
+    }
+    assert_eq!(
+        state.bid_history,
+        vec![
+            BidRecord {
+                sequence: 2,
+                bidder: third_party,
+                amount: 2500,
+                timestamp_millis: 6 * 3_600_000,
+                lot_id: None,
+            },
+            BidRecord {
+                sequence: 3,
+                bidder,
+                amount: 3000,
+                timestamp_millis: 7 * 3_600_000,
+                lot_id: None,
+            },
+        ]
+    );
+}
+
+#[test]
+pub fn test_leaderboard() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (mut state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    for (i, (bidder, amount)) in [(bidder, 2000), (third_party, 2500), (bidder, 3000)]
+        .into_iter()
+        .enumerate()
+    {
+        let (bid_state, _) = bid_callback(
+            create_ctx(bidder, 5 + i as i64),
+            create_callback_ctx(true),
+            state,
+            Bid { bidder, amount },
+        );
+        state = bid_state;
+    }
+    assert_eq!(
+        state.leaderboard,
+        vec![
+            LeaderboardEntry {
+                bidder,
+                amount: 3000,
+                timestamp_millis: 7 * 3_600_000,
+            },
+            LeaderboardEntry {
+                bidder: third_party,
+                amount: 2500,
+                timestamp_millis: 6 * 3_600_000,
+            },
+        ]
+    );
+}
+
+#[test]
+pub fn test_leaderboard_keeps_top_bidders() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (mut state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    for i in 0..12u8 {
+        let bidder = Address {
+            address_type: AddressType::Account,
+            identifier: [i + 0x10; 20],
+        };
+        let (bid_state, _) = bid_callback(
+            create_ctx(bidder, 5),
+            create_callback_ctx(true),
+            state,
+            Bid {
+                bidder,
+                amount: 2000 + 200 * u128::from(i),
+            },
+        );
+        state = bid_state;
+    }
+    assert_eq!(state.leaderboard.len(), 10);
+    assert_eq!(state.leaderboard[0].amount, 4200);
+    assert_eq!(state.leaderboard[9].amount, 2400);
+}
+
+#[test]
+pub fn test_bidder_stats() {
+    let state = started_contract_with_bid(2000);
+    let bidder = get_bidder_address();
+    // a refunded bid still counts in the statistics of the bidder
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 6),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder,
+            amount: 100,
+        },
+    );
+    assert_eq!(
+        *bid_state.bidder_stats.get(&bidder).unwrap(),
+        BidderStats {
+            bid_count: 2,
+            total_escrowed: 2100,
+            highest_bid: 2000,
+            last_bid_millis: 6 * 3_600_000,
+        }
+    );
+    assert!(!bid_state
+        .bidder_stats
+        .contains_key(&get_third_party_address()));
+}
+
+#[test]
+#[should_panic(expected = "[104] The bidder has to wait for the bid cooldown to bid again")]
+pub fn test_bid_in_cooldown() {
+    let mut state = started_contract_with_bid(2000);
+    state.bid_cooldown_millis = Some(2 * 3_600_000);
+    bid(create_ctx(get_bidder_address(), 6), state, 2500);
+}
+
+#[test]
+pub fn test_bid_after_cooldown() {
+    let mut state = started_contract_with_bid(2000);
+    state.bid_cooldown_millis = Some(2 * 3_600_000);
+    let (_, events) = bid(create_ctx(get_bidder_address(), 7), state, 2500);
+    assert_eq!(events.len(), 1);
+}
+
+#[test]
+pub fn test_bid_callback_refunds_bid_in_cooldown() {
+    let mut state = started_contract_with_bid(2000);
+    state.bid_cooldown_millis = Some(2 * 3_600_000);
+    let bidder = get_bidder_address();
+    let (bid_state, events) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder,
+            amount: 3000,
+        },
+    );
+    assert_eq!(
+        events,
+        vec![bid_outcome_event(BidOutcome::Refunded { amount: 3000 })]
+    );
+    assert_eq!(bid_state.highest_bidder.amount, 2000);
+    assert_eq!(
+        bid_state.claim_map.get(&bidder).unwrap().tokens_for_bidding,
+        3000
+    );
+}
+
+#[test]
+#[should_panic(expected = "[103] Tried to configure a non-positive bid cooldown")]
+pub fn test_initialize_invalid_bid_cooldown() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.bid_cooldown_millis = Some(0);
+    configure(create_ctx(get_owner_address(), 2), init_state, config);
+}
+
+#[test]
+pub fn test_bid_history_rejected_bid_not_recorded() {
+    let mut state = started_contract_with_bid(2000);
+    state.max_bid_history = 10;
+    let third_party = get_third_party_address();
+    let (bid_state, _) = bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder: third_party,
+            amount: 2050,
+        },
+    );
+    assert!(bid_state.bid_history.is_empty());
+}
+
+#[test]
+pub fn test_bid_history_lot_bid() {
+    let mut state = started_contract_with_lot();
+    state.max_bid_history = 10;
+    let bidder = get_bidder_address();
+    let (bid_state, _) = bid_lot_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        state,
+        7,
+        Bid {
+            bidder,
+            amount: 300,
+        },
+    );
+    assert_eq!(
+        bid_state.bid_history,
+        vec![BidRecord {
+            sequence: 1,
+            bidder,
+            amount: 300,
+            timestamp_millis: 5 * 3_600_000,
+            lot_id: Some(7),
+        }]
+    );
+}
+
+fn get_observer_address() -> Address {
+    Address {
+        address_type: AddressType::PublicContract,
+        identifier: [
+            0u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 10,
+        ],
+    }
+}
+
+fn observer_event(notifications: Vec<AuctionNotification>) -> EventGroup {
+    let mut expected_event = EventGroup::builder();
+    for notification in notifications {
+        expected_event
+            .call(get_observer_address(), Shortname::from_u32(1))
+            .argument(notification)
+            .done();
+    }
+    expected_event.build()
+}
+
+#[test]
+pub fn test_observer_notified_on_start() {
+    let (mut init_state, _) = initialize_contract();
+    init_state.observer = Some(get_observer_address());
+    let (_, events) = start_with_escrow(
+        create_ctx(get_owner_address(), 3),
+        create_callback_ctx(true),
+        init_state,
+    );
+    assert_eq!(
+        events,
+        vec![observer_event(vec![AuctionNotification::Started {}])]
+    );
+}
+
+#[test]
+pub fn test_observer_notified_on_new_highest_bid() {
+    let mut state = started_contract_with_bid(2000);
+    state.observer = Some(get_observer_address());
+    let third_party = get_third_party_address();
+    let (_, events) = bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder: third_party,
+            amount: 3000,
+        },
+    );
+    assert_eq!(
+        events,
+        vec![
+            observer_event(vec![AuctionNotification::NewHighestBid {
+                bidder: third_party,
+                amount: 3000,
+            }]),
+            bid_outcome_event(BidOutcome::HighestBid { amount: 3000 }),
+        ]
+    );
+}
+
+#[test]
+pub fn test_observer_not_notified_on_rejected_bid() {
+    let mut state = started_contract_with_bid(2000);
+    state.observer = Some(get_observer_address());
+    let third_party = get_third_party_address();
+    let (_, events) = bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder: third_party,
+            amount: 2050,
+        },
+    );
+    assert_eq!(
+        events,
+        vec![bid_outcome_event(BidOutcome::Refunded { amount: 2050 })]
+    );
+}
+
+#[test]
+pub fn test_observer_notified_on_buyout() {
+    let mut state = started_contract_with_bid(2000);
+    state.observer = Some(get_observer_address());
+    state.buyout_price = Some(5000);
+    let third_party = get_third_party_address();
+    let (_, events) = bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder: third_party,
+            amount: 5000,
+        },
+    );
+    assert_eq!(
+        events,
+        vec![
+            observer_event(vec![
+                AuctionNotification::NewHighestBid {
+                    bidder: third_party,
+                    amount: 5000,
+                },
+                AuctionNotification::Executed {
+                    winner: third_party,
+                    price: 5000,
+                },
+            ]),
+            bid_outcome_event(BidOutcome::HighestBid { amount: 5000 }),
+        ]
+    );
+}
+
+#[test]
+pub fn test_observer_notified_on_execute() {
+    let mut state = started_contract_with_bid(2000);
+    state.observer = Some(get_observer_address());
+    let (_, events) = execute(create_ctx(get_owner_address(), 102), state);
+    assert_eq!(
+        events,
+        vec![observer_event(vec![AuctionNotification::Executed {
+            winner: get_bidder_address(),
+            price: 2000,
+        }])]
+    );
+}
+
+#[test]
+pub fn test_observer_notified_on_cancel() {
+    let mut state = started_contract_with_bid(2000);
+    state.observer = Some(get_observer_address());
+    let (_, events) = cancel(create_ctx(get_owner_address(), 6), state);
+    assert_eq!(
+        events,
+        vec![observer_event(vec![AuctionNotification::Cancelled {}])]
+    );
+}
+
+#[test]
+pub fn test_execute_too_few_bidders() {
+    let mut state = started_contract_with_bid(2000);
+    state.min_unique_bidders = 2;
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let (execute_state, _) = execute(create_ctx(owner, 102), state);
+    assert_eq!(execute_state.status, AuctionStatus::Failed {});
+    assert_eq!(
+        *execute_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 2000,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        *execute_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 100_000,
+        }
+    );
+}
+
+#[test]
+pub fn test_execute_enough_bidders() {
+    let mut state = started_contract_with_bid(2000);
+    state.min_unique_bidders = 2;
+    let third_party = get_third_party_address();
+    let (bid_state, _) = bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder: third_party,
+            amount: 3000,
+        },
+    );
+    let owner = get_owner_address();
+    let (execute_state, _) = execute(create_ctx(owner, 102), bid_state);
+    assert_eq!(execute_state.status, AuctionStatus::Ended {});
+    assert_eq!(
+        *execute_state.claim_map.get(&third_party).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 100_000,
+        }
+    );
+}
+
+#[test]
+pub fn test_repeated_bids_count_as_one_bidder() {
+    let mut state = started_contract_with_bid(2000);
+    state.min_unique_bidders = 2;
+    let bidder = get_bidder_address();
+    let (top_up_state, _) = top_up_callback(
+        create_ctx(bidder, 6),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder,
+            amount: 2500,
+        },
+        500,
+    );
+    let (execute_state, _) = execute(create_ctx(get_owner_address(), 102), top_up_state);
+    assert_eq!(execute_state.status, AuctionStatus::Failed {});
+}
+
+fn get_reserve_salt() -> Hash {
+    [7u8; 32]
+}
+
+fn started_contract_with_hidden_reserve(reserve_price: u128) -> AuctionContractState {
+    let mut state = started_contract_with_bid(2000);
+    state.reserve_commitment = Some(reserve_commitment(reserve_price, get_reserve_salt()));
+    state
+}
+
+#[test]
+#[should_panic(expected = "[33] Tried to create a hidden reserve price for a non English auction")]
+pub fn test_initialize_dutch_hidden_reserve() {
+    let ctx = create_ctx(get_owner_address(), 2);
+    initialize(
+        ctx,
+        AuctionConfig {
+            token_amount_for_sale: 100_000,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            auction_format: DUTCH,
+            price_schedule: Some(PriceSchedule {
+                start_price: 5_000,
+                decrement_per_hour: 100,
+                curve: DecayCurve::Linear {},
+            }),
+            reserve_commitment: Some(reserve_commitment(1_500, get_reserve_salt())),
+            ..english_auction_config()
+        },
+    );
+}
+
+#[test]
+pub fn test_reveal_reserve_met() {
+    let state = started_contract_with_hidden_reserve(1_500);
+    let owner = get_owner_address();
+    let (reveal_state, _) =
+        reveal_reserve(create_ctx(owner, 102), state, 1_500, get_reserve_salt());
+    assert_eq!(reveal_state.status, AuctionStatus::Ended {});
+    assert_eq!(reveal_state.reserve_price, 1_500);
+    assert_eq!(reveal_state.reserve_commitment, None);
+    assert_eq!(
+        *reveal_state.claim_map.get(&get_bidder_address()).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 100_000,
+        }
+    );
+}
+
+#[test]
+pub fn test_reveal_reserve_not_met() {
+    let state = started_contract_with_hidden_reserve(2_500);
+    let owner = get_owner_address();
+    let (reveal_state, _) =
+        reveal_reserve(create_ctx(owner, 102), state, 2_500, get_reserve_salt());
+    assert_eq!(reveal_state.status, AuctionStatus::Failed {});
+    assert_eq!(
+        *reveal_state.claim_map.get(&get_bidder_address()).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 2000,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "[35] The revealed reserve price does not match the commitment")]
+pub fn test_reveal_reserve_wrong_price() {
+    let state = started_contract_with_hidden_reserve(2_500);
+    reveal_reserve(
+        create_ctx(get_owner_address(), 102),
+        state,
+        1_500,
+        get_reserve_salt(),
+    );
+}
+
+#[test]
+#[should_panic(expected = "[34] The auction has no hidden reserve price to reveal")]
+pub fn test_reveal_reserve_without_commitment() {
+    let state = started_contract_with_bid(2000);
+    reveal_reserve(
+        create_ctx(get_owner_address(), 102),
+        state,
+        1_500,
+        get_reserve_salt(),
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "[36] Tried to execute the auction before the hidden reserve price was revealed"
+)]
+pub fn test_execute_hidden_reserve_not_revealed() {
+    let state = started_contract_with_hidden_reserve(1_500);
+    execute(create_ctx(get_bidder_address(), 102), state);
+}
+
+#[test]
+pub fn test_execute_hidden_reserve_after_reveal_window() {
+    let state = started_contract_with_hidden_reserve(1_500);
+    let (execute_state, _) = execute(create_ctx(get_bidder_address(), 126), state);
+    assert_eq!(execute_state.status, AuctionStatus::Failed {});
+}
+
+fn get_candle_seed() -> Hash {
+    // an offset of three hours into the closing window
+    let mut seed = [1u8; 32];
+    seed[..8].copy_from_slice(&(3 * 3_600_000u64).to_be_bytes());
+    seed
+}
+
+fn candle_contract_with_window_bids() -> AuctionContractState {
+    let mut state = started_contract_with_bid(2000);
+    state.candle = Some(CandleConfig {
+        window_millis: 10 * 3_600_000,
+        seed_commitment: sha256(&get_candle_seed()),
+    });
+    let third_party = get_third_party_address();
+    let bidder = get_bidder_address();
+    let (first_state, _) = bid_callback(
+        create_ctx(third_party, 94),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder: third_party,
+            amount: 3000,
+        },
+    );
+    let (second_state, _) = bid_callback(
+        create_ctx(bidder, 97),
+        create_callback_ctx(true),
+        first_state,
+        Bid {
+            bidder,
+            amount: 4000,
+        },
+    );
+    second_state
+}
+
+#[test]
+pub fn test_candle_bids_stay_escrowed() {
+    let state = candle_contract_with_window_bids();
+    let bidder = get_bidder_address();
+    assert_eq!(
+        state.highest_bidder,
+        Bid {
+            bidder,
+            amount: 4000,
+        }
+    );
+    assert!(!state.claim_map.contains_key(&bidder));
+    assert!(!state.claim_map.contains_key(&get_third_party_address()));
+    assert_eq!(
+        state.candle_bids,
+        vec![
+            CandleBid {
+                bidder,
+                amount: 2000,
+                timestamp_millis: 92 * 3_600_000,
+            },
+            CandleBid {
+                bidder: get_third_party_address(),
+                amount: 3000,
+                timestamp_millis: 94 * 3_600_000,
+            },
+            CandleBid {
+                bidder,
+                amount: 4000,
+                timestamp_millis: 97 * 3_600_000,
+            },
+        ]
+    );
+}
+
+#[test]
+pub fn test_reveal_candle() {
+    let state = candle_contract_with_window_bids();
+    let owner = get_owner_address();
+    let third_party = get_third_party_address();
+    let (reveal_state, _) = reveal_candle(create_ctx(owner, 102), state, get_candle_seed());
+    assert_eq!(reveal_state.candle_cutoff_millis, Some(95 * 3_600_000));
+    assert_eq!(reveal_state.status, AuctionStatus::Ended {});
+    assert!(reveal_state.candle_bids.is_empty());
+    assert_eq!(
+        *reveal_state.claim_map.get(&third_party).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 100_000,
+        }
+    );
+    assert_eq!(
+        *reveal_state.claim_map.get(&get_bidder_address()).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 6000,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        *reveal_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 3000,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "[40] The revealed candle seed does not match the commitment")]
+pub fn test_reveal_candle_wrong_seed() {
+    let state = candle_contract_with_window_bids();
+    reveal_candle(create_ctx(get_owner_address(), 102), state, [2u8; 32]);
+}
+
+#[test]
+#[should_panic(expected = "[41] Tried to execute the auction before the candle seed was revealed")]
+pub fn test_execute_candle_not_revealed() {
+    let state = candle_contract_with_window_bids();
+    execute(create_ctx(get_bidder_address(), 102), state);
+}
+
+#[test]
+pub fn test_cancel_candle_refunds_window_bids() {
+    let state = candle_contract_with_window_bids();
+    let (cancel_state, _) = cancel(create_ctx(get_owner_address(), 98), state);
+    assert_eq!(
+        *cancel_state.claim_map.get(&get_bidder_address()).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 6000,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        *cancel_state
+            .claim_map
+            .get(&get_third_party_address())
+            .unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 3000,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+pub fn test_block_candle_bidder() {
+    let state = candle_contract_with_window_bids();
+    let bidder = get_bidder_address();
+    let (block_state, _) = block_bidder(create_ctx(get_owner_address(), 98), state, bidder);
+    assert_eq!(
+        block_state.highest_bidder,
+        Bid {
+            bidder: get_third_party_address(),
+            amount: 3000,
+        }
+    );
+    assert_eq!(block_state.candle_bids.len(), 1);
+    assert_eq!(
+        *block_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 6000,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "[38] Tried to create a candle window that is empty or longer than the auction"
+)]
+pub fn test_initialize_candle_window_too_long() {
+    let ctx = create_ctx(get_owner_address(), 2);
+    initialize(
+        ctx,
+        AuctionConfig {
+            token_amount_for_sale: 100_000,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            candle: Some(CandleConfig {
+                window_millis: 101 * 3_600_000,
+                seed_commitment: sha256(&get_candle_seed()),
+            }),
+            ..english_auction_config()
+        },
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "[37] Tried to create a candle auction that is not a plain first price English auction"
+)]
+pub fn test_initialize_candle_second_price() {
+    let ctx = create_ctx(get_owner_address(), 2);
+    initialize(
+        ctx,
+        AuctionConfig {
+            token_amount_for_sale: 100_000,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            settlement_mode: SECOND_PRICE,
+            candle: Some(CandleConfig {
+                window_millis: 10 * 3_600_000,
+                seed_commitment: sha256(&get_candle_seed()),
+            }),
+            ..english_auction_config()
+        },
+    );
+}
+
+fn started_multi_unit_contract(auction_format: u8) -> AuctionContractState {
+    let owner = get_owner_address();
+    let (init_state, _) = initialize(
+        create_ctx(owner, 2),
+        AuctionConfig {
+            token_amount_for_sale: 10,
+            reserve_price: 10,
+            min_increment: 0,
+            auction_duration_millis: 100 * 3_600_000,
+            auction_format,
+            ..english_auction_config()
+        },
+    );
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    started_state
+}
+
+fn place_orders(
+    state: AuctionContractState,
+    orders: Vec<(Address, u128, u128)>,
+) -> AuctionContractState {
+    let mut state = state;
+    for (bidder, quantity, unit_price) in orders {
+        let (order_state, _) = place_order_callback(
+            create_ctx(bidder, 5),
+            create_callback_ctx(true),
+            state,
+            Order {
+                bidder,
+                quantity,
+                unit_price,
+            },
+        );
+        state = order_state;
+    }
+    state
+}
+
+#[test]
+pub fn test_place_order_callback_without_units() {
+    let bidder = get_bidder_address();
+    let state = place_orders(
+        started_multi_unit_contract(UNIFORM_PRICE),
+        vec![(bidder, 0, 50)],
+    );
+    assert!(state.orders.is_empty());
+    assert_eq!(
+        state.rejected_bids.get(&bidder).unwrap().reason,
+        RejectionReason::InvalidOrder {}
+    );
+}
+
+#[test]
+pub fn test_place_order() {
+    let state = started_multi_unit_contract(UNIFORM_PRICE);
+    let bidder = get_bidder_address();
+    let (order_state, events) = place_order(create_ctx(bidder, 5), state.clone(), 6, 50);
+    assert_eq!(order_state, state);
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_currency_token_address(), Shortname::from_u32(3))
+        .argument(bidder)
+        .argument(get_contract_address())
+        .argument(300u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x1a))
+        .argument(bidder)
+        .argument(6u128)
+        .argument(50u128)
+        .done();
+    assert_eq!(events, vec![expected_event.build()]);
+}
+
+#[test]
+#[should_panic(expected = "[42] Tried to place an order outside a multi-unit auction")]
+pub fn test_place_order_english() {
+    let state = started_contract_with_bid(2000);
+    place_order(create_ctx(get_third_party_address(), 6), state, 6, 50);
+}
+
+#[test]
+pub fn test_place_order_above_supply_refunded() {
+    let state = started_multi_unit_contract(UNIFORM_PRICE);
+    let bidder = get_bidder_address();
+    let order_state = place_orders(state, vec![(bidder, 11, 50)]);
+    assert!(order_state.orders.is_empty());
+    assert_eq!(
+        *order_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 550,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+pub fn test_execute_uniform_price() {
+    let state = started_multi_unit_contract(UNIFORM_PRICE);
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let second_bidder = get_royalty_receiver_address();
+    let third_bidder = get_fee_collector_address();
+    let order_state = place_orders(
+        state,
+        vec![
+            (bidder, 6, 50),
+            (third_bidder, 2, 20),
+            (third_party, 3, 40),
+            (second_bidder, 4, 30),
+        ],
+    );
+    let owner = get_owner_address();
+    let (execute_state, _) = execute(create_ctx(owner, 102), order_state);
+    assert_eq!(execute_state.status, AuctionStatus::Ended {});
+    let expected_claims = vec![
+        (bidder, 300 - 180, 6),
+        (third_party, 120 - 90, 3),
+        (second_bidder, 120 - 30, 1),
+        (third_bidder, 40, 0),
+        (owner, 300, 0),
+    ];
+    for (address, tokens_for_bidding, tokens_for_sale) in expected_claims {
+        assert_eq!(
+            *execute_state.claim_map.get(&address).unwrap(),
+            TokenClaim {
+                tokens_for_bidding,
+                tokens_for_sale,
+            }
+        );
+    }
+}
+
+#[test]
+pub fn test_execute_uniform_price_undersubscribed() {
+    let state = started_multi_unit_contract(UNIFORM_PRICE);
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let order_state = place_orders(state, vec![(bidder, 3, 50), (third_party, 2, 40)]);
+    let owner = get_owner_address();
+    let (execute_state, _) = execute(create_ctx(owner, 102), order_state);
+    assert_eq!(
+        *execute_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 30,
+            tokens_for_sale: 3,
+        }
+    );
+    assert_eq!(
+        *execute_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 200,
+            tokens_for_sale: 5,
+        }
+    );
+}
+
+#[test]
+pub fn test_cancel_uniform_price_refunds_orders() {
+    let state = started_multi_unit_contract(UNIFORM_PRICE);
+    let bidder = get_bidder_address();
+    let order_state = place_orders(state, vec![(bidder, 3, 50), (bidder, 2, 40)]);
+    let (cancel_state, _) = cancel(create_ctx(get_owner_address(), 6), order_state);
+    assert_eq!(
+        *cancel_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 230,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+pub fn test_execute_pay_as_bid() {
+    let state = started_multi_unit_contract(PAY_AS_BID);
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let second_bidder = get_royalty_receiver_address();
+    let third_bidder = get_fee_collector_address();
+    let order_state = place_orders(
+        state,
+        vec![
+            (bidder, 6, 50),
+            (third_bidder, 2, 20),
+            (third_party, 3, 40),
+            (second_bidder, 4, 30),
+        ],
+    );
+    let owner = get_owner_address();
+    let (execute_state, _) = execute(create_ctx(owner, 102), order_state);
+    assert_eq!(execute_state.status, AuctionStatus::Ended {});
+    let expected_claims = vec![
+        (bidder, 0, 6),
+        (third_party, 0, 3),
+        (second_bidder, 120 - 30, 1),
+        (third_bidder, 40, 0),
+        (owner, 300 + 120 + 30, 0),
+    ];
+    for (address, tokens_for_bidding, tokens_for_sale) in expected_claims {
+        assert_eq!(
+            *execute_state.claim_map.get(&address).unwrap(),
+            TokenClaim {
+                tokens_for_bidding,
+                tokens_for_sale,
+            }
+        );
+    }
+}
+
+#[test]
+pub fn test_transfer_ownership() {
+    let state = started_contract_with_bid(2000);
+    let owner = get_owner_address();
+    let new_owner = get_third_party_address();
+    let (proposed_state, events) = propose_new_owner(create_ctx(owner, 6), state, new_owner);
+    assert_eq!(events.len(), 0);
+    assert_eq!(proposed_state.pending_owner, Some(new_owner));
+    assert_eq!(proposed_state.contract_owner, owner);
+    let (accepted_state, events) = accept_ownership(create_ctx(new_owner, 7), proposed_state);
+    assert_eq!(events.len(), 0);
+    assert_eq!(accepted_state.contract_owner, new_owner);
+    assert_eq!(accepted_state.pending_owner, None);
+    let (cancelled_state, _) = cancel(create_ctx(new_owner, 8), accepted_state);
+    assert_eq!(cancelled_state.status, AuctionStatus::Cancelled {});
+}
+
+#[test]
+#[should_panic(expected = "[1] Only the contract owner can perform this action")]
+pub fn test_propose_new_owner_not_owner() {
+    let state = started_contract_with_bid(2000);
+    let third_party = get_third_party_address();
+    propose_new_owner(create_ctx(third_party, 6), state, third_party);
+}
+
+#[test]
+#[should_panic(expected = "[43] Tried to accept ownership without being the proposed owner")]
+pub fn test_accept_ownership_not_pending_owner() {
+    let state = started_contract_with_bid(2000);
+    let (proposed_state, _) = propose_new_owner(
+        create_ctx(get_owner_address(), 6),
+        state,
+        get_third_party_address(),
+    );
+    accept_ownership(create_ctx(get_bidder_address(), 7), proposed_state);
+}
+
+#[test]
+#[should_panic(expected = "[1] Only the contract owner can perform this action")]
+pub fn test_previous_owner_loses_control() {
+    let state = started_contract_with_bid(2000);
+    let owner = get_owner_address();
+    let new_owner = get_third_party_address();
+    let (proposed_state, _) = propose_new_owner(create_ctx(owner, 6), state, new_owner);
+    let (accepted_state, _) = accept_ownership(create_ctx(new_owner, 7), proposed_state);
+    cancel(create_ctx(owner, 8), accepted_state);
+}
+
+#[test]
+pub fn test_pause_refunds_bids() {
+    let state = started_contract_with_bid(2000);
+    let owner = get_owner_address();
+    let third_party = get_third_party_address();
+    let (paused_state, events) = pause(create_ctx(owner, 6), state);
+    assert_eq!(events.len(), 0);
+    assert_eq!(paused_state.status, AuctionStatus::Paused {});
+    assert_eq!(paused_state.paused_at_millis, Some(6 * 3_600_000));
+    let (bid_state, _) = bid_callback(
+        create_ctx(third_party, 7),
+        create_callback_ctx(true),
+        paused_state,
+        Bid {
+            bidder: third_party,
+            amount: 3000,
+        },
+    );
+    assert_eq!(bid_state.highest_bidder.bidder, get_bidder_address());
+    assert_eq!(
+        *bid_state.claim_map.get(&third_party).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 3000,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+pub fn test_resume_extends_end_time() {
+    let state = started_contract_with_bid(2000);
+    let owner = get_owner_address();
+    let end_time_millis = state.end_time_millis;
+    let (paused_state, _) = pause(create_ctx(owner, 6), state);
+    let (resumed_state, events) = resume(create_ctx(owner, 16), paused_state, true);
+    assert_eq!(events.len(), 0);
+    assert_eq!(resumed_state.status, AuctionStatus::Bidding {});
+    assert_eq!(resumed_state.paused_at_millis, None);
+    assert_eq!(
+        resumed_state.end_time_millis,
+        end_time_millis + 10 * 3_600_000
+    );
+}
+
+#[test]
+pub fn test_resume_keeps_end_time() {
+    let state = started_contract_with_bid(2000);
+    let owner = get_owner_address();
+    let end_time_millis = state.end_time_millis;
+    let (paused_state, _) = pause(create_ctx(owner, 6), state);
+    let (resumed_state, _) = resume(create_ctx(owner, 16), paused_state, false);
+    assert_eq!(resumed_state.status, AuctionStatus::Bidding {});
+    assert_eq!(resumed_state.end_time_millis, end_time_millis);
+}
+
+#[test]
+pub fn test_cancel_paused() {
+    let state = started_contract_with_bid(2000);
+    let owner = get_owner_address();
+    let (paused_state, _) = pause(create_ctx(owner, 6), state);
+    let (cancelled_state, _) = cancel(create_ctx(owner, 7), paused_state);
+    assert_eq!(cancelled_state.status, AuctionStatus::Cancelled {});
+    assert_eq!(
+        *cancelled_state
+            .claim_map
+            .get(&get_bidder_address())
+            .unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 2000,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "[1] Only the contract owner can perform this action")]
+pub fn test_pause_not_owner() {
+    let state = started_contract_with_bid(2000);
+    pause(create_ctx(get_third_party_address(), 6), state);
+}
+
+#[test]
+#[should_panic(expected = "[44] Tried to resume an auction that is not paused")]
+pub fn test_resume_not_paused() {
+    let state = started_contract_with_bid(2000);
+    resume(create_ctx(get_owner_address(), 6), state, true);
+}
+
+#[test]
+pub fn test_pause_notifies_observer() {
+    let mut state = started_contract_with_bid(2000);
+    state.observer = Some(get_observer_address());
+    let owner = get_owner_address();
+    let (paused_state, events) = pause(create_ctx(owner, 6), state);
+    assert_eq!(
+        events,
+        vec![observer_event(vec![AuctionNotification::Paused {}])]
+    );
+    let end_time_millis = paused_state.end_time_millis;
+    let (_, events) = resume(create_ctx(owner, 10), paused_state, true);
+    assert_eq!(
+        events,
+        vec![observer_event(vec![AuctionNotification::Resumed {
+            end_time_millis: end_time_millis + 4 * 3_600_000,
+        }])]
+    );
+}
+
+fn started_contract_with_max_extension(max_extension_hours: u32) -> AuctionContractState {
+    let owner = get_owner_address();
+    let (init_state, _) = initialize(
+        create_ctx(owner, 2),
+        AuctionConfig {
+            token_amount_for_sale: 100_000,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            max_extension_hours,
+            ..english_auction_config()
+        },
+    );
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    started_state
+}
+
+#[test]
+pub fn test_extend_auction() {
+    let state = started_contract_with_max_extension(10);
+    let owner = get_owner_address();
+    let (extended_state, events) = extend_auction(create_ctx(owner, 5), state, 4);
+    assert_eq!(events.len(), 0);
+    assert_eq!(extended_state.end_time_millis, 106 * 3_600_000);
+    let (extended_state, _) = extend_auction(create_ctx(owner, 6), extended_state, 6);
+    assert_eq!(extended_state.end_time_millis, 112 * 3_600_000);
+    assert_eq!(extended_state.total_extension_millis, 10 * 3_600_000);
+}
+
+#[test]
+#[should_panic(expected = "[45] Tried to extend the auction beyond the maximum total extension")]
+pub fn test_extend_auction_beyond_maximum() {
+    let state = started_contract_with_max_extension(10);
+    let owner = get_owner_address();
+    let (extended_state, _) = extend_auction(create_ctx(owner, 5), state, 4);
+    extend_auction(create_ctx(owner, 6), extended_state, 7);
+}
+
+#[test]
+#[should_panic(expected = "[1] Only the contract owner can perform this action")]
+pub fn test_extend_auction_not_owner() {
+    let state = started_contract_with_max_extension(10);
+    extend_auction(create_ctx(get_third_party_address(), 5), state, 4);
+}
+
+#[test]
+#[should_panic]
+pub fn test_extend_auction_after_end_time() {
+    let state = started_contract_with_max_extension(10);
+    extend_auction(create_ctx(get_owner_address(), 102), state, 4);
+}
+
+#[test]
+#[should_panic(expected = "[46] Tried to extend a candle auction after its candle window opened")]
+pub fn test_extend_candle_auction_in_window() {
+    let mut state = candle_contract_with_window_bids();
+    state.max_extension_millis = 10 * 3_600_000;
+    extend_auction(create_ctx(get_owner_address(), 101), state, 4);
+}
+
+#[test]
+pub fn test_update_terms() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    let (updated_state, events) = update_terms(create_ctx(owner, 4), started_state, 500, 50);
+    assert_eq!(events.len(), 0);
+    assert_eq!(updated_state.reserve_price, 500);
+    assert_eq!(updated_state.min_increment, 50);
+}
+
+#[test]
+#[should_panic(expected = "[47] Tried to update the terms of the auction after a bid was placed")]
+pub fn test_update_terms_after_bid() {
+    let state = started_contract_with_bid(2000);
+    update_terms(create_ctx(get_owner_address(), 6), state, 500, 50);
+}
+
+#[test]
+#[should_panic(expected = "[1] Only the contract owner can perform this action")]
+pub fn test_update_terms_not_owner() {
+    let (init_state, _) = initialize_contract();
+    update_terms(
+        create_ctx(get_third_party_address(), 3),
+        init_state,
+        500,
+        50,
+    );
+}
+
+/// A started auction whose owner actions need the approvals of 2 of 3 signers.
+fn multisig_contract() -> AuctionContractState {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (signers_state, _) = configure_signers(
+        create_ctx(owner, 1),
+        init_state,
+        vec![
+            get_third_party_address(),
+            get_royalty_receiver_address(),
+            get_fee_collector_address(),
+        ],
+        2,
+    );
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        signers_state,
+    );
+    started_state
+}
+
+#[test]
+pub fn test_cancel_with_approvals() {
+    let (approved_state, _) = approve_action(
+        create_ctx(get_third_party_address(), 4),
+        multisig_contract(),
+        OwnerAction::Cancel {},
+    );
+    let (approved_state, _) = approve_action(
+        create_ctx(get_fee_collector_address(), 5),
+        approved_state,
+        OwnerAction::Cancel {},
+    );
+    let (cancel_state, _) = cancel(create_ctx(get_owner_address(), 6), approved_state);
+    assert_eq!(cancel_state.status, AuctionStatus::Cancelled {});
+    assert!(cancel_state.action_approvals.is_empty());
+}
+
+#[test]
+#[should_panic(
+    expected = "[111] Tried to perform an owner action without the approvals of enough signers"
+)]
+pub fn test_cancel_without_enough_approvals() {
+    let signer = get_third_party_address();
+    let (approved_state, _) = approve_action(
+        create_ctx(signer, 4),
+        multisig_contract(),
+        OwnerAction::Cancel {},
+    );
+    // A second approval of the same signer does not count
+    let (approved_state, _) = approve_action(
+        create_ctx(signer, 5),
+        approved_state,
+        OwnerAction::Cancel {},
+    );
+    cancel(create_ctx(get_owner_address(), 6), approved_state);
+}
+
+#[test]
+#[should_panic(
+    expected = "[111] Tried to perform an owner action without the approvals of enough signers"
+)]
+pub fn test_cancel_in_creation_without_approvals() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (signers_state, _) = configure_signers(
+        create_ctx(owner, 1),
+        init_state,
+        vec![get_third_party_address(), get_royalty_receiver_address()],
+        2,
+    );
+    cancel(create_ctx(owner, 2), signers_state);
+}
+
+#[test]
+#[should_panic(
+    expected = "[111] Tried to perform an owner action without the approvals of enough signers"
+)]
+pub fn test_update_terms_with_approvals_of_other_terms() {
+    let action = OwnerAction::UpdateTerms {
+        reserve_price: 500,
+        min_increment: 50,
+    };
+    let (approved_state, _) = approve_action(
+        create_ctx(get_third_party_address(), 4),
+        multisig_contract(),
+        action.clone(),
+    );
+    let (approved_state, _) = approve_action(
+        create_ctx(get_royalty_receiver_address(), 5),
+        approved_state,
+        action,
+    );
+    update_terms(create_ctx(get_owner_address(), 6), approved_state, 400, 50);
+}
+
+#[test]
+#[should_panic(expected = "[110] Only a signer of the owner can approve an owner action")]
+pub fn test_approve_action_not_signer() {
+    approve_action(
+        create_ctx(get_bidder_address(), 4),
+        multisig_contract(),
+        OwnerAction::Cancel {},
+    );
+}
+
+/// A started auction whose owner actions must be proposed 10 hours before they are performed.
+fn timelocked_contract() -> AuctionContractState {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (timelock_state, _) =
+        configure_timelock(create_ctx(owner, 1), init_state, 10 * 60 * 60 * 1000);
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        timelock_state,
+    );
+    started_state
+}
+
+#[test]
+pub fn test_cancel_after_timelock() {
+    let owner = get_owner_address();
+    let (proposed_state, _) = propose_action(
+        create_ctx(owner, 4),
+        timelocked_contract(),
+        OwnerAction::Cancel {},
+    );
+    assert_eq!(
+        proposed_state.pending_actions,
+        vec![PendingAction {
+            action: OwnerAction::Cancel {},
+            executable_at_millis: 14 * 60 * 60 * 1000,
+        }]
+    );
+    let (cancel_state, _) = cancel(create_ctx(owner, 14), proposed_state);
+    assert_eq!(cancel_state.status, AuctionStatus::Cancelled {});
+    assert!(cancel_state.pending_actions.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "[114] Tried to perform an owner action before its timelock elapsed")]
+pub fn test_cancel_before_timelock() {
+    let owner = get_owner_address();
+    let (proposed_state, _) = propose_action(
+        create_ctx(owner, 4),
+        timelocked_contract(),
+        OwnerAction::Cancel {},
+    );
+    cancel(create_ctx(owner, 13), proposed_state);
+}
+
+#[test]
+#[should_panic(
+    expected = "[113] Tried to perform an owner action under a timelock without proposing it"
+)]
+pub fn test_update_terms_not_proposed() {
+    update_terms(
+        create_ctx(get_owner_address(), 20),
+        timelocked_contract(),
+        500,
+        50,
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "[109] Tried to configure duplicate signers or a threshold the signers cannot reach"
+)]
+pub fn test_configure_signers_unreachable_threshold() {
+    let (init_state, _) = initialize_contract();
+    configure_signers(
+        create_ctx(get_owner_address(), 1),
+        init_state,
+        vec![get_third_party_address()],
+        2,
+    );
+}
+
+#[test]
+#[should_panic(expected = "[124] Tried to create an English auction without a minimum increment")]
+pub fn test_update_terms_zero_increment() {
+    let owner = get_owner_address();
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.tie_policy = TiePolicy::FirstReceived {};
+    let (configured_state, _) = configure(create_ctx(owner, 2), init_state, config);
+    update_terms(create_ctx(owner, 3), configured_state, 1_000, 0);
+}
+
+#[test]
+#[should_panic(expected = "[3] The action is only allowed while the auction is bidding")]
+pub fn test_update_terms_pending_start() {
+    let owner = get_owner_address();
+    let (init_state, _) = initialize_contract();
+    let (pending_state, _) = start(create_ctx(owner, 3), init_state);
+    update_terms(create_ctx(owner, 3), pending_state, 500, 50);
+}
+
+#[test]
+#[should_panic(expected = "[11] Tried to create a Dutch auction starting below the reserve price")]
+pub fn test_update_terms_reserve_above_start_price() {
+    let (init_state, _) = initialize_dutch_contract();
+    let start_price = init_state.price_schedule.as_ref().unwrap().start_price;
+    update_terms(
+        create_ctx(get_owner_address(), 3),
+        init_state,
+        start_price + 1,
+        0,
+    );
+}
+
+fn started_contract_with_relist(auction_format: u8, relist: RelistConfig) -> AuctionContractState {
+    let owner = get_owner_address();
+    let (init_state, _) = initialize(
+        create_ctx(owner, 2),
+        AuctionConfig {
+            token_amount_for_sale: 100_000,
+            reserve_price: 1_000,
+            min_increment: 100,
+            auction_duration_millis: 100 * 3_600_000,
+            auction_format,
+            relist: Some(relist),
+            ..english_auction_config()
+        },
+    );
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    started_state
+}
+
+#[test]
+pub fn test_execute_relists_without_bids() {
+    let state = started_contract_with_relist(
+        ENGLISH,
+        RelistConfig {
+            max_relists: 2,
+            reserve_decrease_bps: 1_000,
+        },
+    );
+    let (relisted_state, events) = execute(create_ctx(get_owner_address(), 110), state);
+    assert_eq!(events.len(), 0);
+    assert_eq!(relisted_state.status, AuctionStatus::Bidding {});
+    assert_eq!(relisted_state.relist_count, 1);
+    assert_eq!(relisted_state.reserve_price, 900);
+    assert_eq!(relisted_state.start_time_millis, 110 * 3_600_000);
+    assert_eq!(relisted_state.end_time_millis, 210 * 3_600_000);
+    assert!(relisted_state.claim_map.is_empty());
+}
+
+#[test]
+pub fn test_relisted_auction_accepts_lowered_reserve() {
+    let state = started_contract_with_relist(
+        ENGLISH,
+        RelistConfig {
+            max_relists: 1,
+            reserve_decrease_bps: 1_000,
+        },
+    );
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let (relisted_state, _) = execute(create_ctx(owner, 110), state);
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 120),
+        create_callback_ctx(true),
+        relisted_state,
+        Bid {
+            bidder,
+            amount: 950,
+        },
+    );
+    let (execute_state, _) = execute(create_ctx(owner, 210), bid_state);
+    assert_eq!(execute_state.status, AuctionStatus::Ended {});
+    assert_eq!(
+        *execute_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 100_000,
+        }
+    );
+}
+
+#[test]
+pub fn test_execute_after_last_relist() {
+    let state = started_contract_with_relist(
+        ENGLISH,
+        RelistConfig {
+            max_relists: 1,
+            reserve_decrease_bps: 1_000,
+        },
+    );
+    let owner = get_owner_address();
+    let (relisted_state, _) = execute(create_ctx(owner, 110), state);
+    let (execute_state, _) = execute(create_ctx(owner, 210), relisted_state);
+    assert_eq!(execute_state.status, AuctionStatus::ReserveNotMet {});
+    assert_eq!(execute_state.relist_count, 1);
+    assert_eq!(
+        *execute_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 100_000,
+        }
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "[49] Tried to decrease the reserve price by more than the whole reserve price"
+)]
+pub fn test_initialize_relist_invalid_reserve_decrease() {
+    started_contract_with_relist(
+        ENGLISH,
+        RelistConfig {
+            max_relists: 1,
+            reserve_decrease_bps: 10_001,
+        },
+    );
+}
+
+#[test]
+#[should_panic(expected = "[48] Tried to create a relisting candle or multi-unit auction")]
+pub fn test_initialize_relist_multi_unit() {
+    started_contract_with_relist(
+        UNIFORM_PRICE,
+        RelistConfig {
+            max_relists: 1,
+            reserve_decrease_bps: 1_000,
+        },
+    );
+}
+
+#[test]
+pub fn test_testutils_started_auction() {
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let state = AuctionBuilder::new(
+        owner,
+        get_commodity_token_address(),
+        get_currency_token_address(),
+        10_000,
+    )
+    .reserve_price(500)
+    .configure(|config| config.buyout_price = Some(5_000))
+    .build_started(get_contract_address(), 3_600_000);
+    assert_eq!(state.status, AuctionStatus::Bidding {});
+    assert_eq!(state.end_time_millis, 25 * 3_600_000);
+    let context = ContextBuilder::new(get_contract_address(), bidder).at_millis(3_600_000);
+    let (bid_state, events) = bid_callback(
+        context.advance_millis(3_600_000).build(),
+        callback_context(true, 1),
+        state,
+        Bid {
+            bidder,
+            amount: 5_000,
+        },
+    );
+    assert_event_groups(
+        &events,
+        vec![bid_outcome_event(BidOutcome::HighestBid { amount: 5_000 })],
+    );
+    assert_eq!(bid_state.status, AuctionStatus::Ended {});
+}
+
+#[test]
+pub fn test_testutils_callback_context_returning() {
+    let callback_ctx = callback_context_returning(400u128);
+    assert!(callback_ctx.success);
+    assert_eq!(callback_ctx.results[0].return_data, 400u128.to_be_bytes());
+}
+
+fn invariant_bidders() -> Vec<Address> {
+    vec![get_bidder_address(), get_third_party_address()]
+}
+
+/// Run a day long auction through `bids`, its execution and the claims of every participant,
+/// asserting the invariants after each step.
+fn assert_invariants_over_bids(bids: Vec<Bid>) {
+    let owner = get_owner_address();
+    let mut state = AuctionBuilder::new(
+        owner,
+        get_commodity_token_address(),
+        get_currency_token_address(),
+        10_000,
+    )
+    .reserve_price(100)
+    .min_increment(10)
+    .build_started(get_contract_address(), 0);
+    let mut history = vec![];
+    for (i, bid) in bids.into_iter().enumerate() {
+        history.push(Transfer::Escrowed {
+            from: bid.bidder,
+            amount: bid.amount,
+        });
+        let (bid_state, _) = bid_callback(
+            create_ctx(bid.bidder, 1 + i as i64),
+            create_callback_ctx(true),
+            state,
+            bid,
+        );
+        state = bid_state;
+        assert!(total_escrowed_consistent(&state));
+        assert!(claims_never_exceed_deposits(&state, &history));
+    }
+    let (executed_state, _) = execute(create_ctx(owner, 24), state);
+    state = executed_state;
+    for claimant in std::iter::once(owner).chain(invariant_bidders()) {
+        if let Some(claimable) = state.claim_map.get(&claimant) {
+            history.push(Transfer::PaidOut {
+                to: claimant,
+                amount: claimable.tokens_for_bidding,
+            });
+        }
+        let (claim_state, _) = claim(create_ctx(claimant, 25), state);
+        state = claim_state;
+        assert!(total_escrowed_consistent(&state));
+        assert!(claims_never_exceed_deposits(&state, &history));
+    }
+    assert_eq!(owed_bidding_tokens(&state), Some(0));
+}
+
+proptest! {
+    #[test]
+    fn test_invariants_hold_over_generated_bids(
+        bids in bid_sequences(invariant_bidders(), 20, 5_000)
+    ) {
+        assert_invariants_over_bids(bids);
+    }
+}
+
+#[test]
+pub fn test_claims_exceeding_deposits_detected() {
+    let state = started_contract_with_bid(2000);
+    let history = [Transfer::Escrowed {
+        from: get_bidder_address(),
+        amount: 1500,
+    }];
+    assert!(!claims_never_exceed_deposits(&state, &history));
+}
+
+#[test]
+pub fn test_simulation_bid_is_valid() {
+    let rules = BidRules {
+        highest_bid: 2_000,
+        min_increment: 200,
+        reserve_price: 1_000,
+        buyout_price: Some(2_100),
+        tie_policy: TiePolicy::FirstReceived {},
+    };
+    assert!(bid_is_valid(&rules, 2_200));
+    // the buyout price is accepted below the minimum increment
+    assert!(bid_is_valid(&rules, 2_100));
+    assert!(!bid_is_valid(&rules, 2_050));
+    let opening_rules = BidRules {
+        highest_bid: 0,
+        buyout_price: None,
+        ..rules
+    };
+    assert!(!bid_is_valid(&opening_rules, 999));
+    assert!(bid_is_valid(&opening_rules, 1_000));
+}
+
+#[test]
+pub fn test_simulation_settle() {
+    let rates = ProceedsRates {
+        keeper_reward_bps: 0,
+        referral_bps: 500,
+        fee_bps: 100,
+        royalty_bps: 250,
+        withholding_bps: vec![],
+        beneficiary_weights: vec![1, 2],
+    };
+    let outcome = simulate_settlement(SECOND_PRICE, 3_000, 3_000, 2_000, 1_000, &rates);
+    assert_eq!(
+        outcome,
+        SettlementOutcome {
+            price: 2_000,
+            winner_refund: 1_000,
+            proceeds: ProceedsSplit {
+                keeper_reward: 0,
+                referral_reward: 100,
+                fee: 20,
+                royalty: 50,
+                withholdings: vec![],
+                beneficiary_shares: vec![610, 1_220],
+                owner_share: 0,
+            },
+        }
+    );
+    assert_eq!(clearing_price(FIRST_PRICE, 3_000, 2_000, 1_000), 3_000);
+    assert_eq!(clearing_price(SECOND_PRICE, 3_000, 500, 1_000), 1_000);
+}
+
+/// A started auction with the terms of `initialize_contract` and a bid of 2000, configured with
+/// `tie_policy` and, unless the policy refunds ties, without a minimum increment.
+fn contract_with_tie_policy(tie_policy: TiePolicy) -> AuctionContractState {
+    let owner = get_owner_address();
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.token_amount_for_sale = 100_000;
+    config.reserve_price = 1_000;
+    config.auction_duration_millis = 100 * 3_600_000;
+    config.tie_policy = tie_policy;
+    if tie_policy != (TiePolicy::FirstReceived {}) {
+        config.min_increment = 0;
+    }
+    let (configured_state, _) = configure(create_ctx(owner, 2), init_state, config);
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        configured_state,
+    );
+    let bidder = get_bidder_address();
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_state,
+        Bid {
+            bidder,
+            amount: 2000,
+        },
+    );
+    bid_state
+}
+
+fn tied_contract(tie_policy: TiePolicy) -> AuctionContractState {
+    let state = contract_with_tie_policy(tie_policy);
+    let third_party = get_third_party_address();
+    let (tied_state, _) = bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder: third_party,
+            amount: 2000,
+        },
+    );
+    tied_state
+}
+
+#[test]
+pub fn test_tie_first_received_wins() {
+    let state = tied_contract(TiePolicy::FirstReceived {});
+    let third_party = get_third_party_address();
+    assert_eq!(state.highest_bidder.bidder, get_bidder_address());
+    assert_eq!(
+        state
+            .claim_map
+            .get(&third_party)
+            .unwrap()
+            .tokens_for_bidding,
+        2000
+    );
+}
+
+#[test]
+#[should_panic(expected = "[52] Tried to bid below the reserve price or the minimum increment")]
+pub fn test_tie_first_received_bid_too_low() {
+    let state = contract_with_tie_policy(TiePolicy::FirstReceived {});
+    bid(create_ctx(get_third_party_address(), 6), state, 2000);
+}
+
+#[test]
+pub fn test_tie_last_received_wins() {
+    let state = tied_contract(TiePolicy::LastReceived {});
+    let bidder = get_bidder_address();
+    assert_eq!(state.highest_bidder.bidder, get_third_party_address());
+    assert_eq!(
+        state.claim_map.get(&bidder).unwrap().tokens_for_bidding,
+        2000
+    );
+}
+
+#[test]
+pub fn test_tie_split() {
+    let state = tied_contract(TiePolicy::Split {});
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    assert_eq!(state.highest_bidder.bidder, bidder);
+    assert_eq!(state.tied_bids.len(), 1);
+    assert_eq!(state.tied_bids[0].sequence, 2);
+    let owner = get_owner_address();
+    let (execute_state, _) = execute(create_ctx(owner, 102), state);
+    assert!(execute_state.tied_bids.is_empty());
+    for tied_bidder in [bidder, third_party] {
+        assert_eq!(
+            *execute_state.claim_map.get(&tied_bidder).unwrap(),
+            TokenClaim {
+                tokens_for_bidding: 1000,
+                tokens_for_sale: 50_000,
+            }
+        );
+    }
+    assert_eq!(
+        execute_state
+            .claim_map
+            .get(&owner)
+            .unwrap()
+            .tokens_for_bidding,
+        2000
+    );
+}
+
+#[test]
+pub fn test_tie_split_refunded_when_outbid() {
+    let state = tied_contract(TiePolicy::Split {});
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let outbidder = get_royalty_receiver_address();
+    let (outbid_state, _) = bid_callback(
+        create_ctx(outbidder, 7),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder: outbidder,
+            amount: 2500,
+        },
+    );
+    assert!(outbid_state.tied_bids.is_empty());
+    for tied_bidder in [bidder, third_party] {
+        assert_eq!(
+            outbid_state
+                .claim_map
+                .get(&tied_bidder)
+                .unwrap()
+                .tokens_for_bidding,
+            2000
+        );
+    }
+}
+
+#[test]
+pub fn test_tie_split_block_highest_bidder() {
+    let state = tied_contract(TiePolicy::Split {});
+    let bidder = get_bidder_address();
+    let (blocked_state, _) = block_bidder(create_ctx(get_owner_address(), 7), state, bidder);
+    assert_eq!(
+        blocked_state.highest_bidder,
+        Bid {
+            bidder: get_third_party_address(),
+            amount: 2000,
+        }
+    );
+    assert!(blocked_state.tied_bids.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "[105] Tried to split ties in an auction whose sale cannot be split")]
+pub fn test_initialize_split_ties_second_price() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.tie_policy = TiePolicy::Split {};
+    config.settlement_mode = SECOND_PRICE;
+    configure(create_ctx(get_owner_address(), 2), init_state, config);
+}
+
+#[test]
+pub fn test_instant_win() {
+    let mut state = started_contract_with_bid(2000);
+    state.instant_win_multiple = Some(3);
+    let third_party = get_third_party_address();
+    let (bid_state, _) = bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder: third_party,
+            amount: 3_200,
+        },
+    );
+    assert_eq!(bid_state.status, AuctionStatus::Ended {});
+    assert_eq!(
+        *bid_state.claim_map.get(&third_party).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 100_000,
+        }
+    );
+    assert_eq!(
+        bid_state
+            .claim_map
+            .get(&get_owner_address())
+            .unwrap()
+            .tokens_for_bidding,
+        3_200
+    );
+}
+
+#[test]
+pub fn test_instant_win_below_threshold() {
+    let mut state = started_contract_with_bid(2000);
+    state.instant_win_multiple = Some(3);
+    let third_party = get_third_party_address();
+    let (bid_state, _) = bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder: third_party,
+            amount: 2_900,
+        },
+    );
+    assert_eq!(bid_state.status, AuctionStatus::Bidding {});
+}
+
+#[test]
+#[should_panic(
+    expected = "[106] Tried to configure an instant win multiple that is invalid for the auction"
+)]
+pub fn test_initialize_instant_win_without_reserve() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.reserve_price = 0;
+    config.instant_win_multiple = Some(3);
+    configure(create_ctx(get_owner_address(), 2), init_state, config);
+}
+
+fn english_auction_config() -> AuctionConfig {
+    AuctionConfig {
+        token_amount_for_sale: 50_000,
+        token_for_sale: get_commodity_token_address(),
+        token_for_bidding: get_currency_token_address(),
+        reserve_price: 2_000,
+        min_increment: 200,
+        auction_duration_millis: 50 * 3_600_000,
+        auction_format: ENGLISH,
+        price_schedule: None,
+        settlement_mode: FIRST_PRICE,
+        buyout_price: None,
+        sale_token_id: None,
+        start_time_millis: None,
+        royalty_receiver: None,
+        royalty_bps: 0,
+        fee_collector: None,
+        fee_bps: 0,
+        claim_deadline_millis: None,
+        sweep_sink: None,
+        max_bid_history: 0,
+        observer: None,
+        min_unique_bidders: 0,
+        reserve_commitment: None,
+        candle: None,
+        max_extension_hours: 0,
+        relist: None,
+        title: None,
+        description: None,
+        metadata_uri: None,
+        sale_token_interface: None,
+        bidding_token_interface: None,
+        bidding_balance_shortname: None,
+        allow_owner_bids: false,
+        keeper_reward_bps: 0,
+        bid_bond: None,
+        retraction_penalty_bps: None,
+        max_escrow_per_bidder: None,
+        kyc_registry: None,
+        penny: None,
+        sealed_bid_deposit: None,
+        accepted_currencies: vec![],
+        delivery_timeout_millis: None,
+        arbiter: None,
+        bidding_allowance_shortname: None,
+        strict_refunds: false,
+        semi_fungible_sale: false,
+        bundle: vec![],
+        rounds: vec![],
+        recurrence: None,
+        referral_bps: 0,
+        bid_cooldown_millis: None,
+        tie_policy: TiePolicy::LastReceived {},
+        instant_win_multiple: None,
+        recovery_period_millis: None,
+        reserve_oracle: None,
+        allow_same_token: false,
+        end_time_millis: None,
+        membership_gate: None,
+        withholdings: vec![],
+        decimals_shortname: None,
+        cancellable_after_bids: true,
+        setup_window_millis: None,
+    }
+}
+
+#[test]
+pub fn test_configure() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let third_party = get_third_party_address();
+    let (blocked_state, _) = block_bidder(create_ctx(owner, 2), init_state, third_party);
+    let (configured_state, events) = configure(
+        create_ctx(owner, 4),
+        blocked_state,
+        english_auction_config(),
+    );
+    assert_eq!(events.len(), 0);
+    assert_eq!(configured_state.contract_owner, owner);
+    assert_eq!(configured_state.status, AuctionStatus::Creation {});
+    assert_eq!(configured_state.token_amount_for_sale, 50_000);
+    assert_eq!(configured_state.reserve_price, 2_000);
+    assert_eq!(configured_state.min_increment, 200);
+    assert_eq!(configured_state.start_time_millis, 4 * 3_600_000);
+    assert_eq!(configured_state.end_time_millis, 54 * 3_600_000);
+    assert!(configured_state.blocklist.contains(&third_party));
+}
+
+#[test]
+#[should_panic(expected = "[2] The action is only allowed while setting up the contract")]
+pub fn test_configure_after_start() {
+    let state = started_contract_with_bid(2000);
+    configure(
+        create_ctx(get_owner_address(), 6),
+        state,
+        english_auction_config(),
+    );
+}
+
+#[test]
+#[should_panic(expected = "[1] Only the contract owner can perform this action")]
+pub fn test_configure_not_owner() {
+    let (init_state, _) = initialize_contract();
+    configure(
+        create_ctx(get_third_party_address(), 4),
+        init_state,
+        english_auction_config(),
+    );
+}
+
+#[test]
+#[should_panic(expected = "[10] Tried to create a Dutch auction without a price schedule")]
+pub fn test_configure_validates_config() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.auction_format = DUTCH;
+    configure(create_ctx(get_owner_address(), 4), init_state, config);
+}
+
+#[test]
+pub fn test_configure_metadata() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.title = Some("Genesis".to_string());
+    config.metadata_uri = Some("ipfs://genesis".to_string());
+    let (configured_state, _) = configure(create_ctx(get_owner_address(), 4), init_state, config);
+    assert_eq!(configured_state.title, Some("Genesis".to_string()));
+    assert_eq!(configured_state.description, None);
+    assert_eq!(
+        configured_state.metadata_uri,
+        Some("ipfs://genesis".to_string())
+    );
+}
+
+#[test]
+pub fn test_update_metadata() {
+    let (init_state, _) = initialize_contract();
+    let (updated_state, events) = update_metadata(
+        create_ctx(get_owner_address(), 2),
+        init_state,
+        Some("Genesis".to_string()),
+        Some("The first tokens minted".to_string()),
+        None,
+    );
+    assert_eq!(events.len(), 0);
+    assert_eq!(updated_state.title, Some("Genesis".to_string()));
+    assert_eq!(
+        updated_state.description,
+        Some("The first tokens minted".to_string())
+    );
+    assert_eq!(updated_state.metadata_uri, None);
+}
+
+#[test]
+#[should_panic(expected = "[2] The action is only allowed while setting up the contract")]
+pub fn test_update_metadata_after_start() {
+    let state = started_contract_with_bid(2000);
+    update_metadata(
+        create_ctx(get_owner_address(), 6),
+        state,
+        Some("Genesis".to_string()),
+        None,
+        None,
+    );
+}
+
+fn contract_with_token_interfaces() -> AuctionContractState {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.sale_token_interface = Some(TokenInterface {
+        transfer_shortname: 0x21,
+        transfer_from_shortname: 0x23,
+    });
+    config.bidding_token_interface = Some(TokenInterface {
+        transfer_shortname: 0x11,
+        transfer_from_shortname: 0x13,
+    });
+    let (configured_state, _) = configure(create_ctx(get_owner_address(), 2), init_state, config);
+    configured_state
+}
+
+#[test]
+pub fn test_start_with_sale_token_interface() {
+    let state = contract_with_token_interfaces();
+    let owner = get_owner_address();
+    let (_, events) = start(create_ctx(owner, 3), state);
+    let mut expected = EventGroup::builder();
+    expected
+        .call(get_commodity_token_address(), Shortname::from_u32(0x23))
+        .argument(owner)
+        .argument(get_contract_address())
+        .argument(50_000u128)
+        .done();
+    expected
+        .with_callback(ShortnameCallback::from_u32(2))
+        .done();
+    assert_eq!(events, vec![expected.build()]);
+}
+
+#[test]
+pub fn test_bid_and_claim_with_bidding_token_interface() {
+    let state = contract_with_token_interfaces();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), state);
+    let bidder = get_bidder_address();
+    let (_, events) = bid(create_ctx(bidder, 4), started_state.clone(), 3_000);
+    let mut expected = EventGroup::builder();
+    expected
+        .call(get_currency_token_address(), Shortname::from_u32(0x13))
+        .argument(bidder)
+        .argument(get_contract_address())
+        .argument(3_000u128)
+        .done();
+    expected
+        .with_callback(ShortnameCallback::from_u32(4))
+        .argument(bidder)
+        .argument(3_000u128)
+        .done();
+    assert_eq!(events, vec![expected.build()]);
+    // A bid below the reserve price is refunded through a claim
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 4),
+        create_callback_ctx(true),
+        started_state,
+        Bid {
+            bidder,
+            amount: 1_000,
+        },
+    );
+    let (_, events) = claim(create_ctx(bidder, 5), bid_state);
+    let mut expected = EventGroup::builder();
+    expected
+        .call(get_currency_token_address(), Shortname::from_u32(0x11))
+        .argument(bidder)
+        .argument(1_000u128)
+        .done();
+    expected
+        .with_callback(ShortnameCallback::from_u32(0x13))
+        .argument(bidder)
+        .argument(TokenClaim {
+            tokens_for_bidding: 1_000,
+            tokens_for_sale: 0,
+        })
+        .done();
+    assert_eq!(events, vec![expected.build()]);
+}
+
+fn fee_on_transfer_contract() -> AuctionContractState {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let mut config = english_auction_config();
+    config.bidding_balance_shortname = Some(0x07);
+    let (configured_state, _) = configure(create_ctx(owner, 2), init_state, config);
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        configured_state,
+    );
+    started_state
+}
+
+fn balance_callback_ctx(balance_before: u128, balance_after: u128) -> CallbackContext {
+    CallbackContext {
+        success: true,
+        results: vec![
+            ExecutionResult {
+                succeeded: true,
+                return_data: balance_before.to_be_bytes().to_vec(),
+            },
+            ExecutionResult {
+                succeeded: true,
+                return_data: vec![],
+            },
+            ExecutionResult {
+                succeeded: true,
+                return_data: balance_after.to_be_bytes().to_vec(),
+            },
+        ],
+    }
+}
+
+#[test]
+pub fn test_bid_queries_balance_around_transfer() {
+    let state = fee_on_transfer_contract();
+    let bidder = get_bidder_address();
+    let (_, events) = bid(create_ctx(bidder, 4), state, 3_000);
+    let mut expected = EventGroup::builder();
+    expected
+        .call(get_currency_token_address(), Shortname::from_u32(0x07))
+        .argument(get_contract_address())
+        .done();
+    expected
+        .call(get_currency_token_address(), Shortname::from_u32(3))
+        .argument(bidder)
+        .argument(get_contract_address())
+        .argument(3_000u128)
+        .done();
+    expected
+        .call(get_currency_token_address(), Shortname::from_u32(0x07))
+        .argument(get_contract_address())
+        .done();
+    expected
+        .with_callback(ShortnameCallback::from_u32(4))
+        .argument(bidder)
+        .argument(3_000u128)
+        .done();
+    assert_eq!(events, vec![expected.build()]);
+}
+
+#[test]
+pub fn test_bid_callback_credits_received_amount() {
+    let state = fee_on_transfer_contract();
+    let bidder = get_bidder_address();
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 4),
+        balance_callback_ctx(10_000, 12_970),
+        state,
+        Bid {
+            bidder,
+            amount: 3_000,
+        },
+    );
+    assert_eq!(
+        bid_state.highest_bidder,
+        Bid {
+            bidder,
+            amount: 2_970,
+        }
+    );
+}
+
+#[test]
+pub fn test_bid_callback_below_reserve_after_fee_refunded() {
+    let state = fee_on_transfer_contract();
+    let bidder = get_bidder_address();
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 4),
+        balance_callback_ctx(10_000, 11_980),
+        state,
+        Bid {
+            bidder,
+            amount: 2_000,
+        },
+    );
+    assert_eq!(bid_state.highest_bidder.amount, 0);
+    assert_eq!(
+        *bid_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 1_980,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "[50] The contract owner cannot bid on the auction")]
+pub fn test_owner_bid_rejected() {
+    let state = started_contract_with_bid(2000);
+    bid(create_ctx(get_owner_address(), 6), state, 3_000);
+}
+
+#[test]
+#[should_panic(expected = "[50] The contract owner cannot bid on the auction")]
+pub fn test_owner_proxy_bid_rejected() {
+    let state = started_contract_with_bid(2000);
+    proxy_bid(create_ctx(get_owner_address(), 6), state, 3_000);
+}
+
+#[test]
+pub fn test_owner_bid_allowed() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let mut config = english_auction_config();
+    config.allow_owner_bids = true;
+    let (configured_state, _) = configure(create_ctx(owner, 2), init_state, config);
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        configured_state,
+    );
+    let (_, events) = bid(create_ctx(owner, 4), started_state, 3_000);
+    assert_eq!(events.len(), 1);
+}
+
+#[test]
+#[should_panic(expected = "[52] Tried to bid below the reserve price or the minimum increment")]
+pub fn test_bid_below_reserve_rejected() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    bid(create_ctx(get_bidder_address(), 4), started_state, 999);
+}
+
+#[test]
+#[should_panic(expected = "[52] Tried to bid below the reserve price or the minimum increment")]
+pub fn test_bid_below_increment_rejected() {
+    let state = started_contract_with_bid(2000);
+    bid(create_ctx(get_third_party_address(), 6), state, 2_099);
+}
+
+#[test]
+#[should_panic(expected = "[52] Tried to bid below the reserve price or the minimum increment")]
+pub fn test_top_up_below_increment_rejected() {
+    let state = started_contract_with_bid(2000);
+    bid(create_ctx(get_bidder_address(), 6), state, 2_099);
+}
+
+#[test]
+#[should_panic(expected = "[5] The action is only allowed before auction end block time")]
+pub fn test_bid_after_end_time_rejected() {
+    let state = started_contract_with_bid(2000);
+    bid(create_ctx(get_third_party_address(), 102), state, 3_000);
+}
+
+#[test]
+#[should_panic(expected = "[3] The action is only allowed while the auction is bidding")]
+pub fn test_bid_before_start_rejected() {
+    let (init_state, _) = initialize_contract();
+    bid(create_ctx(get_bidder_address(), 3), init_state, 3_000);
+}
+
+#[test]
+#[should_panic(expected = "[51] Tried to bid outside an English auction")]
+pub fn test_bid_dutch_rejected() {
+    let (init_state, _) = initialize_dutch_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    bid(
+        create_ctx(get_bidder_address(), 4),
+        started_state,
+        1_000_000,
+    );
+}
+
+#[test]
+pub fn test_initialize_duration_in_minutes() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.auction_duration_millis = 90 * 60_000;
+    let (configured_state, _) = configure(create_ctx(get_owner_address(), 4), init_state, config);
+    assert_eq!(
+        configured_state.end_time_millis,
+        4 * 3_600_000 + 90 * 60_000
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "[53] Tried to create an auction shorter than a minute or longer than a year"
+)]
+pub fn test_initialize_zero_duration() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.auction_duration_millis = 0;
+    configure(create_ctx(get_owner_address(), 4), init_state, config);
+}
+
+#[test]
+#[should_panic(
+    expected = "[53] Tried to create an auction shorter than a minute or longer than a year"
+)]
+pub fn test_initialize_duration_above_maximum() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.auction_duration_millis = 366 * 24 * 3_600_000;
+    configure(create_ctx(get_owner_address(), 4), init_state, config);
+}
+
+#[test]
+pub fn test_initialize_absolute_end_time() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.start_time_millis = Some(10 * 3_600_000);
+    config.end_time_millis = Some(30 * 3_600_000);
+    let (configured_state, _) = configure(create_ctx(get_owner_address(), 4), init_state, config);
+    assert_eq!(configured_state.start_time_millis, 10 * 3_600_000);
+    assert_eq!(configured_state.end_time_millis, 30 * 3_600_000);
+}
+
+#[test]
+#[should_panic(expected = "[126] Tried to create an auction ending before it starts")]
+pub fn test_initialize_end_time_before_start() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.end_time_millis = Some(3 * 3_600_000);
+    configure(create_ctx(get_owner_address(), 4), init_state, config);
+}
+
+#[test]
+#[should_panic(expected = "[122] Tried to create an auction of zero tokens")]
+pub fn test_initialize_zero_sale_amount() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.token_amount_for_sale = 0;
+    configure(create_ctx(get_owner_address(), 4), init_state, config);
+}
+
+#[test]
+#[should_panic(
+    expected = "[123] Tried to create an auction selling the bidding token without allowing it"
+)]
+pub fn test_initialize_same_tokens() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.token_for_bidding = config.token_for_sale;
+    configure(create_ctx(get_owner_address(), 4), init_state, config);
+}
+
+#[test]
+pub fn test_initialize_same_tokens_allowed() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.token_for_bidding = config.token_for_sale;
+    config.allow_same_token = true;
+    let (configured_state, _) = configure(create_ctx(get_owner_address(), 4), init_state, config);
+    assert_eq!(
+        configured_state.token_for_bidding,
+        configured_state.token_for_sale
+    );
+}
+
+#[test]
+#[should_panic(expected = "[124] Tried to create an English auction without a minimum increment")]
+pub fn test_initialize_zero_increment() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.min_increment = 0;
+    config.tie_policy = TiePolicy::FirstReceived {};
+    configure(create_ctx(get_owner_address(), 4), init_state, config);
+}
+
+#[test]
+pub fn test_initialize_zero_increment_with_ties() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.min_increment = 0;
+    config.tie_policy = TiePolicy::Split {};
+    let (configured_state, _) = configure(create_ctx(get_owner_address(), 4), init_state, config);
+    assert_eq!(configured_state.min_increment, 0);
+    assert_eq!(configured_state.tie_policy, TiePolicy::Split {});
+}
+
+#[test]
+#[should_panic(
+    expected = "[125] Tried to create a contract scheduled to start more than a year from now"
+)]
+pub fn test_initialize_start_time_too_far() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.start_time_millis = Some(4 * 3_600_000 + 366 * 24 * 3_600_000);
+    configure(create_ctx(get_owner_address(), 4), init_state, config);
+}
+
+#[test]
+pub fn test_execute_rewards_keeper() {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let mut config = english_auction_config();
+    config.keeper_reward_bps = 100;
+    let (configured_state, _) = configure(create_ctx(owner, 2), init_state, config);
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        configured_state,
+    );
+    let bidder = get_bidder_address();
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 4),
+        create_callback_ctx(true),
+        started_state,
+        Bid {
+            bidder,
+            amount: 3_000,
+        },
+    );
+    let keeper = get_third_party_address();
+    let (execute_state, _) = execute(create_ctx(keeper, 52), bid_state);
+    assert_eq!(execute_state.status, AuctionStatus::Ended {});
+    assert_eq!(
+        *execute_state.claim_map.get(&keeper).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 30,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        *execute_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 2_970,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "[17] Tried to create a royalty, fee and keeper reward above 100%")]
+pub fn test_initialize_keeper_reward_exceeds_proceeds() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.fee_collector = Some(get_fee_collector_address());
+    config.fee_bps = 9_000;
+    config.keeper_reward_bps = 1_001;
+    configure(create_ctx(get_owner_address(), 2), init_state, config);
+}
+
+fn started_contract_with_bid_bond() -> AuctionContractState {
+    let owner = get_owner_address();
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.bid_bond = Some(BidBondConfig {
+        bond: 500,
+        payment_window_millis: 10 * 3_600_000,
+    });
+    let (configured_state, _) = configure(create_ctx(owner, 2), init_state, config);
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        configured_state,
+    );
+    started_state
+}
+
+fn bonded_bid(state: AuctionContractState, bidder: Address, amount: u128) -> AuctionContractState {
+    let (bid_state, _) = bond_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        state,
+        Bid { bidder, amount },
+    );
+    bid_state
+}
+
+#[test]
+pub fn test_bid_escrows_bond() {
+    let state = started_contract_with_bid_bond();
+    let bidder = get_bidder_address();
+    let (bid_state, events) = bid(create_ctx(bidder, 5), state, 2_000);
+    assert_eq!(events.len(), 1);
+    assert_eq!(bid_state.highest_bidder.amount, 0);
+    let bid_state = bonded_bid(bid_state, bidder, 2_000);
+    assert_eq!(bid_state.bonded_bids.get(&bidder), Some(&2_000));
+    assert_eq!(
+        bid_state.highest_bidder,
+        Bid {
+            bidder,
+            amount: 2_000,
+        }
+    );
+}
+
+#[test]
+pub fn test_bonded_bidder_raises_without_transfer() {
+    let bidder = get_bidder_address();
+    let state = bonded_bid(started_contract_with_bid_bond(), bidder, 2_000);
+    let (raised_state, events) = bid(create_ctx(bidder, 6), state, 3_000);
+    assert_eq!(events.len(), 0);
+    assert_eq!(raised_state.bonded_bids.get(&bidder), Some(&3_000));
+    assert_eq!(raised_state.highest_bidder.amount, 3_000);
+}
+
+#[test]
+pub fn test_bond_callback_refunds_outbid_bond() {
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let state = bonded_bid(started_contract_with_bid_bond(), bidder, 3_000);
+    let state = bonded_bid(state, third_party, 2_500);
+    assert!(!state.bonded_bids.contains_key(&third_party));
+    assert_eq!(
+        *state.claim_map.get(&third_party).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 500,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        *state.rejected_bids.get(&third_party).unwrap(),
+        RejectedBid {
+            amount: 500,
+            reason: RejectionReason::TooLow {},
+            timestamp_millis: 5 * 60 * 60 * 1000,
+        }
+    );
+}
+
+#[test]
+pub fn test_bond_callback_refunds_second_bond() {
+    let bidder = get_bidder_address();
+    let state = bonded_bid(started_contract_with_bid_bond(), bidder, 3_000);
+    let state = bonded_bid(state, bidder, 3_500);
+    assert_eq!(
+        state.rejected_bids.get(&bidder).unwrap().reason,
+        RejectionReason::AlreadyBonded {}
+    );
+}
+
+#[test]
+pub fn test_execute_awaits_payment() {
+    let bidder = get_bidder_address();
+    let state = bonded_bid(started_contract_with_bid_bond(), bidder, 2_000);
+    let (execute_state, events) = execute(create_ctx(get_third_party_address(), 55), state);
+    assert_eq!(events.len(), 0);
+    assert_eq!(execute_state.status, AuctionStatus::Settling {});
+    assert_eq!(execute_state.payment_deadline_millis, Some(65 * 3_600_000));
+    assert_eq!(execute_state.claim_map.len(), 0);
+}
+
+#[test]
+pub fn test_complete_payment() {
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let state = bonded_bid(started_contract_with_bid_bond(), third_party, 2_000);
+    let state = bonded_bid(state, bidder, 2_500);
+    let (execute_state, _) = execute(create_ctx(owner, 55), state);
+    let (payment_state, events) = complete_payment(create_ctx(bidder, 56), execute_state);
+    assert_eq!(events.len(), 1);
+    let winning_bid = Bid {
+        bidder,
+        amount: 2_500,
+    };
+    let (paid_state, _) = complete_payment_callback(
+        create_ctx(bidder, 56),
+        create_callback_ctx(true),
+        payment_state,
+        winning_bid,
+        2_000,
+    );
+    assert_eq!(paid_state.status, AuctionStatus::Ended {});
+    assert_eq!(
+        *paid_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 2_500,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        *paid_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 50_000,
+        }
+    );
+    assert_eq!(
+        *paid_state.claim_map.get(&third_party).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 500,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "[57] Only the winner of the auction can complete the payment")]
+pub fn test_complete_payment_not_winner() {
+    let bidder = get_bidder_address();
+    let state = bonded_bid(started_contract_with_bid_bond(), bidder, 2_000);
+    let (execute_state, _) = execute(create_ctx(bidder, 55), state);
+    complete_payment(create_ctx(get_third_party_address(), 56), execute_state);
+}
+
+#[test]
+#[should_panic(expected = "[58] Tried to complete the payment after the payment deadline")]
+pub fn test_complete_payment_after_deadline() {
+    let bidder = get_bidder_address();
+    let state = bonded_bid(started_contract_with_bid_bond(), bidder, 2_000);
+    let (execute_state, _) = execute(create_ctx(bidder, 55), state);
+    complete_payment(create_ctx(bidder, 65), execute_state);
+}
+
+#[test]
+#[should_panic(expected = "[59] Tried to pass over the winner before the payment deadline")]
+pub fn test_execute_before_payment_deadline() {
+    let bidder = get_bidder_address();
+    let state = bonded_bid(started_contract_with_bid_bond(), bidder, 2_000);
+    let (execute_state, _) = execute(create_ctx(bidder, 55), state);
+    execute(create_ctx(bidder, 60), execute_state);
+}
+
+#[test]
+pub fn test_execute_passes_over_unpaid_winner() {
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let state = bonded_bid(started_contract_with_bid_bond(), third_party, 2_000);
+    let state = bonded_bid(state, bidder, 2_500);
+    let (execute_state, _) = execute(create_ctx(owner, 55), state);
+    let (passed_state, _) = execute(create_ctx(owner, 65), execute_state);
+    assert_eq!(passed_state.status, AuctionStatus::Settling {});
+    assert_eq!(
+        passed_state.highest_bidder,
+        Bid {
+            bidder: third_party,
+            amount: 2_000,
+        }
+    );
+    assert_eq!(passed_state.payment_deadline_millis, Some(75 * 3_600_000));
+    assert_eq!(
+        *passed_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 500,
+            tokens_for_sale: 0,
+        }
+    );
+    let (failed_state, _) = execute(create_ctx(owner, 75), passed_state);
+    assert_eq!(failed_state.status, AuctionStatus::Failed {});
+    assert_eq!(
+        *failed_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 1_000,
+            tokens_for_sale: 50_000,
+        }
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "[55] Tried to create a bid bond auction that is not a plain first price English auction"
+)]
+pub fn test_initialize_bid_bond_with_buyout() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.buyout_price = Some(10_000);
+    config.bid_bond = Some(BidBondConfig {
+        bond: 500,
+        payment_window_millis: 10 * 3_600_000,
+    });
+    configure(create_ctx(get_owner_address(), 2), init_state, config);
+}
+
+fn started_contract_with_deposit(amount: u128) -> AuctionContractState {
+    let (init_state, _) = initialize_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    let bidder = get_bidder_address();
+    let (deposit_state, _) = deposit_callback(
+        create_ctx(bidder, 4),
+        create_callback_ctx(true),
+        started_state,
+        bidder,
+        amount,
+    );
+    deposit_state
+}
+
+#[test]
+pub fn test_deposit() {
+    let state = started_contract_with_deposit(1_000);
+    let bidder = get_bidder_address();
+    let (deposit_state, events) = deposit(create_ctx(bidder, 4), state, 500);
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_currency_token_address(), Shortname::from_u32(3))
+        .argument(bidder)
+        .argument(get_contract_address())
+        .argument(500u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x27))
+        .argument(bidder)
+        .argument(500u128)
+        .done();
+    assert_eq!(events, vec![expected_event.build()]);
+    let (deposit_state, _) = deposit_callback(
+        create_ctx(bidder, 4),
+        create_callback_ctx(true),
+        deposit_state,
+        bidder,
+        500,
+    );
+    assert_eq!(deposit_state.balances.get(&bidder), Some(&1_500));
+}
+
+#[test]
+#[should_panic(expected = "[61] Tried to deposit before the auction was started")]
+pub fn test_deposit_before_start() {
+    let (init_state, _) = initialize_contract();
+    deposit(create_ctx(get_bidder_address(), 2), init_state, 500);
+}
+
+#[test]
+pub fn test_deposit_callback_opens_scheduled_bidding() {
+    let (init_state, _) = initialize_scheduled_contract();
+    let owner = get_owner_address();
+    let (started_state, _) =
+        start_with_escrow(create_ctx(owner, 3), create_callback_ctx(true), init_state);
+    let bidder = get_bidder_address();
+    let (early_state, _) = deposit_callback(
+        create_ctx(bidder, 9),
+        create_callback_ctx(true),
+        started_state,
+        bidder,
+        500,
+    );
+    assert_eq!(early_state.status, AuctionStatus::BiddingScheduled {});
+    let (open_state, _) = deposit_callback(
+        create_ctx(bidder, 10),
+        create_callback_ctx(true),
+        early_state,
+        bidder,
+        500,
+    );
+    assert_eq!(open_state.status, AuctionStatus::Bidding {});
+    assert_eq!(open_state.balances.get(&bidder), Some(&1_000));
+}
+
+#[test]
+pub fn test_bid_from_balance() {
+    let state = started_contract_with_deposit(5_000);
+    let bidder = get_bidder_address();
+    let (bid_state, events) = bid(create_ctx(bidder, 5), state, 2_000);
+    assert_eq!(events.len(), 0);
+    assert_eq!(
+        bid_state.highest_bidder,
+        Bid {
+            bidder,
+            amount: 2_000,
+        }
+    );
+    assert_eq!(bid_state.balances.get(&bidder), Some(&3_000));
+    let (raised_state, events) = bid(create_ctx(bidder, 6), bid_state, 2_500);
+    assert_eq!(events.len(), 0);
+    assert_eq!(raised_state.highest_bidder.amount, 2_500);
+    assert_eq!(raised_state.balances.get(&bidder), Some(&2_500));
+}
+
+#[test]
+pub fn test_bid_above_balance_transfers() {
+    let state = started_contract_with_deposit(1_000);
+    let bidder = get_bidder_address();
+    let (bid_state, events) = bid(create_ctx(bidder, 5), state, 2_000);
+    assert_eq!(events.len(), 1);
+    assert_eq!(bid_state.highest_bidder.amount, 0);
+    assert_eq!(bid_state.balances.get(&bidder), Some(&1_000));
+}
+
+#[test]
+pub fn test_withdraw() {
+    let state = started_contract_with_deposit(1_000);
+    let bidder = get_bidder_address();
+    let (withdraw_state, events) = withdraw(create_ctx(bidder, 5), state, 400);
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_currency_token_address(), Shortname::from_u32(1))
+        .argument(bidder)
+        .argument(400u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x29))
+        .argument(bidder)
+        .argument(400u128)
+        .done();
+    assert_eq!(events, vec![expected_event.build()]);
+    assert_eq!(withdraw_state.balances.get(&bidder), Some(&600));
+    let (failed_state, _) = withdraw_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(false),
+        withdraw_state,
+        bidder,
+        400,
+    );
+    assert_eq!(failed_state.balances.get(&bidder), Some(&1_000));
+}
+
+#[test]
+#[should_panic(expected = "[60] Tried to withdraw more than the deposited balance")]
+pub fn test_withdraw_more_than_balance() {
+    let state = started_contract_with_deposit(1_000);
+    withdraw(create_ctx(get_bidder_address(), 5), state, 1_001);
+}
+
+#[test]
+pub fn test_fixed_price_fills_orders_first_come() {
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let state = place_orders(
+        started_multi_unit_contract(FIXED_PRICE),
+        vec![(bidder, 4, 10), (third_party, 8, 12)],
+    );
+    assert_eq!(state.status, AuctionStatus::Ended {});
+    assert_eq!(state.units_sold, 10);
+    assert_eq!(
+        *state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 4,
+        }
+    );
+    // The order exceeding the remaining units is partially filled at the fixed price
+    assert_eq!(
+        *state.claim_map.get(&third_party).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 36,
+            tokens_for_sale: 6,
+        }
+    );
+    assert_eq!(
+        *state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 100,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+pub fn test_fixed_price_owner_reclaims_unsold_units() {
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let state = place_orders(
+        started_multi_unit_contract(FIXED_PRICE),
+        vec![(bidder, 3, 10)],
+    );
+    assert_eq!(state.status, AuctionStatus::Bidding {});
+    let (execute_state, _) = execute(create_ctx(owner, 103), state);
+    assert_eq!(execute_state.status, AuctionStatus::Ended {});
+    assert_eq!(
+        *execute_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 30,
+            tokens_for_sale: 7,
+        }
+    );
+}
+
+#[test]
+pub fn test_fixed_price_cancel_returns_unsold_units() {
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let state = place_orders(
+        started_multi_unit_contract(FIXED_PRICE),
+        vec![(bidder, 3, 10)],
+    );
+    let (cancel_state, _) = cancel(create_ctx(owner, 6), state);
+    assert_eq!(
+        *cancel_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 30,
+            tokens_for_sale: 7,
+        }
+    );
+    assert_eq!(
+        *cancel_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 3,
+        }
+    );
+}
+
+fn started_contract_with_retraction(penalty_bps: u32) -> AuctionContractState {
+    let owner = get_owner_address();
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.retraction_penalty_bps = Some(penalty_bps);
+    let (configured_state, _) = configure(create_ctx(owner, 2), init_state, config);
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        configured_state,
+    );
+    started_state
+}
+
+#[test]
+pub fn test_retract_highest_bid() {
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_contract_with_retraction(1_000),
+        Bid {
+            bidder,
+            amount: 3_000,
+        },
+    );
+    let (retract_state, _) = retract_bid(create_ctx(bidder, 6), bid_state);
+    assert_eq!(
+        retract_state.highest_bidder,
+        Bid {
+            bidder: owner,
+            amount: 0,
+        }
+    );
+    assert_eq!(
+        *retract_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 2_700,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        *retract_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 300,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+pub fn test_retract_order_without_penalty() {
+    let bidder = get_bidder_address();
+    let mut state = started_multi_unit_contract(UNIFORM_PRICE);
+    state.retraction_penalty_bps = Some(1_000);
+    let state = place_orders(state, vec![(bidder, 2, 10)]);
+    let (retract_state, _) = retract_bid(create_ctx(bidder, 6), state);
+    assert!(retract_state.orders.is_empty());
+    assert_eq!(
+        *retract_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 20,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "[64] Tried to retract without a bid in the auction")]
+pub fn test_retract_without_bid() {
+    let state = started_contract_with_retraction(1_000);
+    retract_bid(create_ctx(get_bidder_address(), 6), state);
+}
+
+#[test]
+#[should_panic(expected = "[62] The auction does not allow retracting bids")]
+pub fn test_retract_disabled() {
+    let state = started_contract_with_bid(2000);
+    retract_bid(create_ctx(get_bidder_address(), 6), state);
+}
+
+#[test]
+#[should_panic(expected = "[5] The action is only allowed before auction end block time")]
+pub fn test_retract_after_end() {
+    let bidder = get_bidder_address();
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_contract_with_retraction(1_000),
+        Bid {
+            bidder,
+            amount: 3_000,
+        },
+    );
+    retract_bid(create_ctx(bidder, 60), bid_state);
+}
+
+#[test]
+#[should_panic(expected = "[63] Tried to create a retraction penalty above 100%")]
+pub fn test_initialize_retraction_penalty_above_bid() {
+    started_contract_with_retraction(10_001);
+}
+
+fn started_contract_with_escrow_cap(cap: u128) -> AuctionContractState {
+    let owner = get_owner_address();
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.max_escrow_per_bidder = Some(cap);
+    let (configured_state, _) = configure(create_ctx(owner, 2), init_state, config);
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        configured_state,
+    );
+    started_state
+}
+
+#[test]
+pub fn test_bid_callback_refunds_escrow_above_cap() {
+    let bidder = get_bidder_address();
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_contract_with_escrow_cap(3_000),
+        Bid {
+            bidder,
+            amount: 4_000,
+        },
+    );
+    assert_eq!(
+        bid_state.highest_bidder,
+        Bid {
+            bidder,
+            amount: 3_000,
+        }
+    );
+    assert_eq!(
+        *bid_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 1_000,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+pub fn test_top_up_callback_refunds_escrow_above_cap() {
+    let bidder = get_bidder_address();
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_contract_with_escrow_cap(3_000),
+        Bid {
+            bidder,
+            amount: 2_500,
+        },
+    );
+    let (top_up_state, _) = top_up_callback(
+        create_ctx(bidder, 6),
+        create_callback_ctx(true),
+        bid_state,
+        Bid {
+            bidder,
+            amount: 3_500,
+        },
+        1_000,
+    );
+    assert_eq!(top_up_state.highest_bidder.amount, 3_000);
+    assert_eq!(
+        *top_up_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 500,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+pub fn test_bid_callback_refunds_bid_capped_below_reserve() {
+    let bidder = get_bidder_address();
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_contract_with_escrow_cap(1_500),
+        Bid {
+            bidder,
+            amount: 2_000,
+        },
+    );
+    assert_eq!(bid_state.highest_bidder.amount, 0);
+    assert_eq!(
+        *bid_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 2_000,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+fn get_kyc_registry_address() -> Address {
+    Address {
+        address_type: AddressType::PublicContract,
+        identifier: [9u8, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9],
+    }
+}
+
+fn started_contract_with_kyc_registry() -> AuctionContractState {
+    let owner = get_owner_address();
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.kyc_registry = Some(KycRegistry {
+        address: get_kyc_registry_address(),
+        is_verified_shortname: 0x42,
+    });
+    let (configured_state, _) = configure(create_ctx(owner, 2), init_state, config);
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        configured_state,
+    );
+    started_state
+}
+
+fn registry_callback_ctx(verified: bool) -> CallbackContext {
+    CallbackContext {
+        success: true,
+        results: vec![ExecutionResult {
+            succeeded: true,
+            return_data: vec![u8::from(verified)],
+        }],
+    }
+}
+
+#[test]
+pub fn test_verify_bidder() {
+    let bidder = get_bidder_address();
+    let state = started_contract_with_kyc_registry();
+    let (verify_state, events) = verify_bidder(create_ctx(bidder, 4), state);
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_kyc_registry_address(), Shortname::from_u32(0x42))
+        .argument(bidder)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x2c))
+        .argument(bidder)
+        .done();
+    assert_eq!(events, vec![expected_event.build()]);
+    let (verified_state, _) = verify_bidder_callback(
+        create_ctx(bidder, 4),
+        registry_callback_ctx(true),
+        verify_state,
+        bidder,
+    );
+    assert!(verified_state.verified_bidders.contains(&bidder));
+    let (_, events) = bid(create_ctx(bidder, 5), verified_state, 2_000);
+    assert_eq!(events.len(), 1);
+}
+
+#[test]
+pub fn test_verify_bidder_rejected_by_registry() {
+    let bidder = get_bidder_address();
+    let (state, _) = verify_bidder_callback(
+        create_ctx(bidder, 4),
+        registry_callback_ctx(false),
+        started_contract_with_kyc_registry(),
+        bidder,
+    );
+    assert!(state.verified_bidders.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "[65] Only addresses verified by the KYC registry can bid")]
+pub fn test_bid_not_verified() {
+    let state = started_contract_with_kyc_registry();
+    bid(create_ctx(get_bidder_address(), 5), state, 2_000);
+}
+
+#[test]
+#[should_panic(expected = "[66] The auction has no KYC registry to verify with")]
+pub fn test_verify_bidder_without_registry() {
+    let state = started_contract_with_bid(2000);
+    verify_bidder(create_ctx(get_bidder_address(), 5), state);
+}
+
+fn get_membership_token_address() -> Address {
+    Address {
+        address_type: AddressType::PublicContract,
+        identifier: [10u8; 20],
+    }
+}
+
+fn started_contract_with_membership_gate() -> AuctionContractState {
+    let owner = get_owner_address();
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.membership_gate = Some(MembershipGate {
+        address: get_membership_token_address(),
+        balance_shortname: 0x06,
+    });
+    let (configured_state, _) = configure(create_ctx(owner, 2), init_state, config);
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        configured_state,
+    );
+    started_state
+}
+
+#[test]
+pub fn test_verify_membership() {
+    let bidder = get_bidder_address();
+    let state = started_contract_with_membership_gate();
+    let (verify_state, events) = verify_membership(create_ctx(bidder, 4), state);
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_membership_token_address(), Shortname::from_u32(0x06))
+        .argument(bidder)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x51))
+        .argument(bidder)
+        .done();
+    assert_eq!(events, vec![expected_event.build()]);
+    let (member_state, _) = verify_membership_callback(
+        create_ctx(bidder, 4),
+        callback_context_returning(1u128),
+        verify_state,
+        bidder,
+    );
+    assert!(member_state.members.contains(&bidder));
+    let (_, events) = bid(create_ctx(bidder, 5), member_state, 2_000);
+    assert_eq!(events.len(), 1);
+}
+
+#[test]
+#[should_panic(expected = "[127] Only holders of the membership token can bid")]
+pub fn test_bid_without_membership_token() {
+    let bidder = get_bidder_address();
+    let (state, _) = verify_membership_callback(
+        create_ctx(bidder, 4),
+        callback_context_returning(0u128),
+        started_contract_with_membership_gate(),
+        bidder,
+    );
+    assert!(state.members.is_empty());
+    bid(create_ctx(bidder, 5), state, 2_000);
+}
+
+#[test]
+pub fn test_claim_for() {
+    let (mut init_state, _) = initialize_contract();
+    let bidder = get_bidder_address();
+    init_state.add_to_claim_map(
+        bidder,
+        TokenClaim {
+            tokens_for_bidding: 1000,
+            tokens_for_sale: 0,
+        },
+    );
+    let (claim_state, claim_events) =
+        claim_for(create_ctx(get_third_party_address(), 4), init_state, bidder);
+    assert_eq!(
+        *claim_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 0,
+        }
+    );
+    // The tokens go to the claimant, not to the sender of the claim
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_currency_token_address(), Shortname::from_u32(1))
+        .argument(bidder)
+        .argument(1000u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x13))
+        .argument(bidder)
+        .argument(1000u128)
+        .argument(0u128)
+        .done();
+    assert_eq!(claim_events, vec![expected_event.build()]);
+}
+
+#[test]
+pub fn test_claim_for_without_claim() {
+    let (init_state, _) = initialize_contract();
+    let (claim_state, claim_events) = claim_for(
+        create_ctx(get_third_party_address(), 4),
+        init_state,
+        get_bidder_address(),
+    );
+    assert_eq!(claim_events.len(), 0);
+    assert_eq!(claim_state.claim_map.len(), 0);
+}
+
+fn get_cold_wallet_address() -> Address {
+    Address {
+        address_type: AddressType::Account,
+        identifier: [0u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9],
+    }
+}
+
+#[test]
+pub fn test_claim_pays_registered_recipient() {
+    let bidder = get_bidder_address();
+    let (execute_state, _) = execute(
+        create_ctx(get_owner_address(), 102),
+        started_contract_with_bid(2000),
+    );
+    let (mut execute_state, _) = set_payout_recipient(
+        create_ctx(bidder, 102),
+        execute_state,
+        get_cold_wallet_address(),
+    );
+    execute_state.add_to_claim_map(
+        bidder,
+        TokenClaim {
+            tokens_for_bidding: 500,
+            tokens_for_sale: 0,
+        },
+    );
+    let (_, claim_events) = claim(create_ctx(bidder, 103), execute_state);
+    // The tokens go to the recipient, while a failed transfer is restored to the winner
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_currency_token_address(), Shortname::from_u32(1))
+        .argument(get_cold_wallet_address())
+        .argument(500u128)
+        .done();
+    expected_event
+        .call(get_commodity_token_address(), Shortname::from_u32(1))
+        .argument(get_cold_wallet_address())
+        .argument(100_000u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x13))
+        .argument(bidder)
+        .argument(500u128)
+        .argument(100_000u128)
+        .done();
+    assert_eq!(claim_events, vec![expected_event.build()]);
+}
+
+#[test]
+pub fn test_set_payout_recipient_to_self_clears_recipient() {
+    let owner = get_owner_address();
+    let (cancelled_state, _) = cancel(create_ctx(owner, 3), initialize_contract().0);
+    let (recipient_state, _) = set_payout_recipient(
+        create_ctx(owner, 4),
+        cancelled_state,
+        get_cold_wallet_address(),
+    );
+    assert_eq!(
+        recipient_state.payout_recipients.get(&owner),
+        Some(&get_cold_wallet_address())
+    );
+    let (cleared_state, _) = set_payout_recipient(create_ctx(owner, 5), recipient_state, owner);
+    assert!(cleared_state.payout_recipients.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "[107] Only the winner or the seller can register a payout recipient")]
+pub fn test_set_payout_recipient_not_winner() {
+    let (execute_state, _) = execute(
+        create_ctx(get_owner_address(), 102),
+        started_contract_with_bid(2000),
+    );
+    set_payout_recipient(
+        create_ctx(get_third_party_address(), 102),
+        execute_state,
+        get_cold_wallet_address(),
+    );
+}
+
+#[test]
+#[should_panic(expected = "[107] Only the winner or the seller can register a payout recipient")]
+pub fn test_set_payout_recipient_outbid_bidder() {
+    let third_party = get_third_party_address();
+    let (outbid_state, _) = bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        started_contract_with_bid(2000),
+        Bid {
+            bidder: third_party,
+            amount: 2500,
+        },
+    );
+    let (execute_state, _) = execute(create_ctx(get_owner_address(), 102), outbid_state);
+    set_payout_recipient(
+        create_ctx(get_bidder_address(), 102),
+        execute_state,
+        get_cold_wallet_address(),
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "[30] The action is only allowed after the auction has ended, failed or been cancelled"
+)]
+pub fn test_set_payout_recipient_before_end() {
+    set_payout_recipient(
+        create_ctx(get_bidder_address(), 6),
+        started_contract_with_bid(2000),
+        get_cold_wallet_address(),
+    );
+}
+
+#[test]
+pub fn test_execute_drops_payout_recipients_of_losers() {
+    let bidder = get_bidder_address();
+    let mut state = started_contract_with_bid(2000);
+    // Recipients registered while the auction was still bidding
+    state
+        .payout_recipients
+        .insert(get_third_party_address(), get_cold_wallet_address());
+    state
+        .payout_recipients
+        .insert(bidder, get_cold_wallet_address());
+    let (execute_state, _) = execute(create_ctx(get_owner_address(), 102), state);
+    assert_eq!(
+        execute_state.payout_recipients.keys().collect::<Vec<_>>(),
+        vec![&bidder]
+    );
+}
+
+#[test]
+pub fn test_audit_balances_through_lifecycle() {
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let state = started_contract_with_bid(2000);
+    assert_eq!(state.total_escrowed_bidding, 2000);
+    assert_eq!(state.total_escrowed_sale, 100_000);
+    let (outbid_state, _) = bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder: third_party,
+            amount: 2500,
+        },
+    );
+    let (audited_state, _) = audit(create_ctx(owner, 7), outbid_state);
+    let (execute_state, _) = execute(create_ctx(owner, 102), audited_state);
+    let (audited_state, _) = audit(create_ctx(owner, 102), execute_state);
+    let (claim_state, _) = claim(create_ctx(bidder, 103), audited_state);
+    assert_eq!(claim_state.total_escrowed_bidding, 2500);
+    let (claim_state, _) = claim(create_ctx(third_party, 103), claim_state);
+    assert_eq!(claim_state.total_escrowed_sale, 0);
+    // A failed payout is back in the books
+    let (callback_state, _) = claim_callback(
+        create_ctx(third_party, 104),
+        create_callback_ctx(false),
+        claim_state,
+        third_party,
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 100_000,
+        },
+    );
+    let (audited_state, _) = audit(create_ctx(owner, 105), callback_state);
+    assert_eq!(audited_state.total_escrowed_sale, 100_000);
+}
+
+#[test]
+#[should_panic(
+    expected = "[118] The bidding tokens owed do not match the tracked bidding token escrow"
+)]
+pub fn test_audit_unbalanced_bidding_books() {
+    let mut state = started_contract_with_bid(2000);
+    state.add_to_claim_map(
+        get_third_party_address(),
+        TokenClaim {
+            tokens_for_bidding: 1,
+            tokens_for_sale: 0,
+        },
+    );
+    audit(create_ctx(get_owner_address(), 6), state);
+}
+
+#[test]
+#[should_panic(
+    expected = "[119] The claimable tokens for sale exceed the tracked escrow of tokens for sale"
+)]
+pub fn test_audit_unbalanced_sale_books() {
+    let mut state = started_contract_with_bid(2000);
+    state.total_escrowed_sale = 50_000;
+    let (execute_state, _) = execute(create_ctx(get_owner_address(), 102), state);
+    audit(create_ctx(get_owner_address(), 102), execute_state);
+}
+
+/// A paused auction with a bid, which any participant can recover 48 hours after its end.
+fn abandoned_contract() -> AuctionContractState {
+    let mut state = started_contract_with_bid(2000);
+    state.recovery_period_millis = Some(48 * 60 * 60 * 1000);
+    let (paused_state, _) = pause(create_ctx(get_owner_address(), 6), state);
+    paused_state
+}
+
+#[test]
+pub fn test_recover_settles_paused_auction() {
+    let owner = get_owner_address();
+    let (recovered_state, events) =
+        recover(create_ctx(get_bidder_address(), 150), abandoned_contract());
+    assert_eq!(recovered_state.status, AuctionStatus::Ended {});
+    // The proceeds of the owner are pushed along with the refunds
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        *recovered_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        recovered_state
+            .claim_map
+            .get(&get_bidder_address())
+            .unwrap()
+            .tokens_for_sale,
+        100_000
+    );
+}
+
+#[test]
+#[should_panic(expected = "[116] Tried to recover the auction before its recovery period elapsed")]
+pub fn test_recover_before_recovery_period() {
+    recover(create_ctx(get_bidder_address(), 149), abandoned_contract());
+}
+
+#[test]
+#[should_panic(expected = "[117] Only a participant of the auction can recover it")]
+pub fn test_recover_not_participant() {
+    recover(
+        create_ctx(get_third_party_address(), 150),
+        abandoned_contract(),
+    );
+}
+
+#[test]
+#[should_panic(expected = "[115] Tried to configure a non-positive recovery period")]
+pub fn test_initialize_invalid_recovery_period() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.recovery_period_millis = Some(-1);
+    configure(create_ctx(get_owner_address(), 2), init_state, config);
+}
+
+fn finalized_contract() -> AuctionContractState {
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let (execute_state, _) = execute(create_ctx(owner, 102), started_contract_with_bid(2000));
+    let (claim_state, _) = claim(create_ctx(owner, 103), execute_state);
+    let (claim_state, _) = claim(create_ctx(bidder, 103), claim_state);
+    let (finalize_state, events) = finalize(create_ctx(owner, 104), claim_state);
+    assert_eq!(events.len(), 0);
+    finalize_state
+}
+
+#[test]
+pub fn test_finalize() {
+    let state = finalized_contract();
+    assert_eq!(state.status, AuctionStatus::Finalized {});
+    assert!(state.claim_map.is_empty());
+    assert!(state.bid_history.is_empty());
+    assert!(state.participants.is_empty());
+}
+
+#[test]
+#[should_panic(
+    expected = "[67] Tried to finalize the auction before every claim and balance was paid out"
+)]
+pub fn test_finalize_with_outstanding_claims() {
+    let owner = get_owner_address();
+    let (execute_state, _) = execute(create_ctx(owner, 102), started_contract_with_bid(2000));
+    finalize(create_ctx(owner, 103), execute_state);
+}
+
+#[test]
+#[should_panic(
+    expected = "[30] The action is only allowed after the auction has ended, failed or been cancelled"
+)]
+pub fn test_finalize_while_bidding() {
+    let state = started_contract_with_bid(2000);
+    finalize(create_ctx(get_owner_address(), 6), state);
+}
+
+#[test]
+#[should_panic(expected = "[68] The auction has been finalized")]
+pub fn test_deposit_after_finalize() {
+    deposit(
+        create_ctx(get_bidder_address(), 105),
+        finalized_contract(),
+        500,
+    );
+}
+
+fn all_pay_contract_with_bids() -> AuctionContractState {
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.settlement_mode = ALL_PAY;
+    let (configured_state, _) = configure(create_ctx(owner, 2), init_state, config);
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        configured_state,
+    );
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_state,
+        Bid {
+            bidder,
+            amount: 2_000,
+        },
+    );
+    let (bid_state, _) = bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        bid_state,
+        Bid {
+            bidder: third_party,
+            amount: 2_500,
+        },
+    );
+    bid_state
+}
+
+#[test]
+pub fn test_all_pay_outbid_bid_not_refunded() {
+    let state = all_pay_contract_with_bids();
+    let bidder = get_bidder_address();
+    assert_eq!(state.claim_map.get(&bidder), None);
+    assert_eq!(state.all_pay_bids.get(&bidder), Some(&2_000));
+}
+
+#[test]
+pub fn test_execute_all_pay() {
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let (execute_state, _) = execute(create_ctx(owner, 55), all_pay_contract_with_bids());
+    assert_eq!(execute_state.status, AuctionStatus::Ended {});
+    assert_eq!(
+        *execute_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 4_500,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        *execute_state.claim_map.get(&third_party).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 50_000,
+        }
+    );
+    assert_eq!(execute_state.claim_map.get(&bidder), None);
+}
+
+#[test]
+pub fn test_cancel_all_pay_refunds_every_bid() {
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let (cancel_state, _) = cancel(
+        create_ctx(get_owner_address(), 7),
+        all_pay_contract_with_bids(),
+    );
+    assert_eq!(
+        *cancel_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 2_000,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        *cancel_state.claim_map.get(&third_party).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 2_500,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "[70] The action is not supported by all-pay auctions")]
+pub fn test_proxy_bid_all_pay() {
+    proxy_bid(
+        create_ctx(get_bidder_address(), 7),
+        all_pay_contract_with_bids(),
+        5_000,
+    );
+}
+
+fn started_penny_contract() -> AuctionContractState {
+    let owner = get_owner_address();
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.reserve_price = 0;
+    config.penny = Some(PennyConfig {
+        bid_fee: 50,
+        price_increment: 1,
+        extension_millis: 3_600_000,
+    });
+    let (configured_state, _) = configure(create_ctx(owner, 2), init_state, config);
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        configured_state,
+    );
+    started_state
+}
+
+#[test]
+pub fn test_penny_bid() {
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let (bid_state, events) = penny_bid(create_ctx(bidder, 5), started_penny_contract());
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_currency_token_address(), Shortname::from_u32(3))
+        .argument(bidder)
+        .argument(get_contract_address())
+        .argument(51u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x30))
+        .argument(Bid { bidder, amount: 1 })
+        .argument(51u128)
+        .done();
+    assert_eq!(events, vec![expected_event.build()]);
+    let (bid_state, _) = penny_bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        bid_state,
+        Bid { bidder, amount: 1 },
+        51,
+    );
+    assert_eq!(bid_state.highest_bidder, Bid { bidder, amount: 1 });
+    assert_eq!(
+        *bid_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 50,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+pub fn test_penny_bid_extends_end_time() {
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let (bid_state, _) = penny_bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_penny_contract(),
+        Bid { bidder, amount: 1 },
+        51,
+    );
+    assert_eq!(bid_state.end_time_millis, 52 * 3_600_000);
+    let (bid_state, _) = penny_bid_callback(
+        create_ctx(third_party, 51),
+        create_callback_ctx(true),
+        bid_state,
+        Bid {
+            bidder: third_party,
+            amount: 2,
+        },
+        52,
+    );
+    assert_eq!(bid_state.end_time_millis, 52 * 3_600_000);
+    let mut ctx = create_ctx(bidder, 51);
+    ctx.block_production_time += 1_800_000;
+    let (bid_state, _) = penny_bid_callback(
+        ctx,
+        create_callback_ctx(true),
+        bid_state,
+        Bid { bidder, amount: 3 },
+        53,
+    );
+    assert_eq!(bid_state.highest_bidder, Bid { bidder, amount: 3 });
+    assert_eq!(bid_state.end_time_millis, 52 * 3_600_000 + 1_800_000);
+    // The outbid price is refunded, the bid fees are not
+    assert_eq!(
+        *bid_state.claim_map.get(&third_party).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 2,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+pub fn test_penny_bid_callback_refunds_stale_price() {
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let (bid_state, _) = penny_bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_penny_contract(),
+        Bid { bidder, amount: 1 },
+        51,
+    );
+    let (bid_state, _) = penny_bid_callback(
+        create_ctx(third_party, 5),
+        create_callback_ctx(true),
+        bid_state,
+        Bid {
+            bidder: third_party,
+            amount: 1,
+        },
+        51,
+    );
+    assert_eq!(bid_state.highest_bidder, Bid { bidder, amount: 1 });
+    assert_eq!(
+        *bid_state.claim_map.get(&third_party).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 51,
+            tokens_for_sale: 0,
+        }
+    );
+    assert_eq!(
+        *bid_state.rejected_bids.get(&third_party).unwrap(),
+        RejectedBid {
+            amount: 51,
+            reason: RejectionReason::Outbid {},
+            timestamp_millis: 5 * 60 * 60 * 1000,
+        }
+    );
+}
+
+#[test]
+pub fn test_penny_bid_callback_refunds_short_payment() {
+    let bidder = get_bidder_address();
+    let (bid_state, _) = penny_bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_penny_contract(),
+        Bid { bidder, amount: 1 },
+        50,
+    );
+    assert_eq!(bid_state.bid_count, 0);
+    assert_eq!(
+        bid_state.rejected_bids.get(&bidder).unwrap().reason,
+        RejectionReason::Underpaid {}
+    );
+}
+
+#[test]
+pub fn test_strict_refund_of_stale_penny_bid() {
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.reserve_price = 0;
+    config.penny = Some(PennyConfig {
+        bid_fee: 50,
+        price_increment: 1,
+        extension_millis: 3_600_000,
+    });
+    config.strict_refunds = true;
+    let (configured_state, _) = configure(create_ctx(owner, 2), init_state, config);
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        configured_state,
+    );
+    let (bid_state, _) = penny_bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_state,
+        Bid { bidder, amount: 1 },
+        51,
+    );
+    let (refund_state, events) = penny_bid_callback(
+        create_ctx(third_party, 5),
+        create_callback_ctx(true),
+        bid_state,
+        Bid {
+            bidder: third_party,
+            amount: 1,
+        },
+        51,
+    );
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_currency_token_address(), Shortname::from_u32(1))
+        .argument(third_party)
+        .argument(51u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x13))
+        .argument(third_party)
+        .argument(TokenClaim {
+            tokens_for_bidding: 51,
+            tokens_for_sale: 0,
+        })
+        .done();
+    assert_eq!(events, vec![expected_event.build()]);
+    assert!(!refund_state.claim_map.contains_key(&third_party));
+}
+
+#[test]
+#[should_panic(expected = "[71] Bids on a penny auction can only be placed through penny_bid")]
+pub fn test_bid_on_penny_auction() {
+    bid(
+        create_ctx(get_bidder_address(), 5),
+        started_penny_contract(),
+        2_000,
+    );
+}
+
+#[test]
+#[should_panic(expected = "[72] Tried to place a penny bid outside a penny auction")]
+pub fn test_penny_bid_outside_penny_auction() {
+    let state = started_contract_with_bid(2000);
+    penny_bid(create_ctx(get_third_party_address(), 6), state);
+}
+
+fn started_sealed_bid_contract() -> AuctionContractState {
+    let owner = get_owner_address();
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.sealed_bid_deposit = Some(100);
+    let (configured_state, _) = configure(create_ctx(owner, 2), init_state, config);
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        configured_state,
+    );
+    started_state
+}
+
+fn committed_sealed_bid(
+    state: AuctionContractState,
+    bidder: Address,
+    amount: u128,
+) -> AuctionContractState {
+    let (commit_state, _) = commit_bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        state,
+        bidder,
+        reserve_commitment(amount, [7u8; 32]),
+    );
+    commit_state
+}
+
+#[test]
+pub fn test_commit_bid() {
+    let bidder = get_bidder_address();
+    let commitment = reserve_commitment(3_000, [7u8; 32]);
+    let (commit_state, events) = commit_bid(
+        create_ctx(bidder, 5),
+        started_sealed_bid_contract(),
+        commitment,
+    );
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_currency_token_address(), Shortname::from_u32(3))
+        .argument(bidder)
+        .argument(get_contract_address())
+        .argument(100u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x32))
+        .argument(bidder)
+        .argument(commitment)
+        .done();
+    assert_eq!(events, vec![expected_event.build()]);
+    let (commit_state, _) = commit_bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        commit_state,
+        bidder,
+        commitment,
+    );
+    assert_eq!(
+        commit_state.sealed_commitments.get(&bidder),
+        Some(&commitment)
+    );
+}
+
+#[test]
+pub fn test_commit_bid_twice() {
+    let bidder = get_bidder_address();
+    let state = committed_sealed_bid(started_sealed_bid_contract(), bidder, 3_000);
+    let state = committed_sealed_bid(state, bidder, 4_000);
+    assert_eq!(
+        state.sealed_commitments.get(&bidder),
+        Some(&reserve_commitment(3_000, [7u8; 32]))
+    );
+    assert_eq!(
+        state.rejected_bids.get(&bidder).unwrap().reason,
+        RejectionReason::AlreadyCommitted {}
+    );
+    assert_eq!(
+        *state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 100,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+pub fn test_strict_refund_of_outbid_reveal() {
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.sealed_bid_deposit = Some(100);
+    config.strict_refunds = true;
+    let (configured_state, _) = configure(create_ctx(owner, 2), init_state, config);
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        configured_state,
+    );
+    let state = committed_sealed_bid(started_state, bidder, 3_000);
+    let state = committed_sealed_bid(state, third_party, 2_500);
+    let (revealed_state, _) = reveal_bid_callback(
+        create_ctx(bidder, 53),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder,
+            amount: 3_000,
+        },
+    );
+    // The lower bid was revealed right before the higher bid, and arrives after it
+    let (reveal_state, events) = reveal_bid_callback(
+        create_ctx(third_party, 53),
+        create_callback_ctx(true),
+        revealed_state,
+        Bid {
+            bidder: third_party,
+            amount: 2_500,
+        },
+    );
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_currency_token_address(), Shortname::from_u32(1))
+        .argument(third_party)
+        .argument(2_500u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x13))
+        .argument(third_party)
+        .argument(TokenClaim {
+            tokens_for_bidding: 2_500,
+            tokens_for_sale: 0,
+        })
+        .done();
+    assert_eq!(events, vec![expected_event.build()]);
+    assert_eq!(
+        reveal_state.rejected_bids.get(&third_party).unwrap().reason,
+        RejectionReason::Outbid {}
+    );
+}
+
+#[test]
+pub fn test_reveal_bid() {
+    let bidder = get_bidder_address();
+    let state = committed_sealed_bid(started_sealed_bid_contract(), bidder, 3_000);
+    let (reveal_state, events) = reveal_bid(create_ctx(bidder, 53), state, 3_000, [7u8; 32]);
+    assert_eq!(reveal_state.status, AuctionStatus::Revealing {});
+    assert_eq!(events.len(), 1);
+    let (reveal_state, _) = reveal_bid_callback(
+        create_ctx(bidder, 53),
+        create_callback_ctx(true),
+        reveal_state,
+        Bid {
+            bidder,
+            amount: 3_000,
+        },
+    );
+    assert_eq!(
+        reveal_state.highest_bidder,
+        Bid {
+            bidder,
+            amount: 3_000,
+        }
+    );
+    assert!(reveal_state.sealed_commitments.is_empty());
+    assert_eq!(
+        *reveal_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 100,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+pub fn test_reveal_losing_bid_refunds_deposit() {
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let state = committed_sealed_bid(started_sealed_bid_contract(), bidder, 3_000);
+    let mut state = committed_sealed_bid(state, third_party, 2_500);
+    state.highest_bidder = Bid {
+        bidder,
+        amount: 3_000,
+    };
+    let (reveal_state, events) = reveal_bid(create_ctx(third_party, 53), state, 2_500, [7u8; 32]);
+    assert_eq!(events.len(), 0);
+    assert!(!reveal_state.sealed_commitments.contains_key(&third_party));
+    assert_eq!(
+        *reveal_state.claim_map.get(&third_party).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 100,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "[79] The revealed bid does not match the commitment")]
+pub fn test_reveal_bid_mismatch() {
+    let bidder = get_bidder_address();
+    let state = committed_sealed_bid(started_sealed_bid_contract(), bidder, 3_000);
+    reveal_bid(create_ctx(bidder, 53), state, 3_001, [7u8; 32]);
+}
+
+#[test]
+#[should_panic(
+    expected = "[77] The action is only allowed in the reveal window after auction end block time"
+)]
+pub fn test_reveal_bid_before_end() {
+    let bidder = get_bidder_address();
+    let state = committed_sealed_bid(started_sealed_bid_contract(), bidder, 3_000);
+    reveal_bid(create_ctx(bidder, 6), state, 3_000, [7u8; 32]);
+}
+
+#[test]
+#[should_panic(
+    expected = "[80] Tried to execute the auction before the reveal window of the sealed bids closed"
+)]
+pub fn test_execute_before_bids_revealed() {
+    let state = started_sealed_bid_contract();
+    execute(create_ctx(get_owner_address(), 53), state);
+}
+
+#[test]
+pub fn test_execute_forfeits_unrevealed_deposits() {
+    let owner = get_owner_address();
+    let state = committed_sealed_bid(started_sealed_bid_contract(), get_bidder_address(), 3_000);
+    let (execute_state, _) = execute(create_ctx(owner, 77), state);
+    assert_eq!(execute_state.status, AuctionStatus::ReserveNotMet {});
+    assert!(execute_state.sealed_commitments.is_empty());
+    assert_eq!(
+        *execute_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 100,
+            tokens_for_sale: 50_000,
+        }
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "[74] Bids on a sealed bid auction can only be committed through commit_bid"
+)]
+pub fn test_bid_on_sealed_bid_auction() {
+    bid(
+        create_ctx(get_bidder_address(), 5),
+        started_sealed_bid_contract(),
+        2_000,
+    );
+}
+
+fn get_stablecoin_address() -> Address {
+    Address {
+        address_type: AddressType::PublicContract,
+        identifier: [0u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4],
+    }
+}
+
+fn get_rate_oracle_address() -> Address {
+    Address {
+        address_type: AddressType::PublicContract,
+        identifier: [0u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 5],
+    }
+}
+
+fn started_multi_currency_contract(rate: ExchangeRate) -> AuctionContractState {
+    let owner = get_owner_address();
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.accepted_currencies = vec![AcceptedCurrency {
+        token: get_stablecoin_address(),
+        rate,
+    }];
+    let (configured_state, _) = configure(create_ctx(owner, 2), init_state, config);
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        configured_state,
+    );
+    started_state
+}
+
+/// A stablecoin worth two bidding tokens per unit.
+fn fixed_rate() -> ExchangeRate {
+    ExchangeRate::Fixed {
+        rate: 2 * RATE_SCALE,
+    }
+}
+
+fn currency_bid_placed(
+    state: AuctionContractState,
+    bidder: Address,
+    amount: u128,
+) -> AuctionContractState {
+    let (bid_state, _) = bid_in_currency_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        state,
+        bidder,
+        get_stablecoin_address(),
+        amount,
+    );
+    bid_state
+}
+
+#[test]
+pub fn test_bid_in_currency() {
+    let bidder = get_bidder_address();
+    let stablecoin = get_stablecoin_address();
+    let state = started_multi_currency_contract(fixed_rate());
+    let (bid_state, events) = bid_in_currency(create_ctx(bidder, 5), state, stablecoin, 1_500);
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(stablecoin, Shortname::from_u32(3))
+        .argument(bidder)
+        .argument(get_contract_address())
+        .argument(1_500u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x36))
+        .argument(bidder)
+        .argument(stablecoin)
+        .argument(1_500u128)
+        .done();
+    assert_eq!(events, vec![expected_event.build()]);
+    let bid_state = currency_bid_placed(bid_state, bidder, 1_500);
+    assert_eq!(
+        bid_state.highest_bidder,
+        Bid {
+            bidder,
+            amount: 3_000,
+        }
+    );
+    assert_eq!(
+        bid_state.highest_bid_currency,
+        Some(CurrencyEscrow {
+            token: stablecoin,
+            amount: 1_500,
+        })
+    );
+}
+
+#[test]
+pub fn test_outbid_currency_bid_refunded_in_currency() {
+    let bidder = get_bidder_address();
+    let third_party = get_third_party_address();
+    let state = currency_bid_placed(started_multi_currency_contract(fixed_rate()), bidder, 1_500);
+    let (outbid_state, _) = bid_callback(
+        create_ctx(third_party, 6),
+        create_callback_ctx(true),
+        state,
+        Bid {
+            bidder: third_party,
+            amount: 3_200,
+        },
+    );
+    assert_eq!(outbid_state.highest_bidder.bidder, third_party);
+    assert_eq!(outbid_state.highest_bid_currency, None);
+    assert_eq!(
+        outbid_state
+            .currency_claims
+            .get(&bidder)
+            .unwrap()
+            .get(&get_stablecoin_address()),
+        Some(&1_500)
+    );
+    assert!(!outbid_state.claim_map.contains_key(&bidder));
+}
+
+#[test]
+pub fn test_execute_currency_bid() {
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let stablecoin = get_stablecoin_address();
+    let state = currency_bid_placed(started_multi_currency_contract(fixed_rate()), bidder, 1_500);
+    let (execute_state, _) = execute(create_ctx(owner, 53), state);
+    assert_eq!(
+        execute_state
+            .currency_claims
+            .get(&owner)
+            .unwrap()
+            .get(&stablecoin),
+        Some(&1_500)
+    );
+    assert_eq!(
+        *execute_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 50_000,
+        }
+    );
+    let (claim_state, events) = claim_currency(create_ctx(owner, 54), execute_state, stablecoin);
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(stablecoin, Shortname::from_u32(1))
+        .argument(owner)
+        .argument(1_500u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x38))
+        .argument(owner)
+        .argument(stablecoin)
+        .argument(1_500u128)
+        .done();
+    assert_eq!(events, vec![expected_event.build()]);
+    assert!(claim_state.currency_claims.is_empty());
+    let (restored_state, _) = claim_currency_callback(
+        create_ctx(owner, 54),
+        create_callback_ctx(false),
+        claim_state,
+        owner,
+        stablecoin,
+        1_500,
+    );
+    assert_eq!(
+        restored_state
+            .currency_claims
+            .get(&owner)
+            .unwrap()
+            .get(&stablecoin),
+        Some(&1_500)
+    );
+}
+
+#[test]
+pub fn test_refresh_oracle_rate() {
+    let stablecoin = get_stablecoin_address();
+    let state = started_multi_currency_contract(ExchangeRate::Oracle {
+        address: get_rate_oracle_address(),
+        rate_shortname: 0x07,
+    });
+    let (refresh_state, events) =
+        refresh_rate(create_ctx(get_bidder_address(), 4), state, stablecoin);
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_rate_oracle_address(), Shortname::from_u32(0x07))
+        .argument(stablecoin)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x3a))
+        .argument(stablecoin)
+        .done();
+    assert_eq!(events, vec![expected_event.build()]);
+    let oracle_ctx = CallbackContext {
+        success: true,
+        results: vec![ExecutionResult {
+            succeeded: true,
+            return_data: (RATE_SCALE / 2).to_be_bytes().to_vec(),
+        }],
+    };
+    let (rated_state, _) = refresh_rate_callback(
+        create_ctx(get_bidder_address(), 4),
+        oracle_ctx,
+        refresh_state,
+        stablecoin,
+    );
+    let bid_state = currency_bid_placed(rated_state, get_bidder_address(), 6_000);
+    assert_eq!(bid_state.highest_bidder.amount, 3_000);
+}
+
+/// An auction whose reserve price is 750 reference units, converted by the rate oracle.
+fn oracle_reserve_contract() -> AuctionContractState {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.reserve_oracle = Some(ReserveOracle {
+        address: get_rate_oracle_address(),
+        rate_shortname: 0x07,
+        reference_reserve: 750,
+        reference_increment: None,
+    });
+    let (configured_state, _) = configure(create_ctx(get_owner_address(), 1), init_state, config);
+    configured_state
+}
+
+#[test]
+pub fn test_start_fetches_reserve_rate() {
+    let (start_state, events) = start(
+        create_ctx(get_owner_address(), 2),
+        oracle_reserve_contract(),
+    );
+    assert_eq!(events.len(), 2);
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_rate_oracle_address(), Shortname::from_u32(0x07))
+        .argument(get_currency_token_address())
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x4f))
+        .done();
+    assert_eq!(events[1], expected_event.build());
+    let (rated_state, _) = refresh_reserve_callback(
+        create_ctx(get_owner_address(), 2),
+        callback_context_returning(2 * RATE_SCALE),
+        start_state,
+    );
+    assert_eq!(rated_state.reserve_price, 1500);
+}
+
+#[test]
+pub fn test_refresh_reserve_callback_after_bid_keeps_reserve() {
+    let mut state = started_contract_with_bid(2000);
+    state.reserve_oracle = oracle_reserve_contract().reserve_oracle;
+    let (rated_state, _) = refresh_reserve_callback(
+        create_ctx(get_owner_address(), 6),
+        callback_context_returning(2 * RATE_SCALE),
+        state,
+    );
+    assert_eq!(rated_state.reserve_price, 1000);
+}
+
+#[test]
+pub fn test_refresh_reference_increment_after_bid() {
+    let mut state = started_contract_with_bid(2000);
+    state.reserve_oracle = Some(ReserveOracle {
+        address: get_rate_oracle_address(),
+        rate_shortname: 0x07,
+        reference_reserve: 750,
+        reference_increment: Some(300),
+    });
+    let (refreshed_state, events) =
+        refresh_reserve(create_ctx(get_third_party_address(), 6), state);
+    assert_eq!(events.len(), 1);
+    let (rated_state, _) = refresh_reserve_callback(
+        create_ctx(get_third_party_address(), 6),
+        callback_context_returning(2 * RATE_SCALE),
+        refreshed_state,
+    );
+    assert_eq!(rated_state.reference_rate, Some(2 * RATE_SCALE));
+    assert_eq!(rated_state.reserve_price, 1000);
+    assert_eq!(rated_state.min_increment, 600);
+    let (failed_state, _) = refresh_reserve_callback(
+        create_ctx(get_third_party_address(), 7),
+        create_callback_ctx(false),
+        rated_state,
+    );
+    assert_eq!(failed_state.min_increment, 600);
+}
+
+#[test]
+#[should_panic(
+    expected = "[121] Tried to refresh the reserve price without an oracle or after a bid was placed"
+)]
+pub fn test_refresh_reserve_after_bid() {
+    let mut state = started_contract_with_bid(2000);
+    state.reserve_oracle = oracle_reserve_contract().reserve_oracle;
+    refresh_reserve(create_ctx(get_bidder_address(), 6), state);
+}
+
+#[test]
+#[should_panic(
+    expected = "[120] Tried to configure a reserve oracle for an auction without a public reserve"
+)]
+pub fn test_initialize_reserve_oracle_with_commitment() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.reserve_commitment = Some([7u8; 32]);
+    config.reserve_oracle = oracle_reserve_contract().reserve_oracle;
+    configure(create_ctx(get_owner_address(), 1), init_state, config);
+}
+
+#[test]
+#[should_panic(
+    expected = "[83] The exchange rate of the currency has not been fetched from its oracle"
+)]
+pub fn test_bid_in_currency_without_oracle_rate() {
+    let state = started_multi_currency_contract(ExchangeRate::Oracle {
+        address: get_rate_oracle_address(),
+        rate_shortname: 0x07,
+    });
+    bid_in_currency(
+        create_ctx(get_bidder_address(), 5),
+        state,
+        get_stablecoin_address(),
+        6_000,
+    );
+}
+
+#[test]
+#[should_panic(expected = "[82] The currency is not accepted for bidding")]
+pub fn test_bid_in_unaccepted_currency() {
+    bid_in_currency(
+        create_ctx(get_bidder_address(), 5),
+        started_multi_currency_contract(fixed_rate()),
+        get_commodity_token_address(),
+        1_500,
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "[81] Tried to accept bidding currencies that are invalid for the auction"
+)]
+pub fn test_accept_bidding_token_as_currency() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.accepted_currencies = vec![AcceptedCurrency {
+        token: get_currency_token_address(),
+        rate: fixed_rate(),
+    }];
+    configure(create_ctx(get_owner_address(), 2), init_state, config);
+}
+
+fn executed_delivery_contract() -> AuctionContractState {
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.delivery_timeout_millis = Some(24 * 3_600_000);
+    config.arbiter = Some(get_arbiter_address());
+    let (configured_state, _) = configure(create_ctx(owner, 2), init_state, config);
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        configured_state,
+    );
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_state,
+        Bid {
+            bidder,
+            amount: 3_000,
+        },
+    );
+    let (execute_state, _) = execute(create_ctx(owner, 53), bid_state);
+    execute_state
+}
+
+#[test]
+pub fn test_execute_holds_payment_for_delivery() {
+    let state = executed_delivery_contract();
+    assert_eq!(state.status, AuctionStatus::Ended {});
+    assert_eq!(state.held_proceeds, 3_000);
+    assert_eq!(
+        state
+            .claim_map
+            .get(&get_owner_address())
+            .map_or(0, |claim| claim.tokens_for_bidding),
+        0
+    );
+    assert_eq!(
+        *state.claim_map.get(&get_bidder_address()).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 50_000,
+        }
+    );
+}
+
+#[test]
+pub fn test_confirm_receipt() {
+    let (confirm_state, _) = confirm_receipt(
+        create_ctx(get_bidder_address(), 60),
+        executed_delivery_contract(),
+    );
+    assert_eq!(confirm_state.held_proceeds, 0);
+    assert_eq!(
+        *confirm_state.claim_map.get(&get_owner_address()).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 3_000,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "[87] Only the winner of the auction can confirm the receipt of the goods"
+)]
+pub fn test_confirm_receipt_not_winner() {
+    confirm_receipt(
+        create_ctx(get_owner_address(), 60),
+        executed_delivery_contract(),
+    );
+}
+
+#[test]
+pub fn test_release_payment_after_delivery_timeout() {
+    let (release_state, _) = release_payment(
+        create_ctx(get_owner_address(), 76),
+        executed_delivery_contract(),
+    );
+    assert_eq!(
+        *release_state.claim_map.get(&get_owner_address()).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 3_000,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "[88] Tried to release the held payment before the delivery timeout")]
+pub fn test_release_payment_before_delivery_timeout() {
+    release_payment(
+        create_ctx(get_owner_address(), 75),
+        executed_delivery_contract(),
+    );
+}
+
+fn get_arbiter_address() -> Address {
+    Address {
+        address_type: AddressType::Account,
+        identifier: [0u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 6],
+    }
+}
+
+#[test]
+pub fn test_resolve_dispute() {
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let (dispute_state, _) = open_dispute(create_ctx(bidder, 60), executed_delivery_contract());
+    assert!(dispute_state.disputed);
+    let (resolved_state, _) =
+        resolve_dispute(create_ctx(get_arbiter_address(), 80), dispute_state, 1_000);
+    assert!(!resolved_state.disputed);
+    assert_eq!(resolved_state.held_proceeds, 0);
+    assert_eq!(
+        *resolved_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 1_000,
+            tokens_for_sale: 50_000,
+        }
+    );
+    assert_eq!(
+        *resolved_state.claim_map.get(&owner).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 2_000,
+            tokens_for_sale: 0,
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "[92] The held payment is in dispute")]
+pub fn test_release_payment_in_dispute() {
+    let (dispute_state, _) = open_dispute(
+        create_ctx(get_owner_address(), 60),
+        executed_delivery_contract(),
+    );
+    release_payment(create_ctx(get_owner_address(), 80), dispute_state);
+}
+
+#[test]
+#[should_panic(expected = "[91] Disputes can only be opened before the delivery timeout")]
+pub fn test_open_dispute_after_delivery_timeout() {
+    open_dispute(
+        create_ctx(get_bidder_address(), 76),
+        executed_delivery_contract(),
+    );
+}
+
+#[test]
+#[should_panic(expected = "[93] Only the arbiter can resolve a dispute")]
+pub fn test_resolve_dispute_not_arbiter() {
+    let (dispute_state, _) = open_dispute(
+        create_ctx(get_bidder_address(), 60),
+        executed_delivery_contract(),
+    );
+    resolve_dispute(create_ctx(get_bidder_address(), 61), dispute_state, 3_000);
+}
+
+#[test]
+#[should_panic(expected = "[95] The award exceeds the held payment")]
+pub fn test_resolve_dispute_award_too_high() {
+    let (dispute_state, _) = open_dispute(
+        create_ctx(get_bidder_address(), 60),
+        executed_delivery_contract(),
+    );
+    resolve_dispute(create_ctx(get_arbiter_address(), 61), dispute_state, 3_001);
+}
+
+fn started_contract_with_allowance_check() -> AuctionContractState {
+    let owner = get_owner_address();
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.bidding_allowance_shortname = Some(0x15);
+    let (configured_state, _) = configure(create_ctx(owner, 2), init_state, config);
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        configured_state,
+    );
+    started_state
+}
+
+fn allowance_callback_ctx(allowance: u128) -> CallbackContext {
+    CallbackContext {
+        success: true,
+        results: vec![ExecutionResult {
+            succeeded: true,
+            return_data: allowance.to_be_bytes().to_vec(),
+        }],
+    }
+}
+
+#[test]
+pub fn test_bid_checks_allowance() {
+    let bidder = get_bidder_address();
+    let (bid_state, events) = bid(
+        create_ctx(bidder, 5),
+        started_contract_with_allowance_check(),
+        3_000,
+    );
+    let bid_of = || Bid {
+        bidder,
+        amount: 3_000,
+    };
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_currency_token_address(), Shortname::from_u32(0x15))
+        .argument(bidder)
+        .argument(get_contract_address())
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x3f))
+        .argument(bid_of())
+        .argument(None::<u128>)
+        .done();
+    assert_eq!(events, vec![expected_event.build()]);
+    let (_, events) = bid_allowance_callback(
+        create_ctx(bidder, 5),
+        allowance_callback_ctx(3_000),
+        bid_state,
+        bid_of(),
+        None,
+    );
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_currency_token_address(), Shortname::from_u32(3))
+        .argument(bidder)
+        .argument(get_contract_address())
+        .argument(3_000u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x04))
+        .argument(bid_of())
+        .done();
+    assert_eq!(events, vec![expected_event.build()]);
+}
+
+#[test]
+#[should_panic(
+    expected = "[97] The bidding token allowance or balance of the bidder does not cover the bid"
+)]
+pub fn test_bid_exceeding_allowance() {
+    let bidder = get_bidder_address();
+    bid_allowance_callback(
+        create_ctx(bidder, 5),
+        allowance_callback_ctx(2_999),
+        started_contract_with_allowance_check(),
+        Bid {
+            bidder,
+            amount: 3_000,
+        },
+        None,
+    );
+}
+
+#[test]
+pub fn test_strict_refund_of_invalid_bid() {
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.strict_refunds = true;
+    let (configured_state, _) = configure(create_ctx(owner, 2), init_state, config);
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        configured_state,
+    );
+    // The bid arrives after the end time
+    let (refund_state, events) = bid_callback(
+        create_ctx(bidder, 52),
+        create_callback_ctx(true),
+        started_state,
+        Bid {
+            bidder,
+            amount: 3_000,
+        },
+    );
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_currency_token_address(), Shortname::from_u32(1))
+        .argument(bidder)
+        .argument(3_000u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x13))
+        .argument(bidder)
+        .argument(TokenClaim {
+            tokens_for_bidding: 3_000,
+            tokens_for_sale: 0,
+        })
+        .done();
+    assert_eq!(
+        events,
+        vec![
+            expected_event.build(),
+            bid_outcome_event(BidOutcome::Refunded { amount: 3_000 }),
+        ]
+    );
+    assert!(!refund_state.claim_map.contains_key(&bidder));
+}
+
+fn configured_semi_fungible_contract() -> AuctionContractState {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.sale_token_id = Some(42);
+    config.semi_fungible_sale = true;
+    let (configured_state, _) = configure(create_ctx(get_owner_address(), 2), init_state, config);
+    configured_state
+}
+
+#[test]
+pub fn test_start_semi_fungible() {
+    let owner = get_owner_address();
+    let (_, events) = start(create_ctx(owner, 3), configured_semi_fungible_contract());
+    let mut expected = EventGroup::builder();
+    expected
+        .call(get_commodity_token_address(), Shortname::from_u32(3))
+        .argument(owner)
+        .argument(get_contract_address())
+        .argument(42u128)
+        .argument(50_000u128)
+        .done();
+    expected
+        .with_callback(ShortnameCallback::from_u32(2))
+        .done();
+    assert_eq!(*events.first().unwrap(), expected.build());
+}
+
+#[test]
+pub fn test_claim_semi_fungible() {
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        configured_semi_fungible_contract(),
+    );
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_state,
+        Bid {
+            bidder,
+            amount: 3_000,
+        },
+    );
+    let (execute_state, _) = execute(create_ctx(owner, 53), bid_state);
+    let (_, claim_events) = claim(create_ctx(bidder, 54), execute_state);
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_commodity_token_address(), Shortname::from_u32(1))
+        .argument(bidder)
+        .argument(42u128)
+        .argument(50_000u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x13))
+        .argument(bidder)
+        .argument(0u128)
+        .argument(50_000u128)
+        .done();
+    assert_eq!(*claim_events.first().unwrap(), expected_event.build());
+}
+
+#[test]
+#[should_panic(expected = "[98] Tried to sell a semi-fungible token without a token id")]
+pub fn test_semi_fungible_without_token_id() {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.semi_fungible_sale = true;
+    configure(create_ctx(get_owner_address(), 2), init_state, config);
+}
+
+fn get_bundle_nft_address() -> Address {
+    Address {
+        address_type: AddressType::PublicContract,
+        identifier: [0u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 7],
+    }
+}
+
+fn configured_bundle_contract() -> AuctionContractState {
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.bundle = vec![BundleItem {
+        token: get_bundle_nft_address(),
+        amount_or_id: 9,
+    }];
+    let (configured_state, _) = configure(create_ctx(get_owner_address(), 2), init_state, config);
+    configured_state
+}
+
+#[test]
+pub fn test_start_bundle() {
+    let owner = get_owner_address();
+    let (_, events) = start(create_ctx(owner, 3), configured_bundle_contract());
+    let mut expected = EventGroup::builder();
+    expected
+        .call(get_commodity_token_address(), Shortname::from_u32(3))
+        .argument(owner)
+        .argument(get_contract_address())
+        .argument(50_000u128)
+        .done();
+    expected
+        .call(get_bundle_nft_address(), Shortname::from_u32(3))
+        .argument(owner)
+        .argument(get_contract_address())
+        .argument(9u128)
+        .done();
+    expected
+        .with_callback(ShortnameCallback::from_u32(2))
+        .done();
+    assert_eq!(*events.first().unwrap(), expected.build());
+}
+
+#[test]
+pub fn test_claim_bundle() {
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        configured_bundle_contract(),
+    );
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_state,
+        Bid {
+            bidder,
+            amount: 3_000,
+        },
+    );
+    let (execute_state, _) = execute(create_ctx(owner, 53), bid_state);
+    assert_eq!(execute_state.bundle_claimant, Some(bidder));
+    let (claim_state, claim_events) = claim(create_ctx(bidder, 54), execute_state);
+    let mut expected_event = EventGroup::builder();
+    expected_event
+        .call(get_bundle_nft_address(), Shortname::from_u32(1))
+        .argument(bidder)
+        .argument(9u128)
+        .done();
+    expected_event
+        .with_callback(ShortnameCallback::from_u32(0x40))
+        .argument(bidder)
+        .done();
+    assert_eq!(claim_events.len(), 2);
+    assert_eq!(claim_events[1], expected_event.build());
+    assert_eq!(claim_state.bundle_claimant, None);
+    let (restored_state, _) = claim_bundle_callback(
+        create_ctx(bidder, 54),
+        create_callback_ctx(false),
+        claim_state,
+        bidder,
+    );
+    assert_eq!(restored_state.bundle_claimant, Some(bidder));
+}
+
+#[test]
+pub fn test_cancel_returns_bundle_to_owner() {
+    let owner = get_owner_address();
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        configured_bundle_contract(),
+    );
+    let (cancel_state, _) = cancel(create_ctx(owner, 4), started_state);
+    assert_eq!(cancel_state.bundle_claimant, Some(owner));
+}
+
+fn started_sequential_contract() -> AuctionContractState {
+    let owner = get_owner_address();
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.rounds = vec![RoundConfig {
+        token_amount_for_sale: 20_000,
+        reserve_price: 1_000,
+        duration_millis: 10 * 3_600_000,
+    }];
+    let (configured_state, _) = configure(create_ctx(owner, 2), init_state, config);
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        configured_state,
+    );
+    started_state
+}
+
+#[test]
+pub fn test_start_escrows_every_round() {
+    let owner = get_owner_address();
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.rounds = vec![RoundConfig {
+        token_amount_for_sale: 20_000,
+        reserve_price: 1_000,
+        duration_millis: 10 * 3_600_000,
+    }];
+    let (configured_state, _) = configure(create_ctx(owner, 2), init_state, config);
+    let (_, events) = start(create_ctx(owner, 3), configured_state);
+    let mut expected = EventGroup::builder();
+    expected
+        .call(get_commodity_token_address(), Shortname::from_u32(3))
+        .argument(owner)
+        .argument(get_contract_address())
+        .argument(70_000u128)
+        .done();
+    expected
+        .with_callback(ShortnameCallback::from_u32(2))
+        .done();
+    assert_eq!(*events.first().unwrap(), expected.build());
+}
+
+#[test]
+pub fn test_execute_starts_next_round() {
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_sequential_contract(),
+        Bid {
+            bidder,
+            amount: 3_000,
+        },
+    );
+    let (round_state, _) = execute(create_ctx(owner, 53), bid_state);
+    assert_eq!(round_state.status, AuctionStatus::Bidding {});
+    assert_eq!(round_state.round_index, 1);
+    assert_eq!(round_state.token_amount_for_sale, 20_000);
+    assert_eq!(round_state.reserve_price, 1_000);
+    assert_eq!(round_state.end_time_millis, 63 * 3_600_000);
+    assert_eq!(round_state.highest_bidder.amount, 0);
+    assert_eq!(
+        round_state.round_results,
+        vec![RoundResult {
+            round: 0,
+            winner: bidder,
+            price: 3_000,
+            units: 50_000,
+        }]
+    );
+    assert_eq!(
+        *round_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 50_000,
+        }
+    );
+    let (final_state, _) = execute(create_ctx(owner, 63), round_state);
+    assert_eq!(final_state.status, AuctionStatus::ReserveNotMet {});
+    assert_eq!(final_state.round_results.len(), 1);
+    assert_eq!(
+        final_state.claim_map.get(&owner).unwrap().tokens_for_sale,
+        20_000
+    );
+}
+
+#[test]
+pub fn test_round_without_bids_carries_tranche_over() {
+    let (round_state, _) = execute(
+        create_ctx(get_owner_address(), 53),
+        started_sequential_contract(),
+    );
+    assert_eq!(round_state.token_amount_for_sale, 70_000);
+    assert!(round_state.round_results.is_empty());
+}
+
+#[test]
+pub fn test_cancel_returns_upcoming_rounds() {
+    let owner = get_owner_address();
+    let (cancel_state, _) = cancel(create_ctx(owner, 4), started_sequential_contract());
+    assert_eq!(
+        cancel_state.claim_map.get(&owner).unwrap().tokens_for_sale,
+        70_000
+    );
+}
+
+fn started_recurring_contract() -> AuctionContractState {
+    let owner = get_owner_address();
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.recurrence = Some(RecurrenceConfig {
+        interval_millis: 168 * 3_600_000,
+        max_repeats: 2,
+    });
+    let (configured_state, _) = configure(create_ctx(owner, 2), init_state, config);
+    let (started_state, _) = start_with_escrow(
+        create_ctx(owner, 3),
+        create_callback_ctx(true),
+        configured_state,
+    );
+    started_state
+}
+
+#[test]
+pub fn test_start_escrows_recurring_inventory() {
+    let owner = get_owner_address();
+    let (init_state, _) = initialize_contract();
+    let mut config = english_auction_config();
+    config.recurrence = Some(RecurrenceConfig {
+        interval_millis: 168 * 3_600_000,
+        max_repeats: 2,
+    });
+    let (configured_state, _) = configure(create_ctx(owner, 2), init_state, config);
+    assert_eq!(configured_state.total_tokens_for_sale(), 150_000);
+}
+
+#[test]
+pub fn test_execute_relists_recurring_auction() {
+    let owner = get_owner_address();
+    let bidder = get_bidder_address();
+    let (bid_state, _) = bid_callback(
+        create_ctx(bidder, 5),
+        create_callback_ctx(true),
+        started_recurring_contract(),
+        Bid {
+            bidder,
+            amount: 3_000,
+        },
+    );
+    let (repeat_state, _) = execute(create_ctx(owner, 53), bid_state);
+    assert_eq!(repeat_state.status, AuctionStatus::BiddingScheduled {});
+    assert_eq!(repeat_state.repeat_count, 1);
+    assert_eq!(repeat_state.start_time_millis, 170 * 3_600_000);
+    assert_eq!(repeat_state.end_time_millis, 220 * 3_600_000);
+    assert_eq!(repeat_state.highest_bidder.amount, 0);
+    assert_eq!(
+        *repeat_state.claim_map.get(&bidder).unwrap(),
+        TokenClaim {
+            tokens_for_bidding: 0,
+            tokens_for_sale: 50_000,
+        }
+    );
+    let (cancel_state, _) = cancel(create_ctx(owner, 171), repeat_state);
+    assert_eq!(
+        cancel_state.claim_map.get(&owner).unwrap().tokens_for_sale,
+        100_000
+    );
+}
+
+#[test]
+pub fn test_recurring_auction_ends_after_max_repeats() {
+    let owner = get_owner_address();
+    let mut state = started_recurring_contract();
+    state.repeat_count = 2;
+    let (execute_state, _) = execute(create_ctx(owner, 53), state);
+    assert_eq!(execute_state.status, AuctionStatus::ReserveNotMet {});
+}
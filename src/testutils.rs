@@ -0,0 +1,241 @@
+//! Builders for contexts and auction states, for writing tests of the auction contract without
+//! copying the boilerplate of the contract's own tests. Enabled by the `testutils` feature.
+
+use pbc_contract_common::address::Address;
+use pbc_contract_common::context::{CallbackContext, ContractContext, ExecutionResult};
+use pbc_contract_common::events::EventGroup;
+use pbc_contract_common::Hash;
+use pbc_traits::WriteRPC;
+
+use crate::{
+    new_auction, start, start_callback, AuctionConfig, AuctionContractState, AuctionFormat,
+    TiePolicy, ENGLISH, FIRST_PRICE,
+};
+
+const TRANSACTION_HASH: Hash = [1u8; 32];
+
+/// Builds the `ContractContext` of a transaction from `sender` at a block production time,
+/// which can be advanced between transactions.
+#[derive(Clone, Copy)]
+pub struct ContextBuilder {
+    contract_address: Address,
+    sender: Address,
+    block_production_time: i64,
+}
+
+impl ContextBuilder {
+    /// A context of the contract at `contract_address` for transactions from `sender` at
+    /// block production time 0.
+    pub fn new(contract_address: Address, sender: Address) -> Self {
+        ContextBuilder {
+            contract_address,
+            sender,
+            block_production_time: 0,
+        }
+    }
+
+    /// Send the transaction from `sender`.
+    pub fn sender(self, sender: Address) -> Self {
+        ContextBuilder { sender, ..self }
+    }
+
+    /// Produce the block at `block_production_time`, in milliseconds.
+    pub fn at_millis(self, block_production_time: i64) -> Self {
+        ContextBuilder {
+            block_production_time,
+            ..self
+        }
+    }
+
+    /// Advance the block production time by `millis`.
+    pub fn advance_millis(self, millis: i64) -> Self {
+        self.at_millis(self.block_production_time + millis)
+    }
+
+    /// The context of the transaction. The contract only reads the block production time, so
+    /// the block time is left at 0.
+    pub fn build(&self) -> ContractContext {
+        ContractContext {
+            contract_address: self.contract_address,
+            sender: self.sender,
+            block_time: 0,
+            block_production_time: self.block_production_time,
+            current_transaction: TRANSACTION_HASH,
+            original_transaction: TRANSACTION_HASH,
+        }
+    }
+}
+
+/// The `CallbackContext` of event groups whose interactions all succeeded or all failed,
+/// without return data.
+pub fn callback_context(success: bool, interactions: usize) -> CallbackContext {
+    CallbackContext {
+        success,
+        results: (0..interactions)
+            .map(|_| ExecutionResult {
+                succeeded: success,
+                return_data: vec![],
+            })
+            .collect(),
+    }
+}
+
+/// The `CallbackContext` of a successful event group whose single interaction returned `data`,
+/// such as a balance or an exchange rate.
+pub fn callback_context_returning<T: WriteRPC>(data: T) -> CallbackContext {
+    let mut return_data = vec![];
+    data.rpc_write_to(&mut return_data)
+        .expect("Writing to a vector does not fail");
+    CallbackContext {
+        success: true,
+        results: vec![ExecutionResult {
+            succeeded: true,
+            return_data,
+        }],
+    }
+}
+
+/// Builds the state of a plain first price English auction, which the setters adjust.
+pub struct AuctionBuilder {
+    owner: Address,
+    config: AuctionConfig,
+}
+
+impl AuctionBuilder {
+    /// An English auction by `owner` of `token_amount_for_sale` units of `token_for_sale`,
+    /// paid in `token_for_bidding`, without a reserve price, running for a day.
+    pub fn new(
+        owner: Address,
+        token_for_sale: Address,
+        token_for_bidding: Address,
+        token_amount_for_sale: u128,
+    ) -> Self {
+        AuctionBuilder {
+            owner,
+            config: AuctionConfig {
+                token_amount_for_sale,
+                token_for_sale,
+                token_for_bidding,
+                reserve_price: 0,
+                min_increment: 1,
+                auction_duration_millis: 24 * 60 * 60 * 1000,
+                auction_format: ENGLISH,
+                price_schedule: None,
+                settlement_mode: FIRST_PRICE,
+                buyout_price: None,
+                sale_token_id: None,
+                start_time_millis: None,
+                royalty_receiver: None,
+                royalty_bps: 0,
+                fee_collector: None,
+                fee_bps: 0,
+                claim_deadline_millis: None,
+                sweep_sink: None,
+                max_bid_history: 0,
+                observer: None,
+                min_unique_bidders: 0,
+                reserve_commitment: None,
+                candle: None,
+                max_extension_hours: 0,
+                relist: None,
+                title: None,
+                description: None,
+                metadata_uri: None,
+                sale_token_interface: None,
+                bidding_token_interface: None,
+                bidding_balance_shortname: None,
+                allow_owner_bids: false,
+                keeper_reward_bps: 0,
+                bid_bond: None,
+                retraction_penalty_bps: None,
+                max_escrow_per_bidder: None,
+                kyc_registry: None,
+                penny: None,
+                sealed_bid_deposit: None,
+                accepted_currencies: vec![],
+                delivery_timeout_millis: None,
+                arbiter: None,
+                bidding_allowance_shortname: None,
+                strict_refunds: false,
+                semi_fungible_sale: false,
+                bundle: vec![],
+                rounds: vec![],
+                recurrence: None,
+                referral_bps: 0,
+                bid_cooldown_millis: None,
+                tie_policy: TiePolicy::LastReceived {},
+                instant_win_multiple: None,
+                recovery_period_millis: None,
+                reserve_oracle: None,
+                allow_same_token: false,
+                end_time_millis: None,
+                membership_gate: None,
+                withholdings: vec![],
+                decimals_shortname: None,
+                cancellable_after_bids: true,
+                setup_window_millis: None,
+            },
+        }
+    }
+
+    pub fn reserve_price(mut self, reserve_price: u128) -> Self {
+        self.config.reserve_price = reserve_price;
+        self
+    }
+
+    pub fn min_increment(mut self, min_increment: u128) -> Self {
+        self.config.min_increment = min_increment;
+        self
+    }
+
+    pub fn duration_millis(mut self, auction_duration_millis: i64) -> Self {
+        self.config.auction_duration_millis = auction_duration_millis;
+        self
+    }
+
+    pub fn format(mut self, auction_format: AuctionFormat) -> Self {
+        self.config.auction_format = auction_format;
+        self
+    }
+
+    /// Adjust any other parameter of the auction.
+    pub fn configure(mut self, adjust: impl FnOnce(&mut AuctionConfig)) -> Self {
+        adjust(&mut self.config);
+        self
+    }
+
+    /// The state of the auction initialized at `block_production_time`, before it is started.
+    pub fn build(self, block_production_time: i64) -> AuctionContractState {
+        new_auction(self.owner, block_production_time, self.config)
+    }
+
+    /// The state of the auction initialized and started at `block_production_time` in the
+    /// contract at `contract_address`, with the tokens for sale escrowed by `start`.
+    pub fn build_started(
+        self,
+        contract_address: Address,
+        block_production_time: i64,
+    ) -> AuctionContractState {
+        let context =
+            ContextBuilder::new(contract_address, self.owner).at_millis(block_production_time);
+        let state = self.build(block_production_time);
+        let (pending_state, _) = start(context.build(), state);
+        let (started_state, _) =
+            start_callback(context.build(), callback_context(true, 1), pending_state);
+        started_state
+    }
+}
+
+/// Assert that `events` consists of exactly the `expected` event groups, in order.
+pub fn assert_event_groups(events: &[EventGroup], expected: Vec<EventGroup>) {
+    assert_eq!(
+        events.len(),
+        expected.len(),
+        "Expected {} event groups, got {}",
+        expected.len(),
+        events.len()
+    );
+    for (index, (event_group, expected)) in events.iter().zip(expected).enumerate() {
+        assert_eq!(*event_group, expected, "Event group {index} differs");
+    }
+}
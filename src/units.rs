@@ -0,0 +1,68 @@
+//! Conversions between whole token units, as people write amounts, and the base units the
+//! contract counts in, given the decimals of a token. The contract caches the decimals of the
+//! token for sale and the bidding token at `start`, so front ends can convert with these.
+
+use std::cmp::Ordering;
+
+/// `10^decimals`, or `None` if it does not fit in a `u128`.
+fn unit(decimals: u8) -> Option<u128> {
+    10u128.checked_pow(u32::from(decimals))
+}
+
+/// The base units of `amount` whole units of a token with `decimals` decimals, or `None` on
+/// overflow.
+pub fn to_base_units(amount: u128, decimals: u8) -> Option<u128> {
+    amount.checked_mul(unit(decimals)?)
+}
+
+/// Rescale `amount` base units of a token with `from_decimals` decimals to a token with
+/// `to_decimals` decimals, rounding down. `None` on overflow.
+pub fn normalize_amount(amount: u128, from_decimals: u8, to_decimals: u8) -> Option<u128> {
+    if amount == 0 {
+        return Some(0);
+    }
+    match from_decimals.cmp(&to_decimals) {
+        Ordering::Less => amount.checked_mul(unit(to_decimals - from_decimals)?),
+        Ordering::Equal => Some(amount),
+        Ordering::Greater => {
+            Some(unit(from_decimals - to_decimals).map_or(0, |unit| amount / unit))
+        }
+    }
+}
+
+/// Compare amounts of two tokens with different decimals by their value in whole units.
+pub fn compare_normalized(
+    amount: u128,
+    decimals: u8,
+    other_amount: u128,
+    other_decimals: u8,
+) -> Ordering {
+    let common_decimals = decimals.max(other_decimals);
+    match (
+        normalize_amount(amount, decimals, common_decimals),
+        normalize_amount(other_amount, other_decimals, common_decimals),
+    ) {
+        (Some(amount), Some(other_amount)) => amount.cmp(&other_amount),
+        // Only the amount with fewer decimals can overflow when rescaled, and it is the larger
+        (None, _) => Ordering::Greater,
+        (_, None) => Ordering::Less,
+    }
+}
+
+/// `amount` base units of a token with `decimals` decimals in whole units, without trailing
+/// zeros, such as `1.5` for 1500 base units of a token with three decimals.
+pub fn format_units(amount: u128, decimals: u8) -> String {
+    let digits = amount.to_string();
+    let decimals = usize::from(decimals);
+    if decimals == 0 {
+        return digits;
+    }
+    let digits = format!("{digits:0>width$}", width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{whole}.{fraction}")
+    }
+}